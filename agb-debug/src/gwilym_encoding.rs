@@ -4,44 +4,154 @@ use thiserror::Error;
 
 const ALPHABET: &[u8] = b"0123456789=ABCDEFGHIJKLMNOPQRSTUVWXYZ_abcdefghijklmnopqrstuvwxyz";
 
+/// The names of the registers in a version 2 crash report, in the order they are stored.
+pub const REGISTER_NAMES: [&str; 3] = ["sp", "lr", "cpsr"];
+
 #[derive(Debug, Error)]
 pub enum GwilymDecodeError {
     #[error("Does not contain version")]
     NoVersion,
-    #[error("Only version 1 is supported")]
+    #[error("Only versions 1 and 2 are supported")]
     WrongVersion,
     #[error("Input must be a multiple of 3 but have {0}")]
     LengthWrong(usize),
+    #[error("Version 2 must have 4 sections but have {0}")]
+    SectionCountWrong(usize),
+    #[error("Expected {expected} values in the {section} section but have {actual}")]
+    ValueCountWrong {
+        section: &'static str,
+        expected: usize,
+        actual: usize,
+    },
 }
 
+/// Decodes the return addresses in a backtrace of either version.
 pub fn gwilym_decode(input: &str) -> Result<GwilymDecodeIter<'_>, GwilymDecodeError> {
-    GwilymDecodeIter::new(input)
+    let (input, version) = split_version(input)?;
+
+    match version {
+        Version::V1 => GwilymDecodeIter::new(input, true),
+        Version::V2 => GwilymDecodeIter::new(sections(input)?[0], true),
+    }
 }
 
-pub struct GwilymDecodeIter<'a> {
-    chunks: ChunksExact<'a, u8>,
+/// Everything in a crash report. Reports using version 1 of the encoding only contain the
+/// frames, so everything else is empty.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CrashReport {
+    /// The return addresses, with the innermost first
+    pub frames: Vec<u32>,
+    /// The registers when the backtrace was taken, in the order of [`REGISTER_NAMES`]. These are
+    /// captured inside agb's panic handler, so the other registers are left out.
+    pub registers: Vec<u32>,
+    /// The words at the top of the panic handler's stack, starting at `sp`
+    pub stack: Vec<u32>,
+    pub panic: Option<PanicLocation>,
 }
 
-impl<'a> GwilymDecodeIter<'a> {
-    fn new(input: &'a str) -> Result<Self, GwilymDecodeError> {
-        let input = input
-            .strip_prefix("https://agbrs.dev/crash#")
-            .unwrap_or(input);
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PanicLocation {
+    pub line: u32,
+    pub column: u32,
+    /// The 32 bit FNV-1a hash of the panic as agb prints it to the log, which includes the
+    /// location and the message
+    pub hash: u32,
+}
 
-        let Some((input, version)) = input.rsplit_once('v') else {
-            return Err(GwilymDecodeError::NoVersion);
-        };
+/// Decodes everything in a crash report of either version.
+pub fn decode_crash_report(input: &str) -> Result<CrashReport, GwilymDecodeError> {
+    let (input, version) = split_version(input)?;
 
-        if version != "1" {
-            return Err(GwilymDecodeError::WrongVersion);
-        }
+    if version == Version::V1 {
+        return Ok(CrashReport {
+            frames: GwilymDecodeIter::new(input, true)?.collect(),
+            ..Default::default()
+        });
+    }
+
+    let [frames, registers, stack, panic] = sections(input)?;
+
+    let registers: Vec<_> = GwilymDecodeIter::new(registers, false)?.collect();
+    check_value_count("registers", REGISTER_NAMES.len(), registers.len())?;
+
+    let panic: Vec<_> = GwilymDecodeIter::new(panic, false)?.collect();
+    check_value_count("panic", 3, panic.len())?;
+
+    Ok(CrashReport {
+        frames: GwilymDecodeIter::new(frames, true)?.collect(),
+        registers,
+        stack: GwilymDecodeIter::new(stack, false)?.collect(),
+        panic: Some(PanicLocation {
+            line: panic[0],
+            column: panic[1],
+            hash: panic[2],
+        }),
+    })
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Version {
+    V1,
+    V2,
+}
+
+fn split_version(input: &str) -> Result<(&str, Version), GwilymDecodeError> {
+    let input = input
+        .strip_prefix("https://agbrs.dev/crash#")
+        .unwrap_or(input);
 
+    let Some((input, version)) = input.rsplit_once('v') else {
+        return Err(GwilymDecodeError::NoVersion);
+    };
+
+    match version {
+        "1" => Ok((input, Version::V1)),
+        "2" => Ok((input, Version::V2)),
+        _ => Err(GwilymDecodeError::WrongVersion),
+    }
+}
+
+// Version 2 has the frames, registers, stack and panic location, separated by `.`
+fn sections(input: &str) -> Result<[&str; 4], GwilymDecodeError> {
+    let sections: Vec<_> = input.split('.').collect();
+    let count = sections.len();
+
+    sections
+        .try_into()
+        .map_err(|_| GwilymDecodeError::SectionCountWrong(count))
+}
+
+fn check_value_count(
+    section: &'static str,
+    expected: usize,
+    actual: usize,
+) -> Result<(), GwilymDecodeError> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(GwilymDecodeError::ValueCountWrong {
+            section,
+            expected,
+            actual,
+        })
+    }
+}
+
+pub struct GwilymDecodeIter<'a> {
+    chunks: ChunksExact<'a, u8>,
+    // whether 16 bit values are the lower half of an address in ROM, as they are in backtraces
+    in_rom: bool,
+}
+
+impl<'a> GwilymDecodeIter<'a> {
+    fn new(input: &'a str, in_rom: bool) -> Result<Self, GwilymDecodeError> {
         if input.len() % 3 != 0 {
             return Err(GwilymDecodeError::LengthWrong(input.len()));
         }
 
         Ok(Self {
             chunks: input.as_bytes().chunks_exact(3),
+            in_rom,
         })
     }
 }
@@ -60,7 +170,11 @@ impl<'a> Iterator for GwilymDecodeIter<'a> {
             return Some(upper_bits | lower_bits);
         }
 
-        Some(value | 0x0800_0000)
+        if self.in_rom {
+            Some(value | 0x0800_0000)
+        } else {
+            Some(value)
+        }
     }
 }
 
@@ -163,4 +277,87 @@ mod test {
 
         Ok(())
     }
+
+    fn encode_value(result: &mut String, value: u32) -> Result<(), Box<dyn std::error::Error>> {
+        if let Ok(value) = u16::try_from(value) {
+            result.push_str(std::str::from_utf8(&encode_16(value))?);
+        } else {
+            result.push_str(std::str::from_utf8(&encode_32(value))?);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_decode_version_2() -> Result<(), Box<dyn std::error::Error>> {
+        let registers = vec![0x0300_7e00, 0x0800_1235, 0x6000_001f];
+        let stack = [0x0300_7e00, 0x0800_1234, 0, 7];
+
+        let mut result = "2QI65Q.".to_string();
+        for &value in registers.iter().chain(&stack) {
+            encode_value(&mut result, value)?;
+            if value == 0x6000_001f {
+                result.push('.');
+            }
+        }
+        result.push('.');
+        for value in [42, 5, 0xdead_beef] {
+            encode_value(&mut result, value)?;
+        }
+        result.push_str("v2");
+
+        assert_eq!(
+            &gwilym_decode(&result)?.collect::<Vec<_>>(),
+            &[0x0800_16d3, 0x0800_315b]
+        );
+
+        assert_eq!(
+            decode_crash_report(&result)?,
+            CrashReport {
+                frames: vec![0x0800_16d3, 0x0800_315b],
+                registers,
+                stack: stack.to_vec(),
+                panic: Some(PanicLocation {
+                    line: 42,
+                    column: 5,
+                    hash: 0xdead_beef,
+                }),
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_decode_version_1_as_a_crash_report() -> Result<(), Box<dyn std::error::Error>> {
+        let report = decode_crash_report("https://agbrs.dev/crash#2QI65Q69306Kv1")?;
+
+        assert_eq!(
+            report.frames,
+            &[0x0800_16d3, 0x0800_315b, 0x0800_3243, 0x0800_0195]
+        );
+        assert!(report.registers.is_empty());
+        assert_eq!(report.panic, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_reject_version_2_with_missing_sections() {
+        assert!(matches!(
+            decode_crash_report("2QI65Q.000v2"),
+            Err(GwilymDecodeError::SectionCountWrong(2))
+        ));
+        assert!(matches!(
+            decode_crash_report("2QI65Q.000..000000000v2"),
+            Err(GwilymDecodeError::ValueCountWrong {
+                section: "registers",
+                ..
+            })
+        ));
+        assert!(matches!(
+            gwilym_decode("2QI65Qv3"),
+            Err(GwilymDecodeError::WrongVersion)
+        ));
+    }
 }
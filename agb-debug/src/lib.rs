@@ -1,9 +1,16 @@
 mod gwilym_encoding;
 mod load_dwarf;
+mod profile;
+mod symbolicate;
 
 use addr2line::gimli;
-pub use gwilym_encoding::{gwilym_decode, GwilymDecodeError};
+pub use gwilym_encoding::{
+    decode_crash_report, gwilym_decode, CrashReport, GwilymDecodeError, PanicLocation,
+    REGISTER_NAMES,
+};
 pub use load_dwarf::{load_dwarf, GimliDwarf, LoadDwarfError};
+pub use profile::{fold_samples, folded_stack, hot_functions, parse_samples};
+pub use symbolicate::{find_addresses, find_crash_trace, parse_sample};
 use thiserror::Error;
 
 pub use addr2line;
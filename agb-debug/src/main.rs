@@ -2,23 +2,39 @@ use std::{
     borrow::Cow,
     error::Error,
     fs::{self, File},
-    io::Read,
-    path::PathBuf,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
     time::SystemTime,
 };
 
-use agb_debug::{address_info, AddressInfo, Location};
+use agb_debug::{address_info, Addr2LineContext, AddressInfo, Location};
 use clap::Parser;
 use colored::Colorize;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// The filename of the elf file
+    /// The filename of the elf file, or of a gba file built with `agb-gbafix --debug`
     elf_path: PathBuf,
 
-    /// The output of agb's dump
-    dump: String,
+    /// The output of agb's dump, or the path to a file of samples when using `--profile`
+    #[arg(required_unless_present = "log")]
+    dump: Option<String>,
+
+    /// Read samples from agb's `interrupt::profiler` and print them in the folded stack format,
+    /// which can be turned into a flamegraph with tools such as `inferno-flamegraph`
+    #[arg(long)]
+    profile: bool,
+
+    /// Read an mGBA log from this file, or from stdin if it is `-`, printing each line with any
+    /// addresses resolved and any crashes symbolicated. Samples from agb's `interrupt::profiler`
+    /// are collected and summarised as a table of the hottest functions once the log ends
+    #[arg(long, conflicts_with_all = ["dump", "profile"])]
+    log: Option<PathBuf>,
+
+    /// When reading a log, also write the profiler samples to this file in the folded stack format
+    #[arg(long, requires = "log")]
+    folded: Option<PathBuf>,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -33,16 +49,148 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let ctx = addr2line::Context::from_dwarf(dwarf)?;
 
-    for (i, address) in agb_debug::gwilym_decode(&cli.dump)?.enumerate() {
-        let infos = address_info(&ctx, address.into())?;
+    if let Some(log) = &cli.log {
+        let reader: Box<dyn BufRead> = if log.as_os_str() == "-" {
+            Box::new(io::stdin().lock())
+        } else {
+            Box::new(BufReader::new(File::open(log)?))
+        };
+
+        return symbolicate_log(&ctx, reader, cli.folded.as_deref(), modification_time);
+    }
+
+    let dump = cli.dump.unwrap_or_default();
+
+    if cli.profile {
+        let samples = agb_debug::parse_samples(&fs::read_to_string(&dump)?);
+        for (stack, count) in agb_debug::fold_samples(&ctx, samples)? {
+            println!("{stack} {count}");
+        }
+
+        return Ok(());
+    }
+
+    print_trace(&ctx, &dump, modification_time)
+}
+
+fn print_trace(
+    ctx: &Addr2LineContext,
+    dump: &str,
+    elf_modification_time: SystemTime,
+) -> Result<(), Box<dyn Error>> {
+    let report = agb_debug::decode_crash_report(dump)?;
+
+    for (i, &address) in report.frames.iter().enumerate() {
+        let infos = address_info(ctx, address.into())?;
         for info in infos {
-            print_address_info(&info, i, modification_time)?;
+            print_address_info(&info, i, elf_modification_time)?;
+        }
+    }
+
+    if let Some(panic) = report.panic {
+        println!();
+        println!(
+            "Panicked at line {} column {} (panic hash {:#010x})",
+            panic.line, panic.column, panic.hash
+        );
+    }
+
+    if !report.registers.is_empty() {
+        println!();
+        println!("{}", "Registers in the panic handler".bold());
+        for (name, &value) in agb_debug::REGISTER_NAMES.iter().zip(&report.registers) {
+            println!(
+                "\t{name:>4}: {}",
+                resolve_addresses(ctx, &format!("{value:#010x}"))?
+            );
+        }
+    }
+
+    if !report.stack.is_empty() {
+        println!();
+        println!("{}", "Stack in the panic handler".bold());
+        for (i, &word) in report.stack.iter().enumerate() {
+            println!(
+                "\tsp+{:<2}: {}",
+                i * 4,
+                resolve_addresses(ctx, &format!("{word:#010x}"))?
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn symbolicate_log(
+    ctx: &Addr2LineContext,
+    log: impl BufRead,
+    folded: Option<&Path>,
+    elf_modification_time: SystemTime,
+) -> Result<(), Box<dyn Error>> {
+    let mut samples = Vec::new();
+
+    for line in log.lines() {
+        let line = line?;
+
+        if let Some(sample) = agb_debug::parse_sample(&line) {
+            samples.push(sample);
+            continue;
+        }
+
+        println!("{}", resolve_addresses(ctx, &line)?);
+
+        if let Some(trace) = agb_debug::find_crash_trace(&line) {
+            print_trace(ctx, trace, elf_modification_time)?;
+        }
+    }
+
+    if samples.is_empty() {
+        return Ok(());
+    }
+
+    println!();
+    println!("{:>8} {:>6}  function", "samples", "%");
+    for (function, count) in agb_debug::hot_functions(ctx, samples.iter().copied())? {
+        let percentage = count as f64 * 100.0 / samples.len() as f64;
+        println!("{count:>8} {percentage:>5.1}%  {function}");
+    }
+
+    if let Some(folded) = folded {
+        let mut file = BufWriter::new(File::create(folded)?);
+        for (stack, count) in agb_debug::fold_samples(ctx, samples)? {
+            writeln!(file, "{stack} {count}")?;
         }
+        file.flush()?;
     }
 
     Ok(())
 }
 
+// Adds the function and location after each address in the line which is in some code
+fn resolve_addresses(ctx: &Addr2LineContext, line: &str) -> Result<String, Box<dyn Error>> {
+    let mut resolved = String::new();
+    let mut copied_up_to = 0;
+
+    for (range, address) in agb_debug::find_addresses(line) {
+        let Some(info) = address_info(ctx, address.into())?.into_iter().next() else {
+            continue;
+        };
+
+        resolved.push_str(&line[copied_up_to..range.end]);
+        copied_up_to = range.end;
+
+        let location = format!(
+            "{}:{}",
+            prettify_path(&info.location.filename),
+            info.location.line
+        );
+        resolved.push_str(&format!(" <{} {}>", info.function, location.green()));
+    }
+
+    resolved.push_str(&line[copied_up_to..]);
+    Ok(resolved)
+}
+
 fn print_address_info(
     info: &AddressInfo,
     index: usize,
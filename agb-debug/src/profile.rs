@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+
+use crate::{address_info, parse_sample, Addr2LineContext, AddressInfo, AddressInfoError};
+
+/// Finds the program counters in the output of agb's `interrupt::profiler`, reading each line with
+/// [`parse_sample`] so any prefix added by the emulator's logging is ignored along with any lines
+/// which aren't samples.
+pub fn parse_samples(output: &str) -> Vec<u32> {
+    output.lines().filter_map(parse_sample).collect()
+}
+
+/// Turns the frames for a single address into a line of the folded stack format, with the
+/// outermost function first. The frames only include functions inlined at the address, since
+/// the profiler only samples the program counter.
+pub fn folded_stack(infos: &[AddressInfo]) -> String {
+    if infos.is_empty() {
+        return "unknown function".to_string();
+    }
+
+    infos
+        .iter()
+        .rev()
+        .map(|info| info.function.replace(';', ":"))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Counts how many samples were in each folded stack, for turning into a flamegraph with tools
+/// such as `inferno-flamegraph`.
+pub fn fold_samples(
+    ctx: &Addr2LineContext,
+    samples: impl IntoIterator<Item = u32>,
+) -> Result<BTreeMap<String, usize>, AddressInfoError> {
+    count_samples_by(ctx, samples, |infos| folded_stack(&infos))
+}
+
+/// Counts how many samples were in each function, with the most sampled function first. Samples
+/// count towards the innermost function, so time spent in functions inlined into another isn't
+/// counted towards the function they were inlined into.
+pub fn hot_functions(
+    ctx: &Addr2LineContext,
+    samples: impl IntoIterator<Item = u32>,
+) -> Result<Vec<(String, usize)>, AddressInfoError> {
+    let counts = count_samples_by(ctx, samples, |infos| {
+        infos
+            .into_iter()
+            .next()
+            .map_or_else(|| "unknown function".to_string(), |info| info.function)
+    })?;
+
+    Ok(sort_by_count(counts))
+}
+
+// Counts the samples under the name `name_of` gives the frames at their address. Each address is
+// only symbolicated once, since the same few addresses tend to be sampled many times.
+fn count_samples_by(
+    ctx: &Addr2LineContext,
+    samples: impl IntoIterator<Item = u32>,
+    name_of: impl Fn(Vec<AddressInfo>) -> String,
+) -> Result<BTreeMap<String, usize>, AddressInfoError> {
+    let mut names_by_address = BTreeMap::new();
+    let mut counts = BTreeMap::new();
+
+    for sample in samples {
+        let name = match names_by_address.get(&sample) {
+            Some(name) => name,
+            None => {
+                let name = name_of(address_info(ctx, sample.into())?);
+                names_by_address.entry(sample).or_insert(name)
+            }
+        };
+
+        *counts.entry(name.clone()).or_default() += 1;
+    }
+
+    Ok(counts)
+}
+
+// The sort is stable, so functions with the same count stay in alphabetical order
+fn sort_by_count(counts: BTreeMap<String, usize>) -> Vec<(String, usize)> {
+    let mut functions: Vec<_> = counts.into_iter().collect();
+    functions.sort_by(|(_, a), (_, b)| b.cmp(a));
+    functions
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Location;
+
+    #[test]
+    fn should_find_samples_after_log_prefixes() {
+        let output = "[INFO] GBA Debug: 0x08001234\n0x0300abcd\nsome other logging\n[INFO] GBA Debug: done 12\n[INFO] GBA Debug: jumped to 0x1234\n";
+
+        assert_eq!(parse_samples(output), &[0x0800_1234, 0x0300_abcd]);
+    }
+
+    fn info(function: &str, is_inline: bool) -> AddressInfo {
+        AddressInfo {
+            location: Location::default(),
+            is_interesting: true,
+            is_inline,
+            function: function.to_string(),
+        }
+    }
+
+    #[test]
+    fn should_put_the_outermost_function_first() {
+        let infos = [
+            info("core::iter::next", false),
+            info("game::update;weird", true),
+            info("game::main", true),
+        ];
+
+        assert_eq!(
+            folded_stack(&infos),
+            "game::main;game::update:weird;core::iter::next"
+        );
+    }
+
+    #[test]
+    fn should_put_the_most_sampled_function_first() {
+        let counts = BTreeMap::from([
+            ("game::draw".to_string(), 3),
+            ("game::main".to_string(), 1),
+            ("game::update".to_string(), 10),
+            ("agb::display".to_string(), 3),
+        ]);
+
+        assert_eq!(
+            sort_by_count(counts),
+            [
+                ("game::update".to_string(), 10),
+                ("agb::display".to_string(), 3),
+                ("game::draw".to_string(), 3),
+                ("game::main".to_string(), 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use std::ops::Range;
+
+use crate::gwilym_decode;
+
+const STACK_TRACE_PREFIX: &str = "Stack trace: ";
+const CRASH_URL: &str = "agbrs.dev/crash#";
+
+/// Finds the encoded stack trace in a line of a log. This is either the `Stack trace: ` message
+/// agb logs when it crashes, which might use a custom website, or a link to agbrs.dev.
+pub fn find_crash_trace(line: &str) -> Option<&str> {
+    let start = line
+        .find(STACK_TRACE_PREFIX)
+        .map(|index| index + STACK_TRACE_PREFIX.len())
+        .or_else(|| line.find(CRASH_URL))?;
+
+    let trace = line[start..].split_whitespace().next()?;
+    let trace = trace.rsplit_once('#').map_or(trace, |(_, trace)| trace);
+
+    (trace.is_ascii() && gwilym_decode(trace).is_ok()).then_some(trace)
+}
+
+/// Reads a single sample from agb's `interrupt::profiler`, which logs each one as a message
+/// containing nothing but the program counter. Anything up to the last `: ` is assumed to be a
+/// prefix added by the emulator.
+pub fn parse_sample(line: &str) -> Option<u32> {
+    let message = line.rsplit_once(": ").map_or(line, |(_, message)| message);
+    let hex = message.trim().strip_prefix("0x")?;
+
+    if hex.len() != 8 {
+        return None;
+    }
+
+    u32::from_str_radix(hex, 16).ok()
+}
+
+/// Finds the hex numbers in a line which could be the address of some code, along with where
+/// they are in the line.
+pub fn find_addresses(line: &str) -> Vec<(Range<usize>, u32)> {
+    let mut addresses = Vec::new();
+    let mut search_from = 0;
+
+    while let Some(offset) = line[search_from..].find("0x") {
+        let start = search_from + offset;
+        let digits = line[start + 2..]
+            .bytes()
+            .take_while(u8::is_ascii_hexdigit)
+            .count();
+        let end = start + 2 + digits;
+        search_from = end;
+
+        // the 0x of something like `a0x10`, which isn't a hex number
+        if line[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_alphanumeric())
+        {
+            continue;
+        }
+
+        if let Ok(address) = u32::from_str_radix(&line[start + 2..end], 16) {
+            if is_code_address(address) {
+                addresses.push((start..end, address));
+            }
+        }
+    }
+
+    addresses
+}
+
+// Code can run from ROM, or from either work ram if it was copied there
+fn is_code_address(address: u32) -> bool {
+    matches!(address >> 24, 0x02 | 0x03 | 0x08 | 0x09)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_find_crash_traces() {
+        assert_eq!(
+            find_crash_trace("[ERROR] GBA Debug: Stack trace: https://agbrs.dev/crash#09rv1"),
+            Some("09rv1")
+        );
+        assert_eq!(
+            find_crash_trace("[ERROR] GBA Debug: Stack trace: https://example.com/trace#09rv1"),
+            Some("09rv1")
+        );
+        assert_eq!(
+            find_crash_trace("[ERROR] GBA Debug: Stack trace: 09rv1"),
+            Some("09rv1")
+        );
+        assert_eq!(
+            find_crash_trace("see https://agbrs.dev/crash#09rv1 for details"),
+            Some("09rv1")
+        );
+        assert_eq!(find_crash_trace("Stack trace: not a trace"), None);
+        assert_eq!(find_crash_trace("[INFO] GBA Debug: hello"), None);
+    }
+
+    #[test]
+    fn should_only_parse_lines_which_are_samples() {
+        assert_eq!(
+            parse_sample("[INFO] GBA Debug: 0x08001234"),
+            Some(0x0800_1234)
+        );
+        assert_eq!(parse_sample("0x03000abc\n"), Some(0x0300_0abc));
+        assert_eq!(parse_sample("[INFO] GBA Debug: pc 0x08001234"), None);
+        assert_eq!(parse_sample("[INFO] GBA Debug: 0x1234"), None);
+    }
+
+    #[test]
+    fn should_find_code_addresses() {
+        let line = "jumping from 0x08001234 to 0x3000010 with 0x10 in r0, ax0x08000000";
+
+        assert_eq!(
+            find_addresses(line),
+            &[(13..23, 0x0800_1234), (27..36, 0x0300_0010)]
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Easing functions for use in animations and tweens.
+//!
+//! Each function takes a progress `t` in the range [0, 1] and returns the eased progress, which
+//! starts at 0 and ends at 1. Some of the functions, such as the elastic ones, overshoot outside of
+//! this range in between. The `_in` variants start slowly, the `_out` variants end slowly and the
+//! `_in_out` variants do both.
+//!
+//! The result is normally used to interpolate between two values with [`Num::lerp`] or
+//! [`Vector2D::lerp`](crate::Vector2D::lerp).
+//!
+//! ```
+//! # use agb_fixnum::*;
+//! let start: Num<i32, 8> = num!(10.);
+//! let end = num!(20.);
+//!
+//! let halfway = start.lerp(end, easing::quad_in(num!(0.5)));
+//! assert_eq!(halfway, num!(12.5));
+//! ```
+
+use crate::{num, Num};
+
+/// Accelerates from zero velocity, following `t^2`.
+#[must_use]
+pub fn quad_in<const N: usize>(t: Num<i32, N>) -> Num<i32, N> {
+    t * t
+}
+
+/// Decelerates to zero velocity, the reverse of [`quad_in`].
+#[must_use]
+pub fn quad_out<const N: usize>(t: Num<i32, N>) -> Num<i32, N> {
+    let one = Num::new(1);
+    one - quad_in(one - t)
+}
+
+/// Accelerates until halfway and then decelerates, combining [`quad_in`] and [`quad_out`].
+#[must_use]
+pub fn quad_in_out<const N: usize>(t: Num<i32, N>) -> Num<i32, N> {
+    in_out(t, quad_in)
+}
+
+/// Accelerates from zero velocity, following `t^3`.
+#[must_use]
+pub fn cubic_in<const N: usize>(t: Num<i32, N>) -> Num<i32, N> {
+    t * t * t
+}
+
+/// Decelerates to zero velocity, the reverse of [`cubic_in`].
+#[must_use]
+pub fn cubic_out<const N: usize>(t: Num<i32, N>) -> Num<i32, N> {
+    let one = Num::new(1);
+    one - cubic_in(one - t)
+}
+
+/// Accelerates until halfway and then decelerates, combining [`cubic_in`] and [`cubic_out`].
+#[must_use]
+pub fn cubic_in_out<const N: usize>(t: Num<i32, N>) -> Num<i32, N> {
+    in_out(t, cubic_in)
+}
+
+/// Accelerates from zero velocity following a quarter of a cosine wave.
+#[must_use]
+pub fn sine_in<const N: usize>(t: Num<i32, N>) -> Num<i32, N> {
+    Num::new(1) - (t / 4).cos()
+}
+
+/// Decelerates to zero velocity following a quarter of a sine wave.
+#[must_use]
+pub fn sine_out<const N: usize>(t: Num<i32, N>) -> Num<i32, N> {
+    (t / 4).sin()
+}
+
+/// Accelerates until halfway and then decelerates following half of a cosine wave.
+#[must_use]
+pub fn sine_in_out<const N: usize>(t: Num<i32, N>) -> Num<i32, N> {
+    (Num::new(1) - (t / 2).cos()) / 2
+}
+
+/// Winds up with an oscillation which grows exponentially before snapping to the end.
+#[must_use]
+pub fn elastic_in<const N: usize>(t: Num<i32, N>) -> Num<i32, N> {
+    let one = Num::new(1);
+    if t <= Num::new(0) || t >= one {
+        return t.clamp(Num::new(0), one);
+    }
+
+    let t = t * 10;
+    -(t - 10).exp2() * ((t - num!(10.75)) / 3).sin()
+}
+
+/// Overshoots the end and then settles with an exponentially decaying oscillation, the reverse of
+/// [`elastic_in`].
+#[must_use]
+pub fn elastic_out<const N: usize>(t: Num<i32, N>) -> Num<i32, N> {
+    let one = Num::new(1);
+    if t <= Num::new(0) || t >= one {
+        return t.clamp(Num::new(0), one);
+    }
+
+    let t = t * 10;
+    (-t).exp2() * ((t - num!(0.75)) / 3).sin() + one
+}
+
+/// Combines [`elastic_in`] and [`elastic_out`], oscillating at both the start and the end.
+#[must_use]
+pub fn elastic_in_out<const N: usize>(t: Num<i32, N>) -> Num<i32, N> {
+    in_out(t, elastic_in)
+}
+
+/// Bounces off the start a few times before accelerating to the end, the reverse of [`bounce_out`].
+#[must_use]
+pub fn bounce_in<const N: usize>(t: Num<i32, N>) -> Num<i32, N> {
+    let one = Num::new(1);
+    one - bounce_out(one - t)
+}
+
+/// Falls to the end and bounces a few times like a dropped ball.
+#[must_use]
+pub fn bounce_out<const N: usize>(t: Num<i32, N>) -> Num<i32, N> {
+    let bounce = |t: Num<i32, N>, centre: Num<i32, N>, height: Num<i32, N>| {
+        let t = t - centre;
+        num!(7.5625) * t * t + height
+    };
+
+    if t < num!(0.36363636) {
+        bounce(t, Num::new(0), Num::new(0))
+    } else if t < num!(0.72727273) {
+        bounce(t, num!(0.54545455), num!(0.75))
+    } else if t < num!(0.90909091) {
+        bounce(t, num!(0.81818182), num!(0.9375))
+    } else {
+        bounce(t, num!(0.95454545), num!(0.984375))
+    }
+}
+
+/// Combines [`bounce_in`] and [`bounce_out`], bouncing at both the start and the end.
+#[must_use]
+pub fn bounce_in_out<const N: usize>(t: Num<i32, N>) -> Num<i32, N> {
+    in_out(t, bounce_in)
+}
+
+// Runs `ease_in` over the first half and its reverse over the second half
+fn in_out<const N: usize>(
+    t: Num<i32, N>,
+    ease_in: impl Fn(Num<i32, N>) -> Num<i32, N>,
+) -> Num<i32, N> {
+    let one = Num::new(1);
+    if t < num!(0.5) {
+        ease_in(t * 2) / 2
+    } else {
+        one - ease_in((one - t) * 2) / 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Easing = fn(Num<i32, 16>) -> Num<i32, 16>;
+    type Reference = fn(f64) -> f64;
+
+    const EASINGS: &[(&str, Easing)] = &[
+        ("quad_in", quad_in),
+        ("quad_out", quad_out),
+        ("quad_in_out", quad_in_out),
+        ("cubic_in", cubic_in),
+        ("cubic_out", cubic_out),
+        ("cubic_in_out", cubic_in_out),
+        ("sine_in", sine_in),
+        ("sine_out", sine_out),
+        ("sine_in_out", sine_in_out),
+        ("elastic_in", elastic_in),
+        ("elastic_out", elastic_out),
+        ("elastic_in_out", elastic_in_out),
+        ("bounce_in", bounce_in),
+        ("bounce_out", bounce_out),
+        ("bounce_in_out", bounce_in_out),
+    ];
+
+    #[test]
+    fn easings_start_at_zero_and_end_at_one() {
+        for (name, easing) in EASINGS {
+            assert!(easing(Num::new(0)).abs() < num!(0.01), "{name} at 0");
+            assert!(
+                (easing(Num::new(1)) - Num::new(1)).abs() < num!(0.01),
+                "{name} at 1"
+            );
+        }
+    }
+
+    #[test]
+    fn in_out_easings_are_halfway_at_half() {
+        for (name, easing) in EASINGS.iter().filter(|(name, _)| name.ends_with("in_out")) {
+            assert!(
+                (easing(num!(0.5)) - num!(0.5)).abs() < num!(0.01),
+                "{name} at 0.5"
+            );
+        }
+    }
+
+    #[test]
+    fn easings_match_reference_implementations() {
+        use core::f64::consts::PI;
+
+        let references: &[(Easing, Reference)] = &[
+            (quad_out, |t| 1. - (1. - t) * (1. - t)),
+            (cubic_in, |t| t * t * t),
+            (sine_in, |t| 1. - (t * PI / 2.).cos()),
+            (sine_in_out, |t| -((PI * t).cos() - 1.) / 2.),
+            (elastic_out, |t| {
+                2f64.powf(-10. * t) * ((t * 10. - 0.75) * (2. * PI) / 3.).sin() + 1.
+            }),
+            (bounce_out, |t| {
+                let (n1, d1) = (7.5625, 2.75);
+                if t < 1. / d1 {
+                    n1 * t * t
+                } else if t < 2. / d1 {
+                    let t = t - 1.5 / d1;
+                    n1 * t * t + 0.75
+                } else if t < 2.5 / d1 {
+                    let t = t - 2.25 / d1;
+                    n1 * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / d1;
+                    n1 * t * t + 0.984375
+                }
+            }),
+        ];
+
+        for (easing, reference) in references {
+            for i in 0..=64 {
+                let t = Num::new(i) / 64;
+                let expected = reference(i as f64 / 64.);
+                let actual = easing(t);
+
+                assert!(
+                    (actual - Num::from_f64(expected)).abs() < num!(0.01),
+                    "at {t}: expected {expected}, got {actual}"
+                );
+            }
+        }
+    }
+}
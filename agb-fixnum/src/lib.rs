@@ -1,6 +1,13 @@
 #![no_std]
 #![deny(missing_docs)]
 //! Fixed point number implementation for representing non integers efficiently.
+//!
+//! # Features
+//!
+//! * `overflow-checks`: By default, multiplication and division of [`Num`]s wrap on overflow even in debug
+//!   builds, since the intermediate results don't fit in the underlying integer. Enabling this feature
+//!   makes them panic on overflow in builds with debug assertions enabled, like integer arithmetic does.
+//!   Use the `checked_*`, `saturating_*` and `wrapping_*` methods where overflow is expected.
 
 use core::{
     cmp::{Eq, Ord, PartialEq, PartialOrd},
@@ -13,6 +20,8 @@ use core::{
 };
 use num_traits::Signed;
 
+pub mod easing;
+
 #[doc(hidden)]
 /// Used internally by the [num!] macro which should be used instead.
 pub use agb_macros::num as num_inner;
@@ -50,6 +59,13 @@ pub trait FixedWidthUnsignedInteger:
     + Debug
     + Display
     + num_traits::Num
+    + num_traits::Bounded
+    + num_traits::CheckedAdd
+    + num_traits::CheckedSub
+    + num_traits::SaturatingAdd
+    + num_traits::SaturatingSub
+    + num_traits::WrappingAdd
+    + num_traits::WrappingSub
     + Not<Output = Self>
 {
     /// Returns the representation of ten
@@ -58,6 +74,10 @@ pub trait FixedWidthUnsignedInteger:
     fn from_as_i32(v: i32) -> Self;
     /// Returns (a * b) >> N
     fn upcast_multiply(a: Self, b: Self, n: usize) -> Self;
+    /// Returns (a * b) >> N, or None if the result doesn't fit
+    fn checked_upcast_multiply(a: Self, b: Self, n: usize) -> Option<Self>;
+    /// Returns (a << N) / b, or None if b is zero or the result doesn't fit
+    fn checked_upcast_divide(a: Self, b: Self, n: usize) -> Option<Self>;
 }
 
 /// Trait for an integer that includes negation
@@ -67,6 +87,9 @@ impl<I: FixedWidthUnsignedInteger + Signed> FixedWidthSignedInteger for I {}
 
 macro_rules! fixed_width_unsigned_integer_impl {
     ($T: ty, $Upcast: ident) => {
+        fixed_width_unsigned_integer_impl!($T, $Upcast, $Upcast);
+    };
+    ($T: ty, $Upcast: ident, $MultiplyImpl: ident) => {
         impl FixedWidthUnsignedInteger for $T {
             #[inline(always)]
             fn ten() -> Self {
@@ -77,7 +100,20 @@ macro_rules! fixed_width_unsigned_integer_impl {
                 v as $T
             }
 
-            upcast_multiply_impl!($T, $Upcast);
+            upcast_multiply_impl!($T, $MultiplyImpl);
+
+            #[inline]
+            fn checked_upcast_multiply(a: Self, b: Self, n: usize) -> Option<Self> {
+                (((a as $Upcast) * (b as $Upcast)) >> n).try_into().ok()
+            }
+
+            #[inline]
+            fn checked_upcast_divide(a: Self, b: Self, n: usize) -> Option<Self> {
+                ((a as $Upcast) << n)
+                    .checked_div(b as $Upcast)?
+                    .try_into()
+                    .ok()
+            }
         }
     };
 }
@@ -117,8 +153,9 @@ fixed_width_unsigned_integer_impl!(u8, u32);
 fixed_width_unsigned_integer_impl!(i16, i32);
 fixed_width_unsigned_integer_impl!(u16, u32);
 
-fixed_width_unsigned_integer_impl!(i32, optimised_64_bit);
-fixed_width_unsigned_integer_impl!(u32, optimised_64_bit);
+fixed_width_unsigned_integer_impl!(i32, i64, optimised_64_bit);
+fixed_width_unsigned_integer_impl!(u32, u64, optimised_64_bit);
+fixed_width_unsigned_integer_impl!(i64, i128);
 
 /// A fixed point number represented using `I` with `N` bits of fractional precision
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -225,7 +262,13 @@ where
 {
     type Output = Self;
     fn mul(self, rhs: Num<I, N>) -> Self::Output {
-        Num(I::upcast_multiply(self.0, rhs.0, N))
+        if cfg!(all(feature = "overflow-checks", debug_assertions)) {
+            return self
+                .checked_mul(rhs)
+                .expect("attempt to multiply with overflow");
+        }
+
+        self.wrapping_mul(rhs)
     }
 }
 
@@ -255,7 +298,13 @@ where
 {
     type Output = Self;
     fn div(self, rhs: Num<I, N>) -> Self::Output {
-        Num((self.0 << N) / rhs.0)
+        let shifted = self.0 << N;
+        // panic rather than using checked_div, which would give a different answer to release builds
+        if cfg!(all(feature = "overflow-checks", debug_assertions)) {
+            assert!(shifted >> N == self.0, "attempt to divide with overflow");
+        }
+
+        Num(shifted / rhs.0)
     }
 }
 
@@ -354,6 +403,155 @@ impl<I: FixedWidthUnsignedInteger, const N: usize> Num<I, N> {
         }
     }
 
+    /// Checked addition, returning `None` if the result would overflow
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let a: Num<i16, 8> = num!(100.);
+    /// assert_eq!(a.checked_add(num!(20.)), Some(num!(120.)));
+    /// assert_eq!(a.checked_add(num!(50.)), None);
+    /// ```
+    #[must_use]
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(&rhs.0).map(Num)
+    }
+
+    /// Checked subtraction, returning `None` if the result would overflow
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let a: Num<u8, 4> = num!(1.5);
+    /// assert_eq!(a.checked_sub(num!(1.)), Some(num!(0.5)));
+    /// assert_eq!(a.checked_sub(num!(2.)), None);
+    /// ```
+    #[must_use]
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(&rhs.0).map(Num)
+    }
+
+    /// Checked multiplication, returning `None` if the result would overflow
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let a: Num<i32, 16> = num!(200.);
+    /// assert_eq!(a.checked_mul(num!(100.)), Some(num!(20000.)));
+    /// assert_eq!(a.checked_mul(a), None);
+    /// ```
+    #[must_use]
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        I::checked_upcast_multiply(self.0, rhs.0, N).map(Num)
+    }
+
+    /// Checked division, returning `None` if `rhs` is zero or the result would overflow.
+    ///
+    /// Unlike the [`Div`] implementation, the intermediate calculation is done at double width so this
+    /// works for any numerator, not just small ones.
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let a: Num<i32, 16> = num!(20000.);
+    /// assert_eq!(a.checked_div(num!(100.)), Some(num!(200.)));
+    /// assert_eq!(a.checked_div(num!(0.001)), None);
+    /// assert_eq!(a.checked_div(num!(0.)), None);
+    /// ```
+    #[must_use]
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        I::checked_upcast_divide(self.0, rhs.0, N).map(Num)
+    }
+
+    // The result to saturate to when a calculation overflows in the given direction
+    fn saturated(negative: bool) -> Self {
+        if negative {
+            Num(I::min_value())
+        } else {
+            Num(I::max_value())
+        }
+    }
+
+    /// Saturating addition, clamping to the largest or smallest representable number on overflow
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let a: Num<i16, 8> = num!(100.);
+    /// assert_eq!(a.saturating_add(num!(50.)), Num::from_raw(i16::MAX));
+    /// ```
+    #[must_use]
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Num(self.0.saturating_add(&rhs.0))
+    }
+
+    /// Saturating subtraction, clamping to the largest or smallest representable number on overflow
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let a: Num<u8, 4> = num!(1.5);
+    /// assert_eq!(a.saturating_sub(num!(2.)), num!(0.));
+    /// ```
+    #[must_use]
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Num(self.0.saturating_sub(&rhs.0))
+    }
+
+    /// Saturating multiplication, clamping to the largest or smallest representable number on overflow
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let a: Num<i32, 16> = num!(200.);
+    /// assert_eq!(a.saturating_mul(a), Num::from_raw(i32::MAX));
+    /// assert_eq!(a.saturating_mul(-a), Num::from_raw(i32::MIN));
+    /// ```
+    #[must_use]
+    pub fn saturating_mul(self, rhs: Self) -> Self {
+        self.checked_mul(rhs)
+            .unwrap_or_else(|| Self::saturated((self.0 < I::zero()) != (rhs.0 < I::zero())))
+    }
+
+    /// Saturating division, clamping to the largest or smallest representable number on overflow.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rhs` is zero.
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let a: Num<i32, 16> = num!(20000.);
+    /// assert_eq!(a.saturating_div(num!(0.001)), Num::from_raw(i32::MAX));
+    /// assert_eq!(a.saturating_div(num!(-0.001)), Num::from_raw(i32::MIN));
+    /// ```
+    #[must_use]
+    pub fn saturating_div(self, rhs: Self) -> Self {
+        assert!(rhs.0 != I::zero(), "attempt to divide by zero");
+
+        self.checked_div(rhs)
+            .unwrap_or_else(|| Self::saturated((self.0 < I::zero()) != (rhs.0 < I::zero())))
+    }
+
+    /// Wrapping addition, wrapping around at the boundary of the underlying integer
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let a: Num<u8, 4> = num!(15.);
+    /// assert_eq!(a.wrapping_add(num!(2.)), num!(1.));
+    /// ```
+    #[must_use]
+    pub fn wrapping_add(self, rhs: Self) -> Self {
+        Num(self.0.wrapping_add(&rhs.0))
+    }
+
+    /// Wrapping subtraction, wrapping around at the boundary of the underlying integer
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let a: Num<u8, 4> = num!(1.);
+    /// assert_eq!(a.wrapping_sub(num!(2.)), num!(15.));
+    /// ```
+    #[must_use]
+    pub fn wrapping_sub(self, rhs: Self) -> Self {
+        Num(self.0.wrapping_sub(&rhs.0))
+    }
+
+    /// Wrapping multiplication, wrapping around at the boundary of the underlying integer.
+    /// This is what the [`Mul`] implementation does unless the `overflow-checks` feature is enabled.
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let a: Num<u8, 4> = num!(4.);
+    /// assert_eq!(a.wrapping_mul(num!(5.)), num!(4.));
+    /// ```
+    #[must_use]
+    pub fn wrapping_mul(self, rhs: Self) -> Self {
+        Num(I::upcast_multiply(self.0, rhs.0, N))
+    }
+
     /// A bit for bit conversion from a number to a fixed num
     pub const fn from_raw(n: I) -> Self {
         Num(n)
@@ -488,6 +686,229 @@ impl<const N: usize> Num<i32, N> {
         }
         Self(c << (N / 2))
     }
+
+    /// Multiplies two numbers at double width, so the result can never overflow. Use
+    /// [`try_change_base`](Num::try_change_base) to convert the result back once you know it
+    /// will fit.
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let a: Num<i32, 16> = num!(300.);
+    /// let squared: Num<i64, 16> = a.widening_mul(a);
+    ///
+    /// assert_eq!(squared, Num::new(90000));
+    /// assert_eq!(squared.try_change_base::<i32, 8>(), Some(num!(90000.)));
+    /// assert_eq!(squared.try_change_base::<i32, 16>(), None);
+    /// ```
+    #[must_use]
+    pub fn widening_mul(self, rhs: Self) -> Num<i64, N> {
+        Num((self.0 as i64 * rhs.0 as i64) >> N)
+    }
+
+    // Division which can't overflow in the intermediate shift, used by the functions below
+    // where the operands can get large relative to the fractional precision.
+    fn wide_div(self, rhs: Self) -> Self {
+        Self((((self.0 as i64) << N) / rhs.0 as i64) as i32)
+    }
+
+    /// Calculates the tangent of a number with domain of [0, 1], see [Num::sin] and [Num::cos].
+    ///
+    /// # Panics
+    ///
+    /// Panics where the cosine of the angle is zero, such as at 0.25 and 0.75.
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let n: Num<i32, 16> = num!(0.125); // pi / 4 radians
+    /// assert!((n.tan() - num!(1.)).abs() < num!(0.01));
+    /// ```
+    #[must_use]
+    pub fn tan(self) -> Self {
+        self.sin().wide_div(self.cos())
+    }
+
+    /// Calculates the four quadrant arctangent of `self` (the y coordinate) and `x`, returning
+    /// the angle in the range (-0.5, 0.5] where 1 is a full rotation, matching the domain of
+    /// [Num::sin] and [Num::cos].
+    ///
+    /// Returns 0 if both `self` and `x` are 0.
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let y: Num<i32, 16> = num!(1.);
+    /// let x: Num<i32, 16> = num!(-1.);
+    /// assert!((y.atan2(x) - num!(0.375)).abs() < num!(0.001));
+    /// ```
+    #[must_use]
+    pub fn atan2(self, x: Self) -> Self {
+        let y = self;
+        let zero = Self::new(0);
+
+        if y == zero && x == zero {
+            return zero;
+        }
+
+        let (abs_x, abs_y) = (x.abs(), y.abs());
+        let (min, max) = if abs_y > abs_x {
+            (abs_x, abs_y)
+        } else {
+            (abs_y, abs_x)
+        };
+
+        // polynomial approximation of atan(z) / 2pi for z in [0, 1]
+        let z = min.wide_div(max);
+        let z2 = z * z;
+        let mut angle = z
+            * (num!(0.15913158)
+                + z2 * (num!(-0.05257563)
+                    + z2 * (num!(0.02867048) + z2 * (num!(-0.01354939) + z2 * num!(0.00331604)))));
+
+        if abs_y > abs_x {
+            angle = num!(0.25) - angle;
+        }
+        if x < zero {
+            angle = num!(0.5) - angle;
+        }
+        if y < zero {
+            angle = -angle;
+        }
+
+        angle
+    }
+
+    /// Calculates the arctangent of a number, returning the angle in the range (-0.25, 0.25)
+    /// where 1 is a full rotation.
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let n: Num<i32, 16> = num!(1.);
+    /// assert!((n.atan() - num!(0.125)).abs() < num!(0.001));
+    /// ```
+    #[must_use]
+    pub fn atan(self) -> Self {
+        self.atan2(Self::new(1))
+    }
+
+    /// Calculates the arcsine of a number in the range [-1, 1], returning the angle in the range
+    /// [-0.25, 0.25] where 1 is a full rotation. Uses [Num::sqrt], so N must be even.
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let n: Num<i32, 16> = num!(0.5);
+    /// assert!((n.asin() - num!(0.0833333)).abs() < num!(0.001));
+    /// ```
+    #[must_use]
+    pub fn asin(self) -> Self {
+        let one = Self::new(1);
+        assert!(self.abs() <= one, "asin is only valid between -1 and 1");
+
+        self.atan2((one - self * self).sqrt())
+    }
+
+    /// Calculates the arccosine of a number in the range [-1, 1], returning the angle in the range
+    /// [0, 0.5] where 1 is a full rotation. Uses [Num::sqrt], so N must be even.
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let n: Num<i32, 16> = num!(0.5);
+    /// assert!((n.acos() - num!(0.1666667)).abs() < num!(0.001));
+    /// ```
+    #[must_use]
+    pub fn acos(self) -> Self {
+        let one = Self::new(1);
+        assert!(self.abs() <= one, "acos is only valid between -1 and 1");
+
+        (one - self * self).sqrt().atan2(self)
+    }
+
+    /// Calculates e raised to the power of the number, saturating to the largest number if the
+    /// result is too large to be represented.
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let n: Num<i32, 16> = num!(1.);
+    /// assert!((n.exp() - num!(2.7182818)).abs() < num!(0.001));
+    /// ```
+    #[must_use]
+    pub fn exp(self) -> Self {
+        // e^x = 2^k * e^r where x = k ln(2) + r and |r| <= ln(2) / 2
+        // worked out at double width, since x / ln(2) is larger than x and might not fit
+        let ln_2: Self = num!(0.69314718);
+        let k = (((self.0 as i64) << N) / ln_2.0 as i64 + (1 << N) / 2) >> N;
+        let r = Self((self.0 as i64 - ln_2.0 as i64 * k) as i32);
+
+        let mut term = Self::new(1);
+        let mut result = term;
+        for i in 1..=6 {
+            term = term * r / i;
+            result += term;
+        }
+
+        if k >= 0 {
+            // the result is positive, so the shift fits if it leaves the sign bit clear
+            if k < i64::from(result.0.leading_zeros()) {
+                Self(result.0 << k)
+            } else {
+                Self(i32::MAX)
+            }
+        } else if k > -32 {
+            Self(result.0 >> -k)
+        } else {
+            Self::new(0)
+        }
+    }
+
+    /// Calculates 2 raised to the power of the number. See [Num::exp].
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let n: Num<i32, 16> = num!(-1.5);
+    /// assert!((n.exp2() - num!(0.35355339)).abs() < num!(0.001));
+    /// ```
+    #[must_use]
+    pub fn exp2(self) -> Self {
+        (self * num!(0.69314718)).exp()
+    }
+
+    /// Calculates the natural logarithm of the number.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number is not positive.
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let n: Num<i32, 16> = num!(10.);
+    /// assert!((n.ln() - num!(2.3025851)).abs() < num!(0.001));
+    /// ```
+    #[must_use]
+    pub fn ln(self) -> Self {
+        assert!(self.0 > 0, "ln is only valid for positive numbers");
+
+        // ln(x) = k ln(2) + ln(m) where x = 2^k * m and m is in [1, 2)
+        let k = (31 - self.0.leading_zeros() as i32) - N as i32;
+        let m = if k >= 0 {
+            Self(self.0 >> k)
+        } else {
+            Self(self.0 << -k)
+        };
+
+        // ln(m) = 2 * (s + s^3 / 3 + s^5 / 5 + ...) where s = (m - 1) / (m + 1)
+        let one = Self::new(1);
+        let s = (m - one).wide_div(m + one);
+        let s2 = s * s;
+
+        let mut power = s;
+        let mut sum = s;
+        for i in [3, 5, 7, 9] {
+            power *= s2;
+            sum += power / i;
+        }
+
+        sum * 2 + num!(0.69314718) * k
+    }
+
+    /// Calculates the base 2 logarithm of the number. See [Num::ln].
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let n: Num<i32, 16> = num!(8.);
+    /// assert!((n.log2() - num!(3.)).abs() < num!(0.001));
+    /// ```
+    #[must_use]
+    pub fn log2(self) -> Self {
+        self.ln() * num!(1.44269504)
+    }
 }
 
 impl<I: FixedWidthSignedInteger, const N: usize> Num<I, N> {
@@ -548,6 +969,19 @@ impl<I: FixedWidthSignedInteger, const N: usize> Num<I, N> {
         let four: I = 4.into();
         (self - one / four).cos()
     }
+
+    /// Linearly interpolates between `self` and `other`, where a `t` of 0 gives `self` and
+    /// a `t` of 1 gives `other`. Values of `t` outside of [0, 1] extrapolate.
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let a: Num<i32, 8> = num!(2.);
+    /// let b: Num<i32, 8> = num!(4.);
+    /// assert_eq!(a.lerp(b, num!(0.25)), num!(2.5));
+    /// ```
+    #[must_use]
+    pub fn lerp(self, other: Self, t: Self) -> Self {
+        self + (other - self) * t
+    }
 }
 
 impl<I: FixedWidthSignedInteger, const N: usize> num_traits::sign::Signed for Num<I, N> {
@@ -862,6 +1296,17 @@ impl<const N: usize> Vector2D<Num<i32, N>> {
     pub fn fast_normalise(self) -> Self {
         self / self.fast_magnitude()
     }
+
+    #[must_use]
+    /// Calculates the angle of the vector from the positive x axis in the range (-0.5, 0.5], see [Num::atan2].
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let v: Vector2D<Num<i32, 16>> = (num!(0.), num!(2.)).into();
+    /// assert!((v.angle() - num!(0.25)).abs() < num!(0.001));
+    /// ```
+    pub fn angle(self) -> Num<i32, N> {
+        self.y.atan2(self.x)
+    }
 }
 
 impl<T: Number, P: Number + Into<T>> From<(P, P)> for Vector2D<T> {
@@ -896,6 +1341,38 @@ impl<I: FixedWidthSignedInteger, const N: usize> Vector2D<Num<I, N>> {
             y: angle.sin(),
         }
     }
+
+    #[must_use]
+    /// Rotates the vector anticlockwise (with y pointing up) by the given angle, where the domain of
+    /// the angle is [0, 1], see [Num::cos] and [Num::sin].
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let v: Vector2D<Num<i32, 8>> = (num!(1.), num!(0.)).into();
+    /// assert_eq!(v.rotate(num!(0.25)), (num!(0.), num!(1.)).into());
+    /// ```
+    pub fn rotate(self, angle: Num<I, N>) -> Self {
+        let (cos, sin) = (angle.cos(), angle.sin());
+
+        Vector2D {
+            x: self.x * cos - self.y * sin,
+            y: self.x * sin + self.y * cos,
+        }
+    }
+
+    #[must_use]
+    /// Linearly interpolates between `self` and `other`, see [Num::lerp].
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let a: Vector2D<Num<i32, 8>> = (num!(0.), num!(2.)).into();
+    /// let b: Vector2D<Num<i32, 8>> = (num!(4.), num!(0.)).into();
+    /// assert_eq!(a.lerp(b, num!(0.5)), (num!(2.), num!(1.)).into());
+    /// ```
+    pub fn lerp(self, other: Self, t: Num<I, N>) -> Self {
+        Vector2D {
+            x: self.x.lerp(other.x, t),
+            y: self.y.lerp(other.y, t),
+        }
+    }
 }
 
 impl<I: FixedWidthUnsignedInteger, const N: usize> From<Vector2D<I>> for Vector2D<Num<I, N>> {
@@ -1126,6 +1603,31 @@ impl<T: Number> Vector2D<T> {
             y: self.x,
         }
     }
+
+    #[must_use]
+    /// Calculates the dot product of the two vectors
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let v1 = Vector2D::new(2, 3);
+    /// let v2 = Vector2D::new(4, 5);
+    /// assert_eq!(v1.dot(v2), 23);
+    /// ```
+    pub fn dot(self, other: Self) -> T {
+        self.x * other.x + self.y * other.y
+    }
+
+    #[must_use]
+    /// Calculates the z component of the cross product of the two vectors as if they were in 3D
+    /// space. This is positive if `other` is anticlockwise of `self` (with y pointing up).
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let v1 = Vector2D::new(2, 3);
+    /// let v2 = Vector2D::new(4, 5);
+    /// assert_eq!(v1.cross(v2), -2);
+    /// ```
+    pub fn cross(self, other: Self) -> T {
+        self.x * other.y - self.y * other.x
+    }
 }
 
 impl<T: Number + Neg<Output = T>> Neg for Vector2D<T> {
@@ -1271,6 +1773,179 @@ mod tests {
         );
     }
 
+    #[test]
+    fn check_atan2_accuracy() {
+        for i in -32..32 {
+            for j in -32..32 {
+                if i == 0 && j == 0 {
+                    continue;
+                }
+
+                let y: Num<i32, 16> = Num::new(i) / 4;
+                let x: Num<i32, 16> = Num::new(j) / 4;
+
+                let expected = (i as f64).atan2(j as f64) / (2. * core::f64::consts::PI);
+                let actual = y.atan2(x);
+
+                assert!(
+                    (actual - Num::from_f64(expected)).abs() < num!(0.0002),
+                    "atan2({y}, {x}): expected {expected}, got {actual}"
+                );
+            }
+        }
+
+        let zero: Num<i32, 16> = num!(0.);
+        assert_eq!(zero.atan2(zero), zero);
+        assert_eq!(zero.atan2(num!(-1.)), num!(0.5));
+    }
+
+    #[test]
+    fn check_inverse_trig_round_trips() {
+        for i in -16..=16 {
+            let x: Num<i32, 16> = Num::new(i) / 16;
+
+            assert!((x.asin().sin() - x).abs() < num!(0.005), "asin({x})");
+            assert!((x.acos().cos() - x).abs() < num!(0.005), "acos({x})");
+            assert!((x.atan().tan() - x).abs() < num!(0.005), "atan({x})");
+        }
+    }
+
+    #[test]
+    fn check_exp_and_ln_accuracy() {
+        for i in -40..40 {
+            let x: Num<i32, 16> = Num::new(i) / 8;
+            let expected = (x.to_raw() as f64 / (1 << 16) as f64).exp();
+
+            assert!(
+                (x.exp() - Num::from_f64(expected)).abs()
+                    < Num::from_f64(expected / 1000.).max(num!(0.0001)),
+                "exp({x}): expected {expected}, got {}",
+                x.exp()
+            );
+        }
+
+        let large: Num<i32, 16> = num!(11.);
+        assert_eq!(large.exp(), Num::from_raw(i32::MAX));
+        assert_eq!(
+            Num::<i32, 16>::from_raw(i32::MAX).exp(),
+            Num::from_raw(i32::MAX)
+        );
+        assert_eq!(Num::<i32, 8>::new(20).exp(), Num::from_raw(i32::MAX));
+        assert_eq!(Num::<i32, 16>::from_raw(i32::MIN).exp(), Num::new(0));
+
+        for i in 1..200 {
+            let x: Num<i32, 16> = Num::new(i) / 8;
+            let expected = (i as f64 / 8.).ln();
+
+            assert!(
+                (x.ln() - Num::from_f64(expected)).abs() < num!(0.001),
+                "ln({x}): expected {expected}, got {}",
+                x.ln()
+            );
+        }
+
+        let n: Num<i32, 16> = num!(3.);
+        assert_eq!(n.exp2(), num!(8.));
+        assert!((n.exp2().log2() - n).abs() < num!(0.001));
+    }
+
+    #[test]
+    fn check_lerp() {
+        let a: Num<i32, 8> = num!(-3.);
+        let b: Num<i32, 8> = num!(5.);
+
+        assert_eq!(a.lerp(b, num!(0.)), a);
+        assert_eq!(a.lerp(b, num!(1.)), b);
+        assert_eq!(a.lerp(b, num!(0.5)), num!(1.));
+        assert_eq!(a.lerp(b, num!(1.5)), num!(9.));
+    }
+
+    #[test]
+    fn check_vector_products_and_rotation() {
+        let v: Vector2D<Num<i32, 16>> = (num!(3.), num!(4.)).into();
+        let w: Vector2D<Num<i32, 16>> = (num!(-4.), num!(3.)).into();
+
+        assert_eq!(v.dot(w), num!(0.));
+        assert_eq!(v.cross(w), num!(25.));
+        assert_eq!(w.cross(v), num!(-25.));
+
+        let rotated = v.rotate(num!(0.25));
+        assert!((rotated - w).magnitude() < num!(0.01));
+        assert!((w.angle() - v.angle() - num!(0.25)).abs() < num!(0.001));
+    }
+
+    #[test]
+    fn checked_arithmetic_matches_wide_arithmetic() {
+        for a in (i16::MIN..=i16::MAX).step_by(97) {
+            for b in (i16::MIN..=i16::MAX).step_by(89) {
+                let (x, y) = (Num::<i16, 8>::from_raw(a), Num::<i16, 8>::from_raw(b));
+
+                let product = (a as i32 * b as i32) >> 8;
+                assert_eq!(
+                    x.checked_mul(y).map(Num::to_raw),
+                    i16::try_from(product).ok(),
+                    "{x} * {y}"
+                );
+
+                let quotient = (b != 0).then(|| ((a as i32) << 8) / b as i32);
+                assert_eq!(
+                    x.checked_div(y).map(Num::to_raw),
+                    quotient.and_then(|q| i16::try_from(q).ok()),
+                    "{x} / {y}"
+                );
+
+                if let Some(product) = x.checked_mul(y) {
+                    assert_eq!(x.saturating_mul(y), product);
+                    assert_eq!(x.wrapping_mul(y), product);
+                } else if product > 0 {
+                    assert_eq!(x.saturating_mul(y), Num::from_raw(i16::MAX));
+                } else {
+                    assert_eq!(x.saturating_mul(y), Num::from_raw(i16::MIN));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn checked_division_handles_large_numerators() {
+        let a: Num<i32, 16> = num!(1000.);
+
+        assert_eq!(a.checked_div(num!(4.)), Some(num!(250.)));
+        assert_eq!(
+            Num::<i32, 16>::from_raw(i32::MIN).checked_div(num!(-1.)),
+            None
+        );
+    }
+
+    #[test]
+    fn widening_mul_does_not_overflow() {
+        let a: Num<i32, 16> = num!(-30000.);
+        let b: Num<i32, 16> = num!(20000.5);
+
+        assert_eq!(a.widening_mul(b), Num::<i64, 16>::new(-600015000));
+    }
+
+    #[test]
+    #[cfg_attr(
+        all(feature = "overflow-checks", debug_assertions),
+        should_panic = "attempt to multiply with overflow"
+    )]
+    fn overflowing_multiplication_panics_with_overflow_checks() {
+        let a: Num<i32, 16> = num!(200.);
+        let _ = a * a;
+    }
+
+    #[test]
+    #[cfg_attr(
+        all(feature = "overflow-checks", debug_assertions),
+        should_panic = "attempt to divide with overflow"
+    )]
+    fn overflowing_division_panics_with_overflow_checks() {
+        // the result fits, but shifting the numerator before dividing doesn't
+        let a: Num<i32, 16> = num!(20000.);
+        let _ = a / num!(100.);
+    }
+
     #[test]
     fn check_16_bit_precision_i32() {
         let a: Num<i32, 16> = num!(1.923);
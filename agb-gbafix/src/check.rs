@@ -0,0 +1,219 @@
+use std::fmt;
+
+use anyhow::{bail, Result};
+
+use crate::{GbaHeader, GBA_HEADER_SIZE, NINTENDO_LOGO};
+
+const LOGO_OFFSET: usize = 0x04;
+const GAME_CODE_OFFSET: usize = 0xAC;
+const FIXED_VALUE_OFFSET: usize = 0xB2;
+const CHECKSUM_OFFSET: usize = 0xBD;
+
+/// Something wrong with a ROM which would stop it from booting on hardware, or which tools might
+/// complain about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// The file is too small to even contain the header.
+    TooSmall(usize),
+    /// The first instruction isn't a branch over the header.
+    StartCode([u8; 4]),
+    /// The Nintendo logo doesn't match, so the BIOS will refuse to boot it.
+    NintendoLogo,
+    /// The byte at 0xB2 must be 0x96.
+    FixedValue(u8),
+    /// The game code should be 4 uppercase letters or digits.
+    GameCode([u8; 4]),
+    /// The header checksum doesn't match, so the BIOS will refuse to boot it.
+    Checksum { expected: u8, found: u8 },
+    /// The size isn't a power of 2, which some flash carts need.
+    NotPadded(usize),
+}
+
+impl Problem {
+    /// Whether [`repair_gba_file`] can fix this problem.
+    pub fn is_repairable(&self) -> bool {
+        match self {
+            Problem::TooSmall(_) | Problem::StartCode(_) => false,
+            Problem::GameCode(game_code) => repaired_game_code(*game_code).is_some(),
+            Problem::NintendoLogo
+            | Problem::FixedValue(_)
+            | Problem::Checksum { .. }
+            | Problem::NotPadded(_) => true,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::TooSmall(size) => write!(
+                f,
+                "The file is {size} bytes, which is too small to contain the {GBA_HEADER_SIZE} byte header"
+            ),
+            Problem::StartCode(start_code) => write!(
+                f,
+                "The start code {start_code:02x?} is not an ARM branch instruction"
+            ),
+            Problem::NintendoLogo => write!(f, "The Nintendo logo is incorrect"),
+            Problem::FixedValue(value) => {
+                write!(f, "The fixed value at 0xB2 is {value:#04x} rather than 0x96")
+            }
+            Problem::GameCode(game_code) => write!(
+                f,
+                "The game code {:?} should be 4 uppercase letters or digits",
+                String::from_utf8_lossy(game_code)
+            ),
+            Problem::Checksum { expected, found } => write!(
+                f,
+                "The header checksum is {found:#04x} but should be {expected:#04x}"
+            ),
+            Problem::NotPadded(size) => write!(
+                f,
+                "The file is {size} bytes, which isn't a power of 2"
+            ),
+        }
+    }
+}
+
+/// Finds everything wrong with the header of a ROM. Only checks the size is a power of 2 if
+/// `check_padding` is set, since agb doesn't pad ROMs by default.
+pub fn check_gba_file(rom: &[u8], check_padding: bool) -> Vec<Problem> {
+    if rom.len() < GBA_HEADER_SIZE {
+        return vec![Problem::TooSmall(rom.len())];
+    }
+
+    let mut problems = vec![];
+
+    let start_code: [u8; 4] = rom[0..4].try_into().unwrap();
+    if start_code[3] != 0xEA {
+        problems.push(Problem::StartCode(start_code));
+    }
+
+    if &rom[LOGO_OFFSET..LOGO_OFFSET + NINTENDO_LOGO.len()] != NINTENDO_LOGO {
+        problems.push(Problem::NintendoLogo);
+    }
+
+    if rom[FIXED_VALUE_OFFSET] != 0x96 {
+        problems.push(Problem::FixedValue(rom[FIXED_VALUE_OFFSET]));
+    }
+
+    let game_code: [u8; 4] = rom[GAME_CODE_OFFSET..GAME_CODE_OFFSET + 4]
+        .try_into()
+        .unwrap();
+    if !is_valid_game_code(game_code) {
+        problems.push(Problem::GameCode(game_code));
+    }
+
+    let expected = GbaHeader::calculate_checksum(&rom[..GBA_HEADER_SIZE]);
+    let found = rom[CHECKSUM_OFFSET];
+    if expected != found {
+        problems.push(Problem::Checksum { expected, found });
+    }
+
+    if check_padding && !rom.len().is_power_of_two() {
+        problems.push(Problem::NotPadded(rom.len()));
+    }
+
+    problems
+}
+
+/// Fixes the problems [`check_gba_file`] finds, failing if any of them can't be repaired.
+pub fn repair_gba_file(rom: &mut Vec<u8>, pad: bool) -> Result<()> {
+    let unrepairable: Vec<_> = check_gba_file(rom, pad)
+        .into_iter()
+        .filter(|problem| !problem.is_repairable())
+        .map(|problem| problem.to_string())
+        .collect();
+
+    if !unrepairable.is_empty() {
+        bail!("Cannot repair the ROM:\n{}", unrepairable.join("\n"));
+    }
+
+    rom[LOGO_OFFSET..LOGO_OFFSET + NINTENDO_LOGO.len()].copy_from_slice(NINTENDO_LOGO);
+    rom[FIXED_VALUE_OFFSET] = 0x96;
+
+    let game_code: [u8; 4] = rom[GAME_CODE_OFFSET..GAME_CODE_OFFSET + 4]
+        .try_into()
+        .unwrap();
+    if let Some(game_code) = repaired_game_code(game_code) {
+        rom[GAME_CODE_OFFSET..GAME_CODE_OFFSET + 4].copy_from_slice(&game_code);
+    }
+
+    // must come after everything else in the header has been fixed
+    rom[CHECKSUM_OFFSET] = GbaHeader::calculate_checksum(&rom[..GBA_HEADER_SIZE]);
+
+    if pad {
+        rom.resize(rom.len().next_power_of_two(), 0);
+    }
+
+    Ok(())
+}
+
+// agb leaves the game code as zeros unless one is given, which is fine for homebrew
+fn is_valid_game_code(game_code: [u8; 4]) -> bool {
+    game_code == [0; 4]
+        || game_code
+            .iter()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
+
+// Lowercase letters can be made uppercase, but anything else needs a new game code
+fn repaired_game_code(game_code: [u8; 4]) -> Option<[u8; 4]> {
+    let repaired = game_code.map(|c| c.to_ascii_uppercase());
+    is_valid_game_code(repaired).then_some(repaired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid_rom() -> Vec<u8> {
+        let header = GbaHeader {
+            start_code: [0x2e, 0x00, 0x00, 0xea],
+            game_code: *b"AGBE",
+            ..Default::default()
+        };
+
+        let mut rom = header.produce_header();
+        rom.resize(1024, 0);
+        rom
+    }
+
+    #[test]
+    fn finds_no_problems_with_a_valid_rom() {
+        assert_eq!(check_gba_file(&valid_rom(), true), []);
+    }
+
+    #[test]
+    fn finds_and_repairs_header_problems() {
+        let mut rom = valid_rom();
+        rom[LOGO_OFFSET + 3] = 0;
+        rom[GAME_CODE_OFFSET] = b'a';
+        rom.push(0);
+
+        let problems = check_gba_file(&rom, true);
+        assert!(matches!(
+            problems.as_slice(),
+            [
+                Problem::NintendoLogo,
+                Problem::GameCode(_),
+                Problem::Checksum { .. },
+                Problem::NotPadded(1025)
+            ]
+        ));
+
+        repair_gba_file(&mut rom, true).unwrap();
+
+        assert_eq!(check_gba_file(&rom, true), []);
+        assert_eq!(&rom[GAME_CODE_OFFSET..GAME_CODE_OFFSET + 4], b"AGBE");
+        assert_eq!(rom.len(), 2048);
+    }
+
+    #[test]
+    fn does_not_repair_invalid_game_codes() {
+        let mut rom = valid_rom();
+        rom[GAME_CODE_OFFSET] = b'-';
+
+        assert!(repair_gba_file(&mut rom, false).is_err());
+    }
+}
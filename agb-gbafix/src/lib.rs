@@ -1,8 +1,16 @@
 use anyhow::{anyhow, bail, ensure, Result};
 use std::{collections::HashMap, io::Write};
 
+mod check;
+
+pub use check::{check_gba_file, repair_gba_file, Problem};
+
 const GBA_HEADER_SIZE: usize = 192;
 
+/// Multiboot images are loaded into EWRAM, so can't be any bigger than it
+const MULTIBOOT_MAX_SIZE: usize = 256 * 1024;
+const EWRAM_START: u64 = 0x0200_0000;
+
 const NINTENDO_LOGO: &[u8] = &[
     0x24, 0xFF, 0xAE, 0x51, 0x69, 0x9A, 0xA2, 0x21, 0x3D, 0x84, 0x82, 0x0A, 0x84, 0xE4, 0x09, 0xAD,
     0x11, 0x24, 0x8B, 0x98, 0xC0, 0x81, 0x7F, 0x21, 0xA3, 0x52, 0xBE, 0x19, 0x93, 0x09, 0xCE, 0x20,
@@ -69,13 +77,77 @@ pub enum PaddingBehaviour {
 
 pub fn write_gba_file<W: Write>(
     input: &[u8],
-    mut header: GbaHeader,
+    header: GbaHeader,
     padding_behaviour: PaddingBehaviour,
     include_debug: bool,
     output: &mut W,
 ) -> Result<()> {
     let elf_file = elf::ElfBytes::<elf::endian::AnyEndian>::minimal_parse(input)?;
 
+    let mut bytes_written = write_sections(&elf_file, header, output)?;
+
+    if include_debug {
+        bytes_written += write_debug(&elf_file, output)?;
+    }
+
+    if !bytes_written.is_power_of_two() && padding_behaviour == PaddingBehaviour::Pad {
+        let required_padding = bytes_written.next_power_of_two() - bytes_written;
+
+        for _ in 0..required_padding {
+            output.write_all(&[0])?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes an image which can be sent to a GBA over the link cable using multiboot, from an elf
+/// file built with agb's `multiboot` feature so that it runs from EWRAM. Debug information is
+/// never included, since it would be copied to EWRAM along with the game.
+pub fn write_multiboot_file<W: Write>(
+    input: &[u8],
+    header: GbaHeader,
+    output: &mut W,
+) -> Result<()> {
+    let elf_file = elf::ElfBytes::<elf::endian::AnyEndian>::minimal_parse(input)?;
+
+    let entry = elf_file.ehdr.e_entry;
+    ensure!(
+        (EWRAM_START..EWRAM_START + MULTIBOOT_MAX_SIZE as u64).contains(&entry),
+        "The entry point {entry:#010x} is not in EWRAM, so the elf file isn't linked for multiboot. Build it with agb's `multiboot` feature"
+    );
+
+    let mut image = vec![];
+    write_sections(&elf_file, header, &mut image)?;
+
+    // the multiboot entry point straight after the header, followed by the boot mode and slave id
+    // which the BIOS fills in when it starts the game
+    ensure!(
+        image.len() > 0xC5 && image[0xC3] == 0xEA,
+        "There is no multiboot entry point after the header, build the elf file with agb's `multiboot` feature"
+    );
+    image[0xC4] = 0;
+    image[0xC5] = 0;
+
+    // the BIOS transfers multiboot images 16 bytes at a time
+    image.resize(image.len().next_multiple_of(16), 0);
+
+    ensure!(
+        image.len() <= MULTIBOOT_MAX_SIZE,
+        "The multiboot image is {} bytes, but at most {MULTIBOOT_MAX_SIZE} bytes fit in EWRAM",
+        image.len()
+    );
+
+    output.write_all(&image)?;
+
+    Ok(())
+}
+
+fn write_sections<W: Write>(
+    elf_file: &elf::ElfBytes<'_, elf::endian::AnyEndian>,
+    mut header: GbaHeader,
+    output: &mut W,
+) -> Result<u64> {
     let section_headers = elf_file
         .section_headers()
         .ok_or_else(|| anyhow!("Failed to parse as elf file"))?;
@@ -124,19 +196,7 @@ pub fn write_gba_file<W: Write>(
         bytes_written += data.len() as u64;
     }
 
-    if include_debug {
-        bytes_written += write_debug(&elf_file, output)?;
-    }
-
-    if !bytes_written.is_power_of_two() && padding_behaviour == PaddingBehaviour::Pad {
-        let required_padding = bytes_written.next_power_of_two() - bytes_written;
-
-        for _ in 0..required_padding {
-            output.write_all(&[0])?;
-        }
-    }
-
-    Ok(())
+    Ok(bytes_written)
 }
 
 fn write_debug<W: Write>(
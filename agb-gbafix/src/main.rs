@@ -4,10 +4,13 @@ use clap::{arg, value_parser};
 use std::{
     fs,
     io::{BufWriter, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
-use agb_gbafix::{write_gba_file, GbaHeader, PaddingBehaviour};
+use agb_gbafix::{
+    check_gba_file, repair_gba_file, write_gba_file, write_multiboot_file, GbaHeader,
+    PaddingBehaviour,
+};
 
 fn main() -> Result<()> {
     let matches = clap::Command::new("agb-gbafix")
@@ -20,11 +23,23 @@ fn main() -> Result<()> {
         .arg(arg!(-r --gameversion <VERSION> "Set the version of the game, 0-255").value_parser(value_parser!(u8)))
         .arg(arg!(-p --padding "Pad the ROM to the next power of 2 in size"))
         .arg(arg!(-g --debug "Include debug information directly in the ROM"))
+        .arg(arg!(--multiboot "Output a multiboot image which can be sent over the link cable. The elf file must be built with agb's `multiboot` feature, and OUTPUT defaults to replacing INPUT's extension with .mb. Debug information can't be included, since the whole image is copied to EWRAM").conflicts_with_all(["padding", "debug"]))
+        .arg(arg!(--check "Check the header of the gba file INPUT rather than converting an elf file. With --padding, also check that the ROM is padded").conflicts_with("multiboot"))
+        .arg(arg!(--fix "Repair any problems found by --check, writing the result to OUTPUT which defaults to INPUT").requires("check"))
         .get_matches();
 
     let input = matches.get_one::<PathBuf>("INPUT").unwrap();
+
+    if matches.get_flag("check") {
+        let output = matches.get_one::<PathBuf>("output").unwrap_or(input);
+        let fix = matches.get_flag("fix").then_some(output.as_path());
+        return check(input, fix, matches.get_flag("padding"));
+    }
+
+    let multiboot = matches.get_flag("multiboot");
     let output = match matches.get_one::<PathBuf>("output") {
         Some(output) => output.clone(),
+        None if multiboot => input.with_extension("mb"),
         None => input.with_extension("gba"),
     };
 
@@ -83,15 +98,45 @@ fn main() -> Result<()> {
     let mut output = BufWriter::new(fs::File::create(output)?);
     let file_data = fs::read(input)?;
 
-    write_gba_file(
-        file_data.as_slice(),
-        header,
-        pad,
-        include_debug,
-        &mut output,
-    )?;
+    if multiboot {
+        write_multiboot_file(file_data.as_slice(), header, &mut output)?;
+    } else {
+        write_gba_file(
+            file_data.as_slice(),
+            header,
+            pad,
+            include_debug,
+            &mut output,
+        )?;
+    }
 
     output.flush()?;
 
     Ok(())
 }
+
+fn check(input: &Path, fix: Option<&Path>, pad: bool) -> Result<()> {
+    let mut rom = fs::read(input)?;
+    let problems = check_gba_file(&rom, pad);
+
+    if problems.is_empty() {
+        println!("No problems found in {}", input.display());
+        return Ok(());
+    }
+
+    println!("Found problems in {}:", input.display());
+    for problem in &problems {
+        println!("  {problem}");
+    }
+
+    let Some(output) = fix else {
+        bail!("{} has problems, use --fix to repair them", input.display());
+    };
+
+    repair_gba_file(&mut rom, pad)?;
+    fs::write(output, rom)?;
+
+    println!("Repaired and written to {}", output.display());
+
+    Ok(())
+}
@@ -3,6 +3,12 @@ use quote::quote;
 
 use proc_macro2::TokenStream;
 
+mod bdf;
+mod image_grid;
+
+pub use bdf::load_bdf;
+pub use image_grid::{load_image_grid, ImageGridOptions};
+
 struct KerningData {
     previous_character: char,
     amount: f32,
@@ -15,11 +21,19 @@ struct LetterData {
     xmin: i32,
     ymin: i32,
     advance_width: f32,
-    rendered: Vec<u8>,
+    // the coverage of each pixel from 0 to 255, row by row
+    coverage: Vec<u8>,
     kerning_data: Vec<KerningData>,
 }
 
-pub fn load_font(font_data: &[u8], pixels_per_em: f32) -> TokenStream {
+/// A font loaded from any of the supported formats, ready to be turned into a `display::Font`
+pub struct FontData {
+    letters: Vec<LetterData>,
+    line_height: i32,
+    ascent: i32,
+}
+
+pub fn load_font(font_data: &[u8], pixels_per_em: f32, charset: Option<&[char]>) -> FontData {
     let font = fontdue::Font::from_bytes(
         font_data,
         fontdue::FontSettings {
@@ -32,34 +46,29 @@ pub fn load_font(font_data: &[u8], pixels_per_em: f32) -> TokenStream {
     let line_metrics = font.horizontal_line_metrics(pixels_per_em).unwrap();
 
     let line_height = line_metrics.new_line_size as i32;
-    let mut ascent = line_metrics.ascent as i32;
+    let ascent = line_metrics.ascent as i32;
 
-    let mut letters: Vec<_> = font
+    let included_chars: Vec<_> = font
         .chars()
         .iter()
-        .map(|(&c, &index)| (c, index, font.rasterize(c, pixels_per_em)))
-        .map(|(c, index, (metrics, bitmap))| {
-            let width = metrics.width;
-            let height = metrics.height;
-
-            let rendered = bitmap
-                .chunks(8)
-                .map(|chunk| {
-                    let mut output = 0u8;
-                    for (i, &value) in chunk.iter().enumerate() {
-                        if value > 100 {
-                            output |= 1 << i;
-                        }
-                    }
-
-                    output
-                })
-                .collect();
+        .filter(|(&c, _)| is_in_charset(charset, c))
+        .collect();
 
-            let mut kerning_data: Vec<_> = font
-                .chars()
+    if let Some(charset) = charset {
+        for c in charset {
+            if !font.chars().contains_key(c) {
+                panic!("Character {c:?} in the charset is not in the font");
+            }
+        }
+    }
+
+    let letters = included_chars
+        .iter()
+        .map(|&(&c, &index)| (c, index, font.rasterize(c, pixels_per_em)))
+        .map(|(c, index, (metrics, bitmap))| {
+            let mut kerning_data: Vec<_> = included_chars
                 .iter()
-                .filter_map(|(&left_char, &left_index)| {
+                .filter_map(|&(&left_char, &left_index)| {
                     let kerning = font.horizontal_kern_indexed(
                         left_index.into(),
                         index.into(),
@@ -77,9 +86,9 @@ pub fn load_font(font_data: &[u8], pixels_per_em: f32) -> TokenStream {
 
             LetterData {
                 character: c,
-                width,
-                height,
-                rendered,
+                width: metrics.width,
+                height: metrics.height,
+                coverage: bitmap,
                 xmin: metrics.xmin,
                 ymin: metrics.ymin,
                 advance_width: metrics.advance_width,
@@ -88,51 +97,140 @@ pub fn load_font(font_data: &[u8], pixels_per_em: f32) -> TokenStream {
         })
         .collect();
 
-    letters.sort_unstable_by_key(|letter| letter.character);
+    FontData::new(letters, line_height, ascent)
+}
 
-    let maximum_above_line = letters
-        .iter()
-        .map(|x| (x.height as i32 + x.ymin))
-        .max()
-        .unwrap();
+// Spaces are always included since they are needed to lay out text
+fn is_in_charset(charset: Option<&[char]>, c: char) -> bool {
+    match charset {
+        Some(charset) => c == ' ' || charset.contains(&c),
+        None => true,
+    }
+}
+
+impl FontData {
+    fn new(mut letters: Vec<LetterData>, line_height: i32, mut ascent: i32) -> Self {
+        letters.sort_unstable_by_key(|letter| letter.character);
+
+        // a charset can leave a font with no letters, in which case the ascent is kept as it is
+        let maximum_above_line = letters.iter().map(|x| x.height as i32 + x.ymin).max();
 
-    if (ascent - maximum_above_line) < 0 {
-        ascent = maximum_above_line;
+        if let Some(maximum_above_line) = maximum_above_line {
+            ascent = ascent.max(maximum_above_line);
+        }
+
+        Self {
+            letters,
+            line_height,
+            ascent,
+        }
     }
 
-    let font = letters.iter().map(|letter_data| {
-        let character = letter_data.character;
-        let data_raw = ByteString(&letter_data.rendered);
-        let height = letter_data.height as u8;
-        let width = letter_data.width as u8;
-        let xmin = letter_data.xmin as i8;
-        let ymin = letter_data.ymin as i8;
-        let advance_width = letter_data.advance_width.ceil() as u8;
-        let kerning_amounts = letter_data.kerning_data.iter().map(|kerning_data| {
-            let amount = kerning_data.amount as i8;
-            let c = kerning_data.previous_character;
-            quote! {
-                (#c, #amount)
-            }
+    pub fn to_tokens(&self, bits_per_pixel: u8) -> TokenStream {
+        let FontData {
+            letters,
+            line_height,
+            ascent,
+        } = self;
+
+        let font = letters.iter().map(|letter_data| {
+            let character = letter_data.character;
+            let rendered = pack_coverage(&letter_data.coverage, bits_per_pixel);
+            let data_raw = ByteString(&rendered);
+            let height = letter_data.height as u8;
+            let width = letter_data.width as u8;
+            let xmin = letter_data.xmin as i8;
+            let ymin = letter_data.ymin as i8;
+            let advance_width = letter_data.advance_width.ceil() as u8;
+            let kerning_amounts = letter_data.kerning_data.iter().map(|kerning_data| {
+                let amount = kerning_data.amount as i8;
+                let c = kerning_data.previous_character;
+                quote! {
+                    (#c, #amount)
+                }
+            });
+
+            quote!(
+                display::FontLetter::new(
+                    #character,
+                    #width,
+                    #height,
+                    #data_raw,
+                    #xmin,
+                    #ymin,
+                    #advance_width,
+                    &[
+                        #(#kerning_amounts),*
+                    ]
+                )
+            )
         });
 
-        quote!(
-            display::FontLetter::new(
-                #character,
-                #width,
-                #height,
-                #data_raw,
-                #xmin,
-                #ymin,
-                #advance_width,
-                &[
-                    #(#kerning_amounts),*
-                ]
-            )
-        )
-    });
+        if bits_per_pixel == 1 {
+            quote![
+                display::Font::new(&[#(#font),*], #line_height, #ascent)
+            ]
+        } else {
+            quote![
+                display::Font::new(&[#(#font),*], #line_height, #ascent)
+                    .with_bits_per_pixel(#bits_per_pixel)
+            ]
+        }
+    }
+}
+
+// Packs the coverage values from fontdue into `bits_per_pixel` bits each, with the first
+// pixel in the least significant bits of the first byte.
+fn pack_coverage(bitmap: &[u8], bits_per_pixel: u8) -> Vec<u8> {
+    let pixels_per_byte = 8 / bits_per_pixel as usize;
+    let max_level = (1u32 << bits_per_pixel) - 1;
+
+    bitmap
+        .chunks(pixels_per_byte)
+        .map(|chunk| {
+            let mut output = 0u8;
+            for (i, &value) in chunk.iter().enumerate() {
+                let level = if bits_per_pixel == 1 {
+                    // 1bpp fonts look best with a slightly lower threshold than rounding would give
+                    u8::from(value > 100)
+                } else {
+                    ((u32::from(value) * max_level + 127) / 255) as u8
+                };
+
+                output |= level << (i * bits_per_pixel as usize);
+            }
+
+            output
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_bit_fonts_are_thresholded() {
+        assert_eq!(
+            pack_coverage(&[0, 255, 100, 101, 0, 0, 0, 0, 255], 1),
+            vec![0b0000_1010, 0b1]
+        );
+    }
 
-    quote![
-        display::Font::new(&[#(#font),*], #line_height, #ascent)
-    ]
+    #[test]
+    fn antialiased_fonts_store_coverage_levels() {
+        assert_eq!(
+            pack_coverage(&[0, 85, 170, 255, 40], 2),
+            vec![0b11_10_01_00, 0b00]
+        );
+        assert_eq!(pack_coverage(&[0, 255, 17, 136], 4), vec![0xf0, 0x81]);
+    }
+
+    #[test]
+    fn fonts_can_have_no_letters() {
+        let font = FontData::new(vec![], 10, 8);
+
+        assert_eq!(font.ascent, 8);
+        assert!(font.letters.is_empty());
+    }
 }
@@ -0,0 +1,228 @@
+use super::{is_in_charset, FontData, LetterData};
+
+#[derive(Default)]
+struct Glyph {
+    encoding: Option<u32>,
+    advance_width: Option<i32>,
+    bounding_box: Option<(usize, usize, i32, i32)>,
+    rows: Vec<Vec<u8>>,
+}
+
+fn parse_numbers<const N: usize>(line_number: usize, args: &[&str]) -> [i32; N] {
+    if args.len() < N {
+        panic!("Expected {N} values on line {line_number} of bdf file");
+    }
+
+    core::array::from_fn(|i| {
+        args[i]
+            .parse()
+            .unwrap_or_else(|_| panic!("Invalid number on line {line_number} of bdf file"))
+    })
+}
+
+fn parse_hex_row(line_number: usize, row: &str) -> Vec<u8> {
+    (0..row.len() / 2)
+        .map(|i| {
+            u8::from_str_radix(&row[i * 2..i * 2 + 2], 16)
+                .unwrap_or_else(|_| panic!("Invalid bitmap on line {line_number} of bdf file"))
+        })
+        .collect()
+}
+
+/// Loads a font in the Glyph Bitmap Distribution Format, as used by many pixel fonts
+pub fn load_bdf(font_data: &str, charset: Option<&[char]>) -> FontData {
+    let mut font_bounding_box = None;
+    let mut font_ascent = None;
+    let mut font_descent = None;
+    let mut default_advance_width = None;
+
+    let mut glyphs = vec![];
+    let mut current_glyph: Option<Glyph> = None;
+    let mut in_bitmap = false;
+
+    for (line_number, line) in font_data.lines().enumerate() {
+        let line_number = line_number + 1;
+        let mut parts = line.split_whitespace();
+        let Some(keyword) = parts.next() else {
+            continue;
+        };
+        let args: Vec<_> = parts.collect();
+
+        if in_bitmap {
+            if keyword == "ENDCHAR" {
+                in_bitmap = false;
+                glyphs.extend(current_glyph.take());
+            } else if let Some(glyph) = &mut current_glyph {
+                glyph.rows.push(parse_hex_row(line_number, keyword));
+            }
+
+            continue;
+        }
+
+        match (keyword, &mut current_glyph) {
+            ("FONTBOUNDINGBOX", _) => {
+                font_bounding_box = Some(parse_numbers::<4>(line_number, &args));
+            }
+            ("FONT_ASCENT", _) => font_ascent = Some(parse_numbers::<1>(line_number, &args)[0]),
+            ("FONT_DESCENT", _) => font_descent = Some(parse_numbers::<1>(line_number, &args)[0]),
+            ("STARTCHAR", _) => current_glyph = Some(Glyph::default()),
+            ("DWIDTH", None) => {
+                default_advance_width = Some(parse_numbers::<1>(line_number, &args)[0]);
+            }
+            ("DWIDTH", Some(glyph)) => {
+                glyph.advance_width = Some(parse_numbers::<1>(line_number, &args)[0]);
+            }
+            ("ENCODING", Some(glyph)) => {
+                // an encoding of -1 means the glyph doesn't have a unicode code point
+                glyph.encoding = u32::try_from(parse_numbers::<1>(line_number, &args)[0]).ok();
+            }
+            ("BBX", Some(glyph)) => {
+                let [width, height, xoff, yoff] = parse_numbers::<4>(line_number, &args);
+                glyph.bounding_box = Some((width as usize, height as usize, xoff, yoff));
+            }
+            ("BITMAP", Some(_)) => in_bitmap = true,
+            ("ENDCHAR", Some(_)) => glyphs.extend(current_glyph.take()),
+            _ => {}
+        }
+    }
+
+    let [_, font_height, _, font_yoff] =
+        font_bounding_box.expect("bdf file is missing FONTBOUNDINGBOX");
+    let ascent = font_ascent.unwrap_or(font_height + font_yoff);
+    let line_height = ascent + font_descent.unwrap_or(-font_yoff);
+
+    let letters: Vec<_> = glyphs
+        .into_iter()
+        .filter_map(|glyph| {
+            let character = char::from_u32(glyph.encoding?)?;
+            if !is_in_charset(charset, character) {
+                return None;
+            }
+
+            let (width, height, xmin, ymin) = glyph
+                .bounding_box
+                .unwrap_or_else(|| panic!("Character {character:?} is missing BBX in bdf file"));
+            let advance_width = glyph
+                .advance_width
+                .or(default_advance_width)
+                .unwrap_or_else(|| panic!("Character {character:?} is missing DWIDTH in bdf file"));
+
+            let coverage = (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(|(x, y)| {
+                    let byte = glyph.rows.get(y).and_then(|row| row.get(x / 8));
+                    match byte {
+                        Some(byte) if byte & (0x80 >> (x % 8)) != 0 => 255,
+                        _ => 0,
+                    }
+                })
+                .collect();
+
+            Some(LetterData {
+                character,
+                width,
+                height,
+                xmin,
+                ymin,
+                advance_width: advance_width as f32,
+                coverage,
+                kerning_data: vec![],
+            })
+        })
+        .collect();
+
+    if let Some(charset) = charset {
+        for c in charset {
+            if !letters.iter().any(|letter| letter.character == *c) {
+                panic!("Character {c:?} in the charset is not in the font");
+            }
+        }
+    }
+
+    FontData::new(letters, line_height, ascent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT: &str = "STARTFONT 2.1
+FONT -test-font
+SIZE 8 75 75
+FONTBOUNDINGBOX 6 8 0 -2
+STARTPROPERTIES 2
+FONT_ASCENT 6
+FONT_DESCENT 2
+ENDPROPERTIES
+CHARS 3
+STARTCHAR space
+ENCODING 32
+DWIDTH 4 0
+BBX 0 0 0 0
+BITMAP
+ENDCHAR
+STARTCHAR A
+ENCODING 65
+DWIDTH 6 0
+BBX 5 3 0 0
+BITMAP
+70
+88
+F8
+ENDCHAR
+STARTCHAR g
+ENCODING 103
+DWIDTH 5 0
+BBX 4 2 1 -2
+BITMAP
+90
+60
+ENDCHAR
+ENDFONT
+";
+
+    #[test]
+    fn loads_bdf_glyphs() {
+        let font = load_bdf(FONT, None);
+
+        assert_eq!(font.line_height, 8);
+        assert_eq!(font.ascent, 6);
+        assert_eq!(
+            font.letters
+                .iter()
+                .map(|letter| letter.character)
+                .collect::<Vec<_>>(),
+            vec![' ', 'A', 'g']
+        );
+
+        let a = &font.letters[1];
+        assert_eq!((a.width, a.height, a.xmin, a.ymin), (5, 3, 0, 0));
+        assert_eq!(a.advance_width, 6.);
+        assert_eq!(
+            a.coverage,
+            [
+                [0, 255, 255, 255, 0],
+                [255, 0, 0, 0, 255],
+                [255, 255, 255, 255, 255]
+            ]
+            .concat()
+        );
+
+        let g = &font.letters[2];
+        assert_eq!((g.xmin, g.ymin), (1, -2));
+        assert_eq!(g.coverage, [[255, 0, 0, 255], [0, 255, 255, 0]].concat());
+    }
+
+    #[test]
+    fn charset_restricts_glyphs() {
+        let font = load_bdf(FONT, Some(&['g']));
+
+        assert_eq!(
+            font.letters
+                .iter()
+                .map(|letter| letter.character)
+                .collect::<Vec<_>>(),
+            vec![' ', 'g']
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use crate::image_loader::Image;
+
+use super::{FontData, LetterData};
+
+pub struct ImageGridOptions {
+    pub cell_width: usize,
+    pub cell_height: usize,
+    /// The row within each cell which the letters sit on, defaults to the bottom of the cell
+    pub baseline: Option<usize>,
+}
+
+/// Loads a pixel font drawn as a grid of equally sized cells, one for each character in `charset`
+/// from left to right and then top to bottom. Any non transparent pixel is part of the letter, and
+/// the advance width is the width of the letter plus a 1 pixel gap. Empty cells (such as space) advance
+/// by half the cell width.
+pub fn load_image_grid(image: &Image, charset: &[char], options: &ImageGridOptions) -> FontData {
+    let ImageGridOptions {
+        cell_width,
+        cell_height,
+        baseline,
+    } = *options;
+    let baseline = baseline.unwrap_or(cell_height);
+
+    if cell_width == 0 || cell_height == 0 {
+        panic!("cell_width and cell_height must be non zero");
+    }
+
+    if baseline > cell_height {
+        panic!("baseline must be within the cell");
+    }
+
+    let columns = image.width / cell_width;
+    let rows = image.height / cell_height;
+
+    if charset.len() > columns * rows {
+        panic!(
+            "Image has space for {} characters, but the charset has {}",
+            columns * rows,
+            charset.len()
+        );
+    }
+
+    let mut letters: Vec<_> = charset
+        .iter()
+        .enumerate()
+        .map(|(i, &character)| {
+            let cell_x = (i % columns) * cell_width;
+            let cell_y = (i / columns) * cell_height;
+
+            let is_set =
+                |x: usize, y: usize| !image.colour(cell_x + x, cell_y + y).is_transparent();
+
+            let set_columns: Vec<_> = (0..cell_width)
+                .filter(|&x| (0..cell_height).any(|y| is_set(x, y)))
+                .collect();
+            let set_rows: Vec<_> = (0..cell_height)
+                .filter(|&y| (0..cell_width).any(|x| is_set(x, y)))
+                .collect();
+
+            let (Some(&left), Some(&right), Some(&top), Some(&bottom)) = (
+                set_columns.first(),
+                set_columns.last(),
+                set_rows.first(),
+                set_rows.last(),
+            ) else {
+                return empty_letter(character, cell_width);
+            };
+
+            let coverage = (top..=bottom)
+                .flat_map(|y| (left..=right).map(move |x| (x, y)))
+                .map(|(x, y)| if is_set(x, y) { 255 } else { 0 })
+                .collect();
+
+            LetterData {
+                character,
+                width: right - left + 1,
+                height: bottom - top + 1,
+                xmin: left as i32,
+                ymin: baseline as i32 - bottom as i32 - 1,
+                advance_width: (right + 2) as f32,
+                coverage,
+                kerning_data: vec![],
+            }
+        })
+        .collect();
+
+    if !charset.contains(&' ') {
+        letters.push(empty_letter(' ', cell_width));
+    }
+
+    FontData::new(letters, cell_height as i32, baseline as i32)
+}
+
+fn empty_letter(character: char, cell_width: usize) -> LetterData {
+    LetterData {
+        character,
+        width: 0,
+        height: 0,
+        xmin: 0,
+        ymin: 0,
+        advance_width: (cell_width / 2) as f32,
+        coverage: vec![],
+        kerning_data: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, Rgba, RgbaImage};
+
+    use super::*;
+
+    #[test]
+    fn loads_letters_from_grid() {
+        // two 4x4 cells, the first has a 2x3 block with a gap and the second is empty
+        let image = RgbaImage::from_fn(8, 4, |x, y| {
+            if x < 4 && (1..3).contains(&x) && y < 3 && !(x == 2 && y == 1) {
+                Rgba([255, 255, 255, 255])
+            } else {
+                Rgba([0, 0, 0, 0])
+            }
+        });
+        let image = Image::load_from_dyn_image(DynamicImage::ImageRgba8(image));
+
+        let font = load_image_grid(
+            &image,
+            &['i', ' '],
+            &ImageGridOptions {
+                cell_width: 4,
+                cell_height: 4,
+                baseline: Some(3),
+            },
+        );
+
+        assert_eq!(font.line_height, 4);
+        assert_eq!(font.ascent, 3);
+
+        let space = &font.letters[0];
+        assert_eq!(
+            (space.character, space.width, space.advance_width),
+            (' ', 0, 2.)
+        );
+
+        let i = &font.letters[1];
+        assert_eq!(i.character, 'i');
+        assert_eq!((i.width, i.height, i.xmin, i.ymin), (2, 3, 1, 0));
+        assert_eq!(i.advance_width, 4.);
+        assert_eq!(i.coverage, vec![255, 255, 255, 0, 255, 255]);
+    }
+
+    #[test]
+    #[should_panic = "Image has space for 2 characters, but the charset has 3"]
+    fn charset_must_fit_in_image() {
+        let image = Image::load_from_dyn_image(DynamicImage::ImageRgba8(RgbaImage::new(8, 4)));
+
+        load_image_grid(
+            &image,
+            &['a', 'b', 'c'],
+            &ImageGridOptions {
+                cell_width: 4,
+                cell_height: 4,
+                baseline: None,
+            },
+        );
+    }
+}
@@ -21,6 +21,7 @@ mod image_loader;
 mod palette16;
 mod palette256;
 mod rust_generator;
+mod strings;
 
 use image_loader::Image;
 
@@ -606,17 +607,69 @@ fn flatten_group(expr: &Expr) -> &Expr {
     }
 }
 
+enum FontArgument {
+    Positional(Expr),
+    Named(syn::Ident, Expr),
+}
+
+impl Parse for FontArgument {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        if input.peek(syn::Ident) && input.peek2(Token![=]) {
+            let name = input.parse()?;
+            let _: Token![=] = input.parse()?;
+            Ok(Self::Named(name, input.parse()?))
+        } else {
+            Ok(Self::Positional(input.parse()?))
+        }
+    }
+}
+
 #[proc_macro]
 pub fn include_font(input: TokenStream) -> TokenStream {
-    let parser = Punctuated::<Expr, syn::Token![,]>::parse_separated_nonempty;
+    let parser = Punctuated::<FontArgument, syn::Token![,]>::parse_separated_nonempty;
     let parsed = match parser.parse(input) {
         Ok(e) => e,
         Err(e) => return e.to_compile_error().into(),
     };
 
-    let all_args: Vec<_> = parsed.into_iter().collect();
-    if all_args.len() != 2 {
-        panic!("Include_font requires 2 arguments, got {}", all_args.len());
+    let mut all_args = vec![];
+    let mut bits_per_pixel = 1;
+    let mut charset: Option<String> = None;
+    let mut charset_file: Option<String> = None;
+    let mut cell_width = None;
+    let mut cell_height = None;
+    let mut baseline = None;
+    let mut strings_path: Option<String> = None;
+    let mut language: Option<String> = None;
+
+    for argument in parsed {
+        match argument {
+            FontArgument::Positional(expr) => all_args.push(expr),
+            FontArgument::Named(name, value) => match name.to_string().as_str() {
+                "antialias" => {
+                    bits_per_pixel = font_int_option(&name, &value);
+
+                    if ![1, 2, 4].contains(&bits_per_pixel) {
+                        panic!("antialias must be 1, 2 or 4 bits per pixel, got {bits_per_pixel}");
+                    }
+                }
+                "charset" => charset = Some(font_str_option(&name, &value)),
+                "charset_file" => charset_file = Some(font_str_option(&name, &value)),
+                "cell_width" => cell_width = Some(font_int_option(&name, &value)),
+                "cell_height" => cell_height = Some(font_int_option(&name, &value)),
+                "baseline" => baseline = Some(font_int_option(&name, &value)),
+                "strings" => strings_path = Some(font_str_option(&name, &value)),
+                "language" => language = Some(font_str_option(&name, &value)),
+                _ => panic!("Unknown include_font option {name}"),
+            },
+        }
+    }
+
+    if all_args.is_empty() || all_args.len() > 2 {
+        panic!(
+            "Include_font requires 1 or 2 arguments, got {}",
+            all_args.len()
+        );
     }
 
     let filename = match flatten_group(&all_args[0]) {
@@ -627,37 +680,166 @@ pub fn include_font(input: TokenStream) -> TokenStream {
         _ => panic!("Expected literal string as first argument to include_font"),
     };
 
-    let font_size = match flatten_group(&all_args[1]) {
-        Expr::Lit(ExprLit {
-            lit: Lit::Float(value),
-            ..
-        }) => value.base10_parse::<f32>().expect("Invalid float literal"),
-        Expr::Lit(ExprLit {
-            lit: Lit::Int(value),
-            ..
-        }) => value
-            .base10_parse::<i32>()
-            .expect("Invalid integer literal") as f32,
-        _ => panic!("Expected literal float or integer as second argument to include_font"),
-    };
+    let font_size = all_args
+        .get(1)
+        .map(|font_size| match flatten_group(font_size) {
+            Expr::Lit(ExprLit {
+                lit: Lit::Float(value),
+                ..
+            }) => value.base10_parse::<f32>().expect("Invalid float literal"),
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(value),
+                ..
+            }) => value
+                .base10_parse::<i32>()
+                .expect("Invalid integer literal") as f32,
+            _ => panic!("Expected literal float or integer as second argument to include_font"),
+        });
 
     let root = std::env::var("CARGO_MANIFEST_DIR").expect("Failed to get cargo manifest dir");
     let path = Path::new(&root).join(&*filename);
 
-    let file_content = std::fs::read(&path).expect("Failed to read ttf file");
+    let mut include_paths = vec![path.to_string_lossy().into_owned()];
+
+    if let Some(charset_file) = charset_file {
+        if charset.is_some() {
+            panic!("Only one of charset and charset_file can be given to include_font");
+        }
+
+        let charset_path = Path::new(&root).join(charset_file);
+        charset =
+            Some(std::fs::read_to_string(&charset_path).expect("Failed to read charset file"));
+        include_paths.push(charset_path.to_string_lossy().into_owned());
+    }
 
-    let rendered = font_loader::load_font(&file_content, font_size);
+    if let Some(strings_path) = strings_path {
+        let (table, files) = strings::load_strings(&Path::new(&root).join(strings_path));
+        let strings_charset: String = table.charset(language.as_deref()).into_iter().collect();
 
-    let include_path = path.to_string_lossy();
+        charset = Some(charset.unwrap_or_default() + &strings_charset);
+        include_paths.extend(
+            files
+                .into_iter()
+                .map(|file| file.to_string_lossy().into_owned()),
+        );
+    } else if language.is_some() {
+        panic!("language can only be given to include_font along with strings");
+    }
+
+    // newlines are ignored so that long charsets can be split over multiple lines
+    let charset: Option<Vec<char>> = charset.map(|charset| {
+        charset
+            .chars()
+            .filter(|&c| c != '\n' && c != '\r')
+            .collect()
+    });
+
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+
+    let font = match extension.as_deref() {
+        Some("bdf") => {
+            if font_size.is_some() {
+                panic!("Bdf fonts don't take a font size");
+            }
+
+            let file_content = std::fs::read_to_string(&path).expect("Failed to read bdf file");
+            font_loader::load_bdf(&file_content, charset.as_deref())
+        }
+        Some("png" | "bmp" | "aseprite") => {
+            if font_size.is_some() {
+                panic!("Image fonts don't take a font size");
+            }
+
+            let image = Image::load_from_file(&path);
+            font_loader::load_image_grid(
+                &image,
+                charset
+                    .as_deref()
+                    .expect("Image fonts require a charset or charset_file"),
+                &font_loader::ImageGridOptions {
+                    cell_width: cell_width.expect("Image fonts require a cell_width") as usize,
+                    cell_height: cell_height.expect("Image fonts require a cell_height") as usize,
+                    baseline: baseline.map(usize::from),
+                },
+            )
+        }
+        _ => {
+            let font_size = font_size.expect("Ttf fonts require a font size");
+            let file_content = std::fs::read(&path).expect("Failed to read ttf file");
+            font_loader::load_font(&file_content, font_size, charset.as_deref())
+        }
+    };
+
+    let rendered = font.to_tokens(bits_per_pixel);
 
     quote!({
-        let _ = include_bytes!(#include_path);
+        #(let _ = include_bytes!(#include_paths);)*
 
         #rendered
     })
     .into()
 }
 
+struct IncludeStringsInput {
+    module_name: syn::Ident,
+    path: LitStr,
+}
+
+impl Parse for IncludeStringsInput {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let module_name = input.parse()?;
+        let _: Token![,] = input.parse()?;
+        let path = input.parse()?;
+        let _: Option<Token![,]> = input.parse()?;
+
+        Ok(Self { module_name, path })
+    }
+}
+
+#[proc_macro]
+pub fn include_strings(input: TokenStream) -> TokenStream {
+    let IncludeStringsInput { module_name, path } =
+        parse_macro_input!(input as IncludeStringsInput);
+
+    let root = std::env::var("CARGO_MANIFEST_DIR").expect("Failed to get cargo manifest dir");
+    let (table, files) = strings::load_strings(&Path::new(&root).join(path.value()));
+
+    let include_paths = files.iter().map(|file| file.to_string_lossy().into_owned());
+    let strings = table.to_tokens();
+
+    quote! {
+        #[allow(dead_code)]
+        mod #module_name {
+            #(const _: &[u8] = include_bytes!(#include_paths);)*
+
+            #strings
+        }
+    }
+    .into()
+}
+
+fn font_int_option(name: &syn::Ident, value: &Expr) -> u8 {
+    match flatten_group(value) {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(value),
+            ..
+        }) => value.base10_parse::<u8>().expect("Invalid integer literal"),
+        _ => panic!("Expected literal integer for {name} in include_font"),
+    }
+}
+
+fn font_str_option(name: &syn::Ident, value: &Expr) -> String {
+    match flatten_group(value) {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(value),
+            ..
+        }) => value.value(),
+        _ => panic!("Expected literal string for {name} in include_font"),
+    }
+}
+
 fn valid_sprite_size(width: u32, height: u32) -> bool {
     match (width, height) {
         (8, 8) => true,
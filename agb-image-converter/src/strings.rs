@@ -0,0 +1,445 @@
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+mod csv;
+mod fluent;
+mod po;
+
+/// The strings for a single language, in the order they appear in the file. Placeholders
+/// are written as `{name}` whichever format they were loaded from.
+pub struct LanguageStrings {
+    pub language: String,
+    pub strings: Vec<(String, String)>,
+}
+
+/// Every string in every language, checked to make sure that each language has the same strings
+/// with the same placeholders.
+pub struct StringTable {
+    languages: Vec<String>,
+    ids: Vec<String>,
+    // text[language][id]
+    text: Vec<Vec<String>>,
+    placeholders: Vec<Vec<String>>,
+}
+
+/// Loads translations from either a csv file with a column for each language, or a directory
+/// containing a fluent (`.ftl`) or gettext (`.po`) file for each language named after the language.
+/// Returns the table along with every file which was read.
+pub fn load_strings(path: &Path) -> (StringTable, Vec<PathBuf>) {
+    if path.is_dir() {
+        let mut files: Vec<_> = std::fs::read_dir(path)
+            .unwrap_or_else(|e| panic!("Failed to read directory {}: {e}", path.display()))
+            .map(|entry| entry.expect("Failed to read directory entry").path())
+            .filter(|file| {
+                matches!(
+                    file.extension().and_then(|extension| extension.to_str()),
+                    Some("ftl" | "po")
+                )
+            })
+            .collect();
+        files.sort();
+
+        if files.is_empty() {
+            panic!(
+                "No .ftl or .po translation files found in {}",
+                path.display()
+            );
+        }
+
+        let languages = files
+            .iter()
+            .map(|file| {
+                let language = file
+                    .file_stem()
+                    .expect("Translation file has no name")
+                    .to_string_lossy()
+                    .into_owned();
+                let content = std::fs::read_to_string(file)
+                    .unwrap_or_else(|e| panic!("Failed to read {}: {e}", file.display()));
+
+                let strings = match file.extension().and_then(|extension| extension.to_str()) {
+                    Some("ftl") => fluent::load_fluent(&content, &language),
+                    _ => po::load_po(&content, &language),
+                };
+
+                LanguageStrings { language, strings }
+            })
+            .collect();
+
+        (StringTable::new(languages), files)
+    } else {
+        let content = std::fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("Failed to read {}: {e}", path.display()));
+
+        (
+            StringTable::new(csv::load_csv(&content)),
+            vec![path.to_owned()],
+        )
+    }
+}
+
+/// Finds the names of the `{name}` placeholders in some text
+fn placeholders(text: &str, id: &str, language: &str) -> Vec<String> {
+    let mut placeholders = vec![];
+    let mut rest = text;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest[start..].starts_with('}') {
+            panic!("Unmatched '}}' in string {id} for {language}");
+        }
+
+        let Some(length) = rest[start..].find('}') else {
+            panic!("Unmatched '{{' in string {id} for {language}");
+        };
+
+        let name = &rest[start + 1..start + length];
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            panic!("Invalid placeholder {{{name}}} in string {id} for {language}");
+        }
+
+        placeholders.push(name.to_owned());
+        rest = &rest[start + length + 1..];
+    }
+
+    placeholders
+}
+
+impl StringTable {
+    pub fn new(languages: Vec<LanguageStrings>) -> Self {
+        let Some(reference) = languages.first() else {
+            panic!("No languages found in translations");
+        };
+
+        let mut seen_languages = HashSet::new();
+        for language in &languages {
+            if !seen_languages.insert(&language.language) {
+                panic!("Language {} appears more than once", language.language);
+            }
+
+            let mut seen_ids = HashSet::new();
+            for (id, _) in &language.strings {
+                if !seen_ids.insert(id) {
+                    panic!(
+                        "String {id} appears more than once for {}",
+                        language.language
+                    );
+                }
+            }
+        }
+
+        let ids: Vec<String> = reference.strings.iter().map(|(id, _)| id.clone()).collect();
+
+        let text: Vec<Vec<String>> = languages
+            .iter()
+            .map(|language| {
+                for (id, _) in &language.strings {
+                    if !ids.contains(id) {
+                        panic!(
+                            "String {id} for {} is not in {}",
+                            language.language, reference.language
+                        );
+                    }
+                }
+
+                ids.iter()
+                    .map(|id| {
+                        language
+                            .strings
+                            .iter()
+                            .find(|(other, _)| other == id)
+                            .map(|(_, text)| text.clone())
+                            .unwrap_or_else(|| {
+                                panic!("String {id} is missing for {}", language.language)
+                            })
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let placeholders = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| {
+                let expected = placeholders(&text[0][i], id, &reference.language);
+                let expected_set: BTreeSet<_> = expected.iter().collect();
+
+                for (language, language_text) in languages.iter().zip(&text).skip(1) {
+                    let found = placeholders(&language_text[i], id, &language.language);
+                    if found.iter().collect::<BTreeSet<_>>() != expected_set {
+                        panic!(
+                            "String {id} has placeholders {found:?} for {} but {expected:?} for {}",
+                            language.language, reference.language
+                        );
+                    }
+                }
+
+                expected
+            })
+            .collect();
+
+        Self {
+            languages: languages
+                .into_iter()
+                .map(|language| language.language)
+                .collect(),
+            ids,
+            text,
+            placeholders,
+        }
+    }
+
+    /// Every character used by the strings for the given language, or for all languages if `None`.
+    /// Placeholders are filled in at runtime, so the characters in their names aren't included.
+    pub fn charset(&self, language: Option<&str>) -> Vec<char> {
+        let texts: Vec<&Vec<String>> = match language {
+            Some(language) => {
+                let index = self
+                    .languages
+                    .iter()
+                    .position(|other| other == language)
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "Language {language} is not in the translations, expected one of {:?}",
+                            self.languages
+                        )
+                    });
+                vec![&self.text[index]]
+            }
+            None => self.text.iter().collect(),
+        };
+
+        let mut charset = BTreeSet::new();
+        for text in texts.into_iter().flatten() {
+            let mut in_placeholder = false;
+            for c in text.chars() {
+                match c {
+                    '{' => in_placeholder = true,
+                    '}' => in_placeholder = false,
+                    '\n' | '\r' => {}
+                    c if !in_placeholder => {
+                        charset.insert(c);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        charset.into_iter().collect()
+    }
+
+    pub fn to_tokens(&self) -> TokenStream {
+        let language_names: Vec<_> = self
+            .languages
+            .iter()
+            .map(|language| format_ident!("{}", to_type_name(language, "language")))
+            .collect();
+        let id_names: Vec<_> = self
+            .ids
+            .iter()
+            .map(|id| format_ident!("{}", to_type_name(id, "string id")))
+            .collect();
+
+        for (names, kind) in [(&language_names, "language"), (&id_names, "string id")] {
+            let mut seen = HashSet::new();
+            for name in names {
+                if !seen.insert(name) {
+                    panic!("More than one {kind} would be called {name}");
+                }
+            }
+        }
+
+        let languages = &self.languages;
+        let charsets = self
+            .languages
+            .iter()
+            .map(|language| self.charset(Some(language)).into_iter().collect::<String>());
+        let language_count = self.languages.len();
+        let id_count = self.ids.len();
+        let text = self.text.iter().map(|text| quote!([#(#text),*]));
+        let placeholders = self
+            .placeholders
+            .iter()
+            .map(|placeholders| quote!(&[#(#placeholders),*]));
+
+        quote! {
+            /// The languages which strings have been translated into
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+            pub enum Language {
+                #(#language_names),*
+            }
+
+            impl Language {
+                /// Every language, in the order they appear in the translations
+                pub const ALL: &'static [Language] = &[#(Language::#language_names),*];
+
+                /// The name of the language as given in the translations
+                #[must_use]
+                pub const fn code(self) -> &'static str {
+                    match self {
+                        #(Language::#language_names => #languages),*
+                    }
+                }
+
+                /// Every character used by the strings in this language, not including placeholders
+                #[must_use]
+                pub const fn charset(self) -> &'static str {
+                    match self {
+                        #(Language::#language_names => #charsets),*
+                    }
+                }
+            }
+
+            /// Identifies a translated string
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+            pub enum StringId {
+                #(#id_names),*
+            }
+
+            impl StringId {
+                /// Every string, in the order they appear in the translations
+                pub const ALL: &'static [StringId] = &[#(StringId::#id_names),*];
+
+                /// The names of the placeholders in this string
+                #[must_use]
+                pub fn placeholders(self) -> &'static [&'static str] {
+                    static PLACEHOLDERS: [&[&str]; #id_count] = [#(#placeholders),*];
+                    PLACEHOLDERS[self as usize]
+                }
+            }
+
+            /// The text of a string in the given language. Placeholders are left in the text as `{name}`,
+            /// use [`write_text`] to fill them in.
+            #[must_use]
+            pub fn text(language: Language, id: StringId) -> &'static str {
+                static TEXT: [[&str; #id_count]; #language_count] = [#(#text),*];
+                TEXT[language as usize][id as usize]
+            }
+
+            /// Writes a string in the given language, replacing each `{name}` placeholder with the
+            /// matching argument.
+            ///
+            /// # Panics
+            ///
+            /// Panics if an argument is missing for one of the placeholders.
+            pub fn write_text(
+                output: &mut impl core::fmt::Write,
+                language: Language,
+                id: StringId,
+                arguments: &[(&str, &dyn core::fmt::Display)],
+            ) -> core::fmt::Result {
+                let mut rest = text(language, id);
+
+                while let Some(start) = rest.find('{') {
+                    output.write_str(&rest[..start])?;
+
+                    let end = start + rest[start..].find('}').unwrap();
+                    let name = &rest[start + 1..end];
+                    match arguments.iter().find(|(argument, _)| *argument == name) {
+                        Some((_, value)) => core::write!(output, "{}", value)?,
+                        None => panic!("missing argument {} for {:?}", name, id),
+                    }
+
+                    rest = &rest[end + 1..];
+                }
+
+                output.write_str(rest)
+            }
+        }
+    }
+}
+
+/// Converts an id like `main-menu.title` or `en_GB` into a type name like `MainMenuTitle` or `EnGB`
+fn to_type_name(id: &str, kind: &str) -> String {
+    let name: String = id
+        .split(|c: char| !c.is_alphanumeric())
+        .flat_map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .into_iter()
+                .flat_map(char::to_uppercase)
+                .chain(chars)
+        })
+        .collect();
+
+    match name.chars().next() {
+        Some(c) if c.is_alphabetic() => name,
+        _ => panic!("Cannot make a {kind} name from {id:?}, it must start with a letter"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn language(language: &str, strings: &[(&str, &str)]) -> LanguageStrings {
+        LanguageStrings {
+            language: language.to_owned(),
+            strings: strings
+                .iter()
+                .map(|&(id, text)| (id.to_owned(), text.to_owned()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn strings_are_ordered_by_first_language() {
+        let table = StringTable::new(vec![
+            language("en", &[("hello", "Hello"), ("score", "Score: {points}")]),
+            language(
+                "fr",
+                &[("score", "Points : {points}"), ("hello", "Bonjour")],
+            ),
+        ]);
+
+        assert_eq!(table.ids, ["hello", "score"]);
+        assert_eq!(table.text[1], ["Bonjour", "Points : {points}"]);
+        assert_eq!(table.placeholders, [vec![], vec!["points".to_owned()]]);
+    }
+
+    #[test]
+    #[should_panic = "String score has placeholders [\"score\"] for fr but [\"points\"] for en"]
+    fn placeholders_must_match() {
+        StringTable::new(vec![
+            language("en", &[("score", "Score: {points}")]),
+            language("fr", &[("score", "Points : {score}")]),
+        ]);
+    }
+
+    #[test]
+    #[should_panic = "String goodbye is missing for fr"]
+    fn every_language_needs_every_string() {
+        StringTable::new(vec![
+            language("en", &[("hello", "Hello"), ("goodbye", "Goodbye")]),
+            language("fr", &[("hello", "Bonjour")]),
+        ]);
+    }
+
+    #[test]
+    fn charset_skips_placeholders() {
+        let table = StringTable::new(vec![
+            language("en", &[("score", "Score: {points}")]),
+            language("ja", &[("score", "点数：{points}")]),
+        ]);
+
+        assert_eq!(
+            table.charset(Some("en")),
+            [' ', ':', 'S', 'c', 'e', 'o', 'r']
+        );
+        assert_eq!(table.charset(Some("ja")), ['数', '点', '：']);
+        assert_eq!(table.charset(None).len(), 10);
+    }
+
+    #[test]
+    fn ids_become_type_names() {
+        assert_eq!(
+            to_type_name("main-menu.title", "string id"),
+            "MainMenuTitle"
+        );
+        assert_eq!(to_type_name("en_GB", "language"), "EnGB");
+        assert_eq!(to_type_name("helloWorld", "string id"), "HelloWorld");
+    }
+}
@@ -0,0 +1,113 @@
+use super::LanguageStrings;
+
+// Splits csv data into records of fields, handling quoted fields which can contain commas,
+// newlines and doubled quotes.
+fn parse_records(data: &str) -> Vec<Vec<String>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+
+    let mut chars = data.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => record.push(core::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                record.push(core::mem::take(&mut field));
+                records.push(core::mem::take(&mut record));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        panic!("Unterminated quoted field in csv file");
+    }
+
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    records
+        .into_iter()
+        .filter(|record| record.iter().any(|field| !field.is_empty()))
+        .collect()
+}
+
+/// Loads a csv file where the first row is a header of `id` followed by the name of each
+/// language, and every other row is the id of a string followed by its translations.
+pub fn load_csv(data: &str) -> Vec<LanguageStrings> {
+    let mut records = parse_records(data).into_iter();
+    let header = records.next().expect("Csv file is empty");
+
+    let mut languages: Vec<_> = header
+        .iter()
+        .skip(1)
+        .map(|language| LanguageStrings {
+            language: language.trim().to_owned(),
+            strings: vec![],
+        })
+        .collect();
+
+    if languages.is_empty() {
+        panic!("Csv file must have a column for each language after the id column");
+    }
+
+    for (row, record) in records.enumerate() {
+        if record.len() != header.len() {
+            panic!(
+                "Row {} of csv file has {} fields, but the header has {}",
+                row + 2,
+                record.len(),
+                header.len()
+            );
+        }
+
+        let id = record[0].trim();
+        for (language, text) in languages.iter_mut().zip(&record[1..]) {
+            if text.is_empty() {
+                panic!("String {id} has no translation for {}", language.language);
+            }
+
+            language.strings.push((id.to_owned(), text.clone()));
+        }
+    }
+
+    languages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_quoted_fields() {
+        let languages =
+            load_csv("id,en,fr\r\nhello,Hello,Bonjour\r\n\r\nlist,\"a, b\",\"\"\"a\"\"\nb\"\n");
+
+        assert_eq!(languages.len(), 2);
+        assert_eq!(languages[0].language, "en");
+        assert_eq!(
+            languages[0].strings,
+            [
+                ("hello".to_owned(), "Hello".to_owned()),
+                ("list".to_owned(), "a, b".to_owned())
+            ]
+        );
+        assert_eq!(languages[1].strings[1].1, "\"a\"\nb");
+    }
+
+    #[test]
+    #[should_panic = "String hello has no translation for fr"]
+    fn empty_translations_are_missing() {
+        load_csv("id,en,fr\nhello,Hello,\n");
+    }
+}
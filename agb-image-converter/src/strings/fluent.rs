@@ -0,0 +1,125 @@
+/// Loads the messages from a fluent file. Only simple messages are supported, where each
+/// placeable is a variable such as `{ $name }`, and text can continue over multiple indented lines.
+pub fn load_fluent(data: &str, language: &str) -> Vec<(String, String)> {
+    let mut strings: Vec<(String, String)> = vec![];
+    let mut current: Option<(String, Vec<String>)> = None;
+
+    for (line_number, line) in data.lines().enumerate() {
+        let line_number = line_number + 1;
+
+        if line.starts_with([' ', '\t']) {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            let Some((_, lines)) = &mut current else {
+                panic!("Unexpected indented line {line_number} in {language}.ftl");
+            };
+
+            if trimmed.starts_with('.') {
+                panic!(
+                    "Attributes are not supported, found on line {line_number} of {language}.ftl"
+                );
+            }
+            if trimmed.starts_with(['[', '*']) {
+                panic!(
+                    "Selectors are not supported, found on line {line_number} of {language}.ftl"
+                );
+            }
+
+            lines.push(trimmed.to_owned());
+            continue;
+        }
+
+        strings.extend(current.take().map(|(id, lines)| (id, lines.join("\n"))));
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('-') {
+            panic!("Terms are not supported, found on line {line_number} of {language}.ftl");
+        }
+
+        let Some((id, value)) = line.split_once('=') else {
+            panic!("Expected `id = value` on line {line_number} of {language}.ftl");
+        };
+
+        let value = value.trim();
+        current = Some((
+            id.trim().to_owned(),
+            if value.is_empty() {
+                vec![]
+            } else {
+                vec![value.to_owned()]
+            },
+        ));
+    }
+
+    strings.extend(current.take().map(|(id, lines)| (id, lines.join("\n"))));
+
+    strings
+        .into_iter()
+        .map(|(id, text)| {
+            let text = convert_placeables(&text, &id, language);
+            (id, text)
+        })
+        .collect()
+}
+
+// Turns `{ $name }` into `{name}`
+fn convert_placeables(text: &str, id: &str, language: &str) -> String {
+    let mut output = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+
+        let Some(length) = rest[start..].find('}') else {
+            panic!("Unmatched '{{' in {id} in {language}.ftl");
+        };
+
+        let placeable = rest[start + 1..start + length].trim();
+        let Some(name) = placeable.strip_prefix('$') else {
+            panic!("Only variables are supported in placeables, found {{ {placeable} }} in {id} in {language}.ftl");
+        };
+
+        output.push('{');
+        output.push_str(name);
+        output.push('}');
+
+        rest = &rest[start + length + 1..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_messages() {
+        let strings = load_fluent(
+            "# comment\nhello = Hello, { $name }!\n\nintro =\n    First line\n    second line\nlast = done",
+            "en",
+        );
+
+        assert_eq!(
+            strings,
+            [
+                ("hello".to_owned(), "Hello, {name}!".to_owned()),
+                ("intro".to_owned(), "First line\nsecond line".to_owned()),
+                ("last".to_owned(), "done".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic = "Terms are not supported, found on line 1 of en.ftl"]
+    fn terms_are_not_supported() {
+        load_fluent("-brand = agb", "en");
+    }
+}
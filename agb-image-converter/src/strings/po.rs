@@ -0,0 +1,172 @@
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Context,
+    Id,
+    Text,
+}
+
+#[derive(Default)]
+struct Entry {
+    context: Option<String>,
+    id: String,
+    text: String,
+}
+
+fn parse_string(line_number: usize, language: &str, value: &str) -> String {
+    let Some(value) = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    else {
+        panic!("Expected a quoted string on line {line_number} of {language}.po");
+    };
+
+    let mut output = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => output.push('\n'),
+            Some('t') => output.push('\t'),
+            Some(c @ ('"' | '\\')) => output.push(c),
+            _ => panic!("Invalid escape on line {line_number} of {language}.po"),
+        }
+    }
+
+    output
+}
+
+/// Loads a gettext po file. The `msgctxt` of each entry is used as its id if there is one, otherwise
+/// the `msgid` is used.
+pub fn load_po(data: &str, language: &str) -> Vec<(String, String)> {
+    let mut entries = vec![];
+    let mut entry: Option<Entry> = None;
+    let mut field = Field::Text;
+
+    for (line_number, line) in data.lines().enumerate() {
+        let line_number = line_number + 1;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('"') {
+            let Some(entry) = &mut entry else {
+                panic!("Unexpected string on line {line_number} of {language}.po");
+            };
+
+            let value = parse_string(line_number, language, line);
+            match field {
+                Field::Context => entry
+                    .context
+                    .get_or_insert_with(String::new)
+                    .push_str(&value),
+                Field::Id => entry.id.push_str(&value),
+                Field::Text => entry.text.push_str(&value),
+            }
+
+            continue;
+        }
+
+        let (keyword, value) = line.split_once(' ').unwrap_or((line, ""));
+        let value = parse_string(line_number, language, value);
+
+        match keyword {
+            "msgctxt" => {
+                entries.extend(entry.take());
+                entry = Some(Entry {
+                    context: Some(value),
+                    ..Default::default()
+                });
+                field = Field::Context;
+            }
+            "msgid" => {
+                if !matches!(&entry, Some(entry) if field == Field::Context && entry.id.is_empty())
+                {
+                    entries.extend(entry.take());
+                    entry = Some(Entry::default());
+                }
+
+                if let Some(entry) = &mut entry {
+                    entry.id = value;
+                }
+                field = Field::Id;
+            }
+            "msgstr" => {
+                let Some(entry) = &mut entry else {
+                    panic!("msgstr without msgid on line {line_number} of {language}.po");
+                };
+
+                entry.text = value;
+                field = Field::Text;
+            }
+            _ if keyword.starts_with("msgid_plural") || keyword.starts_with("msgstr[") => {
+                panic!("Plurals are not supported, found on line {line_number} of {language}.po");
+            }
+            _ => panic!("Unknown keyword {keyword} on line {line_number} of {language}.po"),
+        }
+    }
+
+    entries.extend(entry);
+
+    entries
+        .into_iter()
+        // the entry with an empty id is the header
+        .filter(|entry| !entry.id.is_empty() || entry.context.is_some())
+        .map(|entry| {
+            let id = entry.context.unwrap_or(entry.id);
+            if entry.text.is_empty() {
+                panic!("String {id} has no translation in {language}.po");
+            }
+
+            (id, entry.text)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_entries() {
+        let strings = load_po(
+            r#"
+# header
+msgid ""
+msgstr ""
+"Language: fr\n"
+
+msgid "hello"
+msgstr "Bonjour"
+
+#: src/main.rs
+msgctxt "menu.quit"
+msgid "Quit"
+msgstr ""
+"Quitter "
+"\"maintenant\""
+"#,
+            "fr",
+        );
+
+        assert_eq!(
+            strings,
+            [
+                ("hello".to_owned(), "Bonjour".to_owned()),
+                ("menu.quit".to_owned(), "Quitter \"maintenant\"".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic = "String hello has no translation in fr.po"]
+    fn untranslated_entries_are_missing() {
+        load_po("msgid \"hello\"\nmsgstr \"\"\n", "fr");
+    }
+}
@@ -0,0 +1,211 @@
+#![no_std]
+#![deny(missing_docs)]
+//! The compact format `agb` records button input in, so that recordings made on the Game Boy
+//! Advance can be replayed by tools running on the host such as `mgba-test-runner`.
+//!
+//! Buttons are stored as the bits of the GBA's `KEYINPUT` register, except that a set bit means the
+//! button is pressed. Each run of identical button states is a little endian u16, where the bottom
+//! 10 bits are the buttons pressed and the top 6 bits are the length of the run minus 1.
+
+extern crate alloc;
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::fmt;
+
+const BUTTON_BITS: u16 = 10;
+const BUTTON_MASK: u16 = (1 << BUTTON_BITS) - 1;
+const MAX_RUN_LENGTH: u16 = 1 << (16 - BUTTON_BITS);
+
+const fn encode_run(buttons: u16, length: u16) -> u16 {
+    ((length - 1) << BUTTON_BITS) | (buttons & BUTTON_MASK)
+}
+
+const fn decode_run(run: u16) -> (u16, u16) {
+    (run & BUTTON_MASK, (run >> BUTTON_BITS) + 1)
+}
+
+/// A run length encoded recording of the buttons pressed on each frame.
+///
+/// The [`Display`](core::fmt::Display) implementation prints the bytes as a hex string, so a
+/// recording can be dumped to a log and converted back to a file with `xxd -r -p`.
+#[derive(Clone, Default)]
+pub struct InputRecording {
+    runs: Vec<u16>,
+    current: u16,
+    current_length: u16,
+}
+
+impl InputRecording {
+    /// Creates a new, empty recording.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            runs: Vec::new(),
+            current: 0,
+            current_length: 0,
+        }
+    }
+
+    /// Records the buttons pressed for a single frame. Bits above the 10 buttons are ignored.
+    pub fn push(&mut self, pressed: u16) {
+        let pressed = pressed & BUTTON_MASK;
+
+        if self.current_length > 0
+            && (pressed != self.current || self.current_length == MAX_RUN_LENGTH)
+        {
+            self.runs
+                .push(encode_run(self.current, self.current_length));
+            self.current_length = 0;
+        }
+
+        self.current = pressed;
+        self.current_length += 1;
+    }
+
+    /// The number of frames which have been recorded.
+    #[must_use]
+    pub fn frames(&self) -> usize {
+        self.runs
+            .iter()
+            .map(|&run| decode_run(run).1 as usize)
+            .sum::<usize>()
+            + self.current_length as usize
+    }
+
+    fn encoded_runs(&self) -> impl Iterator<Item = u16> + '_ {
+        let last_run =
+            (self.current_length > 0).then(|| encode_run(self.current, self.current_length));
+        self.runs.iter().copied().chain(last_run)
+    }
+
+    /// Returns the encoded recording, which can be played back by passing it to
+    /// [`InputReplay::new()`].
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.encoded_runs().flat_map(u16::to_le_bytes).collect()
+    }
+}
+
+impl fmt::Display for InputRecording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for run in self.encoded_runs() {
+            for byte in run.to_le_bytes() {
+                write!(f, "{byte:02x}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Plays back an [`InputRecording`] one frame at a time, giving the buttons pressed on each frame.
+///
+/// The data can either be owned (such as data loaded from save media or a file) or static (such
+/// as a demo included in the ROM with `include_bytes!`).
+#[derive(Clone)]
+pub struct InputReplay {
+    data: Cow<'static, [u8]>,
+    position: usize,
+    current: u16,
+    remaining: u16,
+}
+
+impl InputReplay {
+    /// Creates a new replay from data previously produced by [`InputRecording::to_bytes()`].
+    ///
+    /// A trailing odd byte is ignored.
+    #[must_use]
+    pub fn new(data: impl Into<Cow<'static, [u8]>>) -> Self {
+        Self {
+            data: data.into(),
+            position: 0,
+            current: 0,
+            remaining: 0,
+        }
+    }
+
+    /// Returns `true` once every recorded frame has been played back.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.remaining == 0 && self.position + 2 > self.data.len()
+    }
+}
+
+impl Iterator for InputReplay {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            let run = self.data.get(self.position..self.position + 2)?;
+            self.position += 2;
+
+            (self.current, self.remaining) = decode_run(u16::from_le_bytes([run[0], run[1]]));
+        }
+
+        self.remaining -= 1;
+        Some(self.current)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::{format, vec};
+
+    #[test]
+    fn recording_round_trips() {
+        let frames = [0, 1, 0x11, 0x11, 0, 0x300];
+
+        let mut recording = InputRecording::new();
+        for &frame in &frames {
+            recording.push(frame);
+        }
+
+        assert_eq!(recording.frames(), frames.len());
+
+        let mut replay = InputReplay::new(recording.to_bytes());
+        for &frame in &frames {
+            assert!(!replay.is_finished());
+            assert_eq!(replay.next(), Some(frame));
+        }
+
+        assert!(replay.is_finished());
+        assert_eq!(replay.next(), None);
+    }
+
+    #[test]
+    fn long_holds_are_compact() {
+        let mut recording = InputRecording::new();
+        for _ in 0..600 {
+            recording.push(2);
+        }
+
+        let bytes = recording.to_bytes();
+        assert_eq!(bytes.len(), 2 * 600usize.div_ceil(MAX_RUN_LENGTH as usize));
+        assert_eq!(recording.frames(), 600);
+
+        let replay = InputReplay::new(bytes);
+        assert_eq!(replay.count(), 600);
+    }
+
+    #[test]
+    fn decodes_runs() {
+        // A held for 2 frames, then START for 1 frame
+        let replay = InputReplay::new(vec![0x01, 0x04, 0x08, 0x00]);
+        assert_eq!(replay.collect::<Vec<_>>(), [1, 1, 8]);
+
+        // a run of the longest length, then a trailing odd byte
+        let replay = InputReplay::new(vec![0x00, 0xfc, 0x02, 0x00, 0xff]);
+        assert_eq!(replay.count(), 65);
+    }
+
+    #[test]
+    fn display_is_hex() {
+        let mut recording = InputRecording::new();
+        recording.push(1);
+        recording.push(1);
+        recording.push(8);
+
+        assert_eq!(format!("{recording}"), "01040800");
+    }
+}
@@ -0,0 +1,99 @@
+#![no_std]
+#![no_main]
+
+use agb::{
+    display::{
+        tiled::{RegularBackgroundSize, TileFormat, TiledMap},
+        Font, Priority,
+    },
+    include_font, include_strings,
+    input::{Button, ButtonController},
+};
+
+use core::fmt::Write;
+
+include_strings!(strings, "examples/strings/strings.csv");
+
+use strings::{Language, StringId};
+
+// Only the characters used by the translations are included, along with the digits used for the score
+static FONT: Font = include_font!(
+    "examples/font/ark-pixel-10px-proportional-ja.ttf",
+    10,
+    strings = "examples/strings/strings.csv",
+    charset = "0123456789"
+);
+
+#[agb::entry]
+fn main(mut gba: agb::Gba) -> ! {
+    let (gfx, mut vram) = gba.display.video.tiled0();
+    let vblank = agb::interrupt::VBlank::get();
+    let mut input = ButtonController::new();
+
+    vram.set_background_palette_raw(&[
+        0x0000, 0x0ff0, 0x00ff, 0xf00f, 0xf0f0, 0x0f0f, 0xaaaa, 0x5555, 0x0000, 0x0000, 0x0000,
+        0x0000, 0x0000, 0x0000, 0x0000, 0x0000,
+    ]);
+
+    let background_tile = vram.new_dynamic_tile().fill_with(0);
+
+    let mut bg = gfx.background(
+        Priority::P0,
+        RegularBackgroundSize::Background32x32,
+        TileFormat::FourBpp,
+    );
+
+    for y in 0..20u16 {
+        for x in 0..30u16 {
+            bg.set_tile(
+                &mut vram,
+                (x, y),
+                &background_tile.tile_set(),
+                background_tile.tile_setting(),
+            );
+        }
+    }
+
+    vram.remove_dynamic_tile(background_tile);
+    bg.set_visible(true);
+
+    let mut language_index = 0;
+    let mut score = 0;
+
+    loop {
+        let language = Language::ALL[language_index];
+
+        let mut renderer = FONT.render_text((1u16, 1u16));
+        let mut writer = renderer.writer(1, 0, &mut bg, &mut vram);
+
+        strings::write_text(&mut writer, language, StringId::Title, &[]).unwrap();
+        writer.write_char('\n').unwrap();
+        strings::write_text(
+            &mut writer,
+            language,
+            StringId::Greeting,
+            &[("name", &"agb")],
+        )
+        .unwrap();
+        writer.write_char('\n').unwrap();
+        strings::write_text(
+            &mut writer,
+            language,
+            StringId::Score,
+            &[("points", &score)],
+        )
+        .unwrap();
+        writer.commit();
+
+        vblank.wait_for_vblank();
+        bg.commit(&mut vram);
+        input.update();
+
+        if input.is_just_pressed(Button::A) {
+            language_index = (language_index + 1) % Language::ALL.len();
+        }
+        score += 1;
+
+        renderer.clear(&mut vram);
+    }
+}
@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+use agb::{
+    display::{
+        example_logo,
+        tiled::{RegularBackgroundSize, TileFormat},
+    },
+    input::{Button, ButtonController},
+};
+
+#[agb::entry]
+fn main(mut gba: agb::Gba) -> ! {
+    let (gfx, mut vram) = gba.display.video.tiled0();
+
+    let mut map = gfx.background(
+        agb::display::Priority::P0,
+        RegularBackgroundSize::Background32x32,
+        TileFormat::FourBpp,
+    );
+
+    example_logo::display_logo(&mut map, &mut vram);
+
+    let vblank = agb::interrupt::VBlank::get();
+    let mut input = ButtonController::new();
+
+    loop {
+        input.update();
+
+        if input.is_just_pressed(Button::SELECT) {
+            agb::println!("Going to sleep, press L + R + SELECT to wake up");
+            agb::sleep::sleep_until_pressed(Button::L | Button::R | Button::SELECT);
+            agb::println!("Woken up");
+        }
+
+        vblank.wait_for_vblank();
+    }
+}
@@ -1,7 +1,15 @@
-use core::{arch::asm, ops::Index};
+use core::{
+    arch::asm,
+    fmt::{self, Write},
+    ops::Index,
+    panic::PanicInfo,
+};
 
 use alloc::vec::Vec;
 
+// How many words from the top of the stack are included in crash reports
+const STACK_WORDS: usize = 4;
+
 // only works for code compiled as THUMB
 #[repr(C)]
 #[derive(Clone, Default, Debug)]
@@ -11,6 +19,9 @@ struct Context {
 
 pub struct Frames {
     frames: Vec<u32>,
+    // sp, lr and the cpsr
+    registers: [u32; 3],
+    stack: [u32; STACK_WORDS],
 }
 
 #[allow(unused)]
@@ -36,6 +47,9 @@ impl Index<Register> for Context {
     }
 }
 
+// The registers are saved here rather than where the panic happened, so r0-r12 and pc would be
+// this function's own values. Only sp and lr say anything about the code which panicked, so they
+// are the only ones kept along with the cpsr.
 #[inline(never)]
 pub(crate) fn unwind_exception() -> Frames {
     let mut context = Context::default();
@@ -65,6 +79,11 @@ pub(crate) fn unwind_exception() -> Frames {
         );
     }
 
+    let cpsr = cpsr();
+
+    let stack_pointer = context[Register::SP] as *const u32;
+    let stack = core::array::from_fn(|i| unsafe { stack_pointer.add(i).read_volatile() });
+
     let mut frame_pointer = context[Register::FP];
 
     let mut frames = Vec::new();
@@ -82,11 +101,40 @@ pub(crate) fn unwind_exception() -> Frames {
         frame_pointer = sp;
     }
 
-    Frames { frames }
+    Frames {
+        frames,
+        registers: [context[Register::SP], context[Register::LR], cpsr],
+        stack,
+    }
+}
+
+// mrs isn't available in THUMB mode
+#[instruction_set(arm::a32)]
+fn cpsr() -> u32 {
+    let cpsr;
+    unsafe { asm!("mrs {}, cpsr", out(reg) cpsr, options(nomem, nostack, preserves_flags)) };
+    cpsr
 }
 
-impl core::fmt::Display for Frames {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+impl Frames {
+    /// The full crash report for this backtrace, in version 2 of the encoding.
+    pub fn crash_report(&self, info: &PanicInfo) -> CrashReport<'_> {
+        let mut hasher = PanicHasher::default();
+        let _ = write!(&mut hasher, "{info}");
+
+        let (line, column) = info
+            .location()
+            .map_or((0, 0), |location| (location.line(), location.column()));
+
+        CrashReport {
+            frames: self,
+            line,
+            column,
+            panic_hash: hasher.0,
+        }
+    }
+
+    fn write_frames(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for frame in &self.frames {
             if frame & 0xFFFF_0000 == 0x0800_0000 {
                 let frame = *frame as u16; // intentionally truncate
@@ -102,10 +150,89 @@ impl core::fmt::Display for Frames {
             }
         }
 
+        Ok(())
+    }
+}
+
+impl fmt::Display for Frames {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_frames(f)?;
         write!(f, "v1")
     }
 }
 
+/// A backtrace along with the location and a hash of the panic, and sp, lr, the CPSR and the top of
+/// the stack as they were while the backtrace was being taken.
+///
+/// These are captured inside the panic handler rather than at the point the game panicked, so the
+/// stack words are the top of the panic handler's stack with the frames of the code which panicked
+/// above them. The other registers would only be the panic handler's own values, so they are left
+/// out to keep the QR code small.
+///
+/// This is displayed as the return addresses in the same way as version 1, followed by `.`
+/// separated sections for sp, lr and the CPSR, the words at the top of the stack, and the panic's
+/// line, column and hash. Values in these sections are only 16 bits if they fit rather than being
+/// assumed to be in ROM.
+pub struct CrashReport<'a> {
+    frames: &'a Frames,
+    line: u32,
+    column: u32,
+    panic_hash: u32,
+}
+
+impl fmt::Display for CrashReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.frames.write_frames(f)?;
+
+        write!(f, ".")?;
+        for &register in &self.frames.registers {
+            write_value(f, register)?;
+        }
+
+        write!(f, ".")?;
+        for &word in &self.frames.stack {
+            write_value(f, word)?;
+        }
+
+        write!(f, ".")?;
+        for value in [self.line, self.column, self.panic_hash] {
+            write_value(f, value)?;
+        }
+
+        write!(f, "v2")
+    }
+}
+
+fn write_value(f: &mut fmt::Formatter<'_>, value: u32) -> fmt::Result {
+    if let Ok(value) = u16::try_from(value) {
+        let encoded = gwilym_encoding::encode_16(value);
+        f.write_str(unsafe { core::str::from_utf8_unchecked(&encoded) })
+    } else {
+        let encoded = gwilym_encoding::encode_32(value);
+        f.write_str(unsafe { core::str::from_utf8_unchecked(&encoded) })
+    }
+}
+
+/// 32 bit FNV-1a of the panic as it is printed to the log, so the same panic can be recognised
+/// without needing to fit the message in the QR code.
+struct PanicHasher(u32);
+
+impl Default for PanicHasher {
+    fn default() -> Self {
+        Self(0x811c_9dc5)
+    }
+}
+
+impl Write for PanicHasher {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for &byte in s.as_bytes() {
+            self.0 = (self.0 ^ u32::from(byte)).wrapping_mul(0x0100_0193);
+        }
+
+        Ok(())
+    }
+}
+
 mod gwilym_encoding {
     static ALPHABET: &[u8] = b"0123456789=ABCDEFGHIJKLMNOPQRSTUVWXYZ_abcdefghijklmnopqrstuvwxyz";
 
@@ -132,3 +259,23 @@ mod gwilym_encoding {
         ]
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test_case]
+    fn panic_hash_matches_fnv1a(_gba: &mut crate::Gba) {
+        let mut hasher = PanicHasher::default();
+        assert_eq!(hasher.0, 0x811c_9dc5);
+
+        let _ = write!(&mut hasher, "a");
+        assert_eq!(hasher.0, 0xe40c_292c);
+
+        // formatting in pieces hashes the same as writing it all at once
+        let mut hasher = PanicHasher::default();
+        let bar = "bar";
+        let _ = write!(&mut hasher, "foo{bar}");
+        assert_eq!(hasher.0, 0xbf9c_f968);
+    }
+}
@@ -0,0 +1,253 @@
+#![deny(missing_docs)]
+//! Customising what happens when the game crashes.
+//!
+//! By default, a panic takes over the screen to show a QR code of the backtrace along with the
+//! panic message. A shipped game might want to show its own error screen instead, or try to save
+//! the player's progress before giving up. A crash handler set with [`set_crash_handler`] runs
+//! before the default screen, and decides whether it should still be shown.
+//!
+//! With the `backtrace` feature, the crash report can also be written to save media with
+//! [`Crash::write_to_save`] so players can send it in later, even if they didn't take a picture of
+//! the QR code. Read it back with [`read_saved_crash`].
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::crash::{self, Crash, CrashAction};
+//! use agb::Gba;
+//!
+//! // the last 256 bytes of SRAM are kept for crash reports
+//! const CRASH_REPORT: core::ops::Range<usize> = 0x7f00..0x8000;
+//!
+//! fn on_crash(gba: &mut Gba, crash: &Crash<'_>) -> CrashAction {
+//!     gba.save.init_sram();
+//!     if let Ok(mut save) = gba.save.access() {
+//!         let _ = crash.write_to_save(&mut save, CRASH_REPORT);
+//!     }
+//!
+//!     CrashAction::ShowDefaultScreen
+//! }
+//!
+//! # fn foo() {
+//! crash::set_crash_handler(on_crash);
+//! # }
+//! ```
+
+use core::panic::PanicInfo;
+
+use portable_atomic::{AtomicBool, Ordering};
+
+use crate::{sync::Lock, Gba};
+
+#[cfg(feature = "backtrace")]
+use crate::{
+    backtrace,
+    save::{self, SaveData},
+};
+#[cfg(feature = "backtrace")]
+use alloc::{format, string::String, vec};
+#[cfg(feature = "backtrace")]
+use core::ops::Range;
+
+/// A function which is called when the game crashes, see [`set_crash_handler`].
+///
+/// The [`Gba`] it is given is a new one created for the crash, so it can be used to access the
+/// hardware regardless of where the game was when it panicked.
+pub type CrashHandler = fn(&mut Gba, &Crash<'_>) -> CrashAction;
+
+/// What to do once the crash handler has finished.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CrashAction {
+    /// Show the default crash screen with the backtrace and panic message. Without the
+    /// `backtrace` feature there is no default screen, so this is the same as [`Halt`](Self::Halt).
+    ShowDefaultScreen,
+    /// Leave the screen as it is and stop, for when the handler has drawn its own error screen.
+    /// Interrupts keep running.
+    Halt,
+}
+
+/// Details of a crash, given to the crash handler.
+pub struct Crash<'a> {
+    info: &'a PanicInfo<'a>,
+    #[cfg(feature = "backtrace")]
+    frames: &'a backtrace::Frames,
+}
+
+static CRASH_HANDLER: Lock<Option<CrashHandler>> = Lock::new(None);
+static IN_CRASH_HANDLER: AtomicBool = AtomicBool::new(false);
+
+// Written before a crash report in save media, followed by its length as a little endian u16
+#[cfg(feature = "backtrace")]
+const SAVED_CRASH_MAGIC: [u8; 4] = *b"agbc";
+#[cfg(feature = "backtrace")]
+const SAVED_CRASH_HEADER_LEN: usize = SAVED_CRASH_MAGIC.len() + 2;
+
+/// Sets the function which is called when the game crashes, replacing any previous one.
+///
+/// If the handler itself panics, the default crash screen is shown instead of calling it again.
+pub fn set_crash_handler(handler: CrashHandler) {
+    *CRASH_HANDLER.lock() = Some(handler);
+}
+
+/// Removes the crash handler, so crashes go straight to the default crash screen.
+pub fn remove_crash_handler() {
+    *CRASH_HANDLER.lock() = None;
+}
+
+impl<'a> Crash<'a> {
+    pub(crate) fn new(
+        info: &'a PanicInfo<'a>,
+        #[cfg(feature = "backtrace")] frames: &'a backtrace::Frames,
+    ) -> Self {
+        Self {
+            info,
+            #[cfg(feature = "backtrace")]
+            frames,
+        }
+    }
+
+    /// The panic which caused the crash, including its message and location.
+    #[must_use]
+    pub fn panic_info(&self) -> &PanicInfo<'_> {
+        self.info
+    }
+
+    /// The crash report in the same encoding as the default crash screen's QR code, which
+    /// `agb-debug` and the crash website can decode.
+    #[cfg(feature = "backtrace")]
+    #[must_use]
+    pub fn backtrace(&self) -> impl core::fmt::Display + '_ {
+        self.frames.crash_report(self.info)
+    }
+
+    /// Writes the crash report to `range` of the save media so that it can be read back with
+    /// [`read_saved_crash`] the next time the game starts. This erases any other data in the
+    /// sectors which overlap `range`.
+    ///
+    /// Returns [`save::Error::OutOfBounds`] if the report doesn't fit in `range`.
+    #[cfg(feature = "backtrace")]
+    pub fn write_to_save(
+        &self,
+        save: &mut SaveData,
+        range: Range<usize>,
+    ) -> Result<(), save::Error> {
+        write_saved_crash(save, range, &format!("{}", self.backtrace()))
+    }
+}
+
+#[cfg(feature = "backtrace")]
+fn write_saved_crash(
+    save: &mut SaveData,
+    range: Range<usize>,
+    report: &str,
+) -> Result<(), save::Error> {
+    let length = u16::try_from(report.len()).map_err(|_| save::Error::OutOfBounds)?;
+
+    if SAVED_CRASH_HEADER_LEN + report.len() > range.len() {
+        return Err(save::Error::OutOfBounds);
+    }
+
+    let start = range.start;
+    let magic = start..start + SAVED_CRASH_MAGIC.len();
+
+    // SRAM isn't erased by prepare_write, so the magic of an older report has to be cleared first
+    // or a partially written report would still look like a real one. This is its own write so
+    // that flash, which can't be written twice without erasing, is erased again afterwards.
+    save.prepare_write(magic.clone())?
+        .write(start, &[0; SAVED_CRASH_MAGIC.len()])?;
+
+    let mut block = save.prepare_write(range)?;
+    block.write(start + SAVED_CRASH_HEADER_LEN, report.as_bytes())?;
+    block.write(magic.end, &length.to_le_bytes())?;
+    // the magic is written last so a partially written report isn't mistaken for a real one
+    block.write(start, &SAVED_CRASH_MAGIC)
+}
+
+/// Reads a crash report written by [`Crash::write_to_save`] at `offset`, or `None` if there isn't
+/// one there.
+///
+/// The report can be decoded by `agb-debug`, or by the crash website if it's added after
+/// `https://agbrs.dev/crash#`.
+#[cfg(feature = "backtrace")]
+pub fn read_saved_crash(save: &mut SaveData, offset: usize) -> Result<Option<String>, save::Error> {
+    let mut header = [0; SAVED_CRASH_HEADER_LEN];
+    save.read(offset, &mut header)?;
+
+    let (magic, length) = header.split_at(SAVED_CRASH_MAGIC.len());
+    if magic != SAVED_CRASH_MAGIC {
+        return Ok(None);
+    }
+
+    let length = u16::from_le_bytes([length[0], length[1]]) as usize;
+    let mut report = vec![0; length];
+    save.read(offset + SAVED_CRASH_HEADER_LEN, &mut report)?;
+
+    Ok(String::from_utf8(report).ok())
+}
+
+/// Calls the crash handler if there is one, returning what to do next.
+pub(crate) fn run_crash_handler(crash: &Crash<'_>) -> CrashAction {
+    // a panic in the handler would otherwise call it again forever
+    if IN_CRASH_HANDLER.swap(true, Ordering::SeqCst) {
+        return CrashAction::ShowDefaultScreen;
+    }
+
+    let Some(handler) = CRASH_HANDLER.try_lock().and_then(|handler| *handler) else {
+        return CrashAction::ShowDefaultScreen;
+    };
+
+    // SAFETY: This is not fine, but we're crashing anyway and the game won't continue
+    let mut gba = unsafe { Gba::new_in_entry() };
+    handler(&mut gba, crash)
+}
+
+#[cfg(all(test, feature = "backtrace"))]
+mod tests {
+    use super::*;
+
+    const CRASH_REPORT: Range<usize> = 0x7f00..0x8000;
+
+    #[test_case]
+    fn crash_reports_round_trip_through_sram(gba: &mut crate::Gba) {
+        gba.save.init_sram();
+        let mut save = gba.save.access().unwrap();
+
+        write_saved_crash(&mut save, CRASH_REPORT, "a long crash report").unwrap();
+        assert_eq!(
+            read_saved_crash(&mut save, CRASH_REPORT.start).unwrap(),
+            Some("a long crash report".into())
+        );
+
+        write_saved_crash(&mut save, CRASH_REPORT, "short").unwrap();
+        assert_eq!(
+            read_saved_crash(&mut save, CRASH_REPORT.start).unwrap(),
+            Some("short".into())
+        );
+
+        assert!(matches!(
+            write_saved_crash(&mut save, 0..8, "too long"),
+            Err(save::Error::OutOfBounds)
+        ));
+    }
+
+    #[test_case]
+    fn partially_written_crash_reports_are_not_read(gba: &mut crate::Gba) {
+        gba.save.init_sram();
+        let mut save = gba.save.access().unwrap();
+
+        write_saved_crash(&mut save, CRASH_REPORT, "an older crash report").unwrap();
+
+        // stop before anything after the cleared magic is written, as if the power went out
+        save.prepare_write(CRASH_REPORT)
+            .unwrap()
+            .write(CRASH_REPORT.start, &[0; SAVED_CRASH_MAGIC.len()])
+            .unwrap();
+
+        assert_eq!(
+            read_saved_crash(&mut save, CRASH_REPORT.start).unwrap(),
+            None
+        );
+    }
+}
@@ -1,8 +1,13 @@
 use core::fmt::{Error, Write};
 
+use alloc::vec::Vec;
+
 use crate::fixnum::Vector2D;
 use crate::hash_map::HashMap;
 
+pub(crate) mod unicode;
+
+use super::palette16::Palette16;
 use super::tiled::{DynamicTile, RegularMap, VRamManager};
 
 /// The text renderer renders a variable width fixed size
@@ -45,11 +50,12 @@ impl FontLetter {
         }
     }
 
-    pub(crate) const fn bit_absolute(&self, x: usize, y: usize) -> bool {
-        let position = x + y * self.width as usize;
-        let byte = self.data[position / 8];
-        let bit = position % 8;
-        ((byte >> bit) & 1) != 0
+    /// The coverage of the given pixel, between 0 and `(1 << bits_per_pixel) - 1`
+    pub(crate) const fn coverage(&self, x: usize, y: usize, bits_per_pixel: u8) -> u8 {
+        let bit_position = (x + y * self.width as usize) * bits_per_pixel as usize;
+        let byte = self.data[bit_position / 8];
+        let mask = (1 << bits_per_pixel) - 1;
+        (byte >> (bit_position % 8)) & mask
     }
 
     pub(crate) fn kerning_amount(&self, previous_char: char) -> i32 {
@@ -64,10 +70,23 @@ impl FontLetter {
     }
 }
 
+/// The direction text is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextDirection {
+    /// Left to right, as used by most languages
+    #[default]
+    LeftToRight,
+    /// Right to left, as used by Arabic and Hebrew
+    RightToLeft,
+}
+
 pub struct Font {
     letters: &'static [FontLetter],
     line_height: i32,
     ascent: i32,
+    bits_per_pixel: u8,
+    fallback: Option<&'static Font>,
+    replacement_character: Option<char>,
 }
 
 impl Font {
@@ -77,20 +96,161 @@ impl Font {
             letters,
             line_height,
             ascent,
+            bits_per_pixel: 1,
+            fallback: None,
+            replacement_character: None,
         }
     }
 
-    pub(crate) fn letter(&self, letter: char) -> &'static FontLetter {
-        let letter = self
-            .letters
-            .binary_search_by_key(&letter, |letter| letter.character);
+    #[must_use]
+    /// Uses `fallback` to draw any characters which aren't in this font. The fallback font can itself have
+    /// a fallback, so you can build up a chain of fonts, for example to use a separate font for Japanese text.
+    ///
+    /// Letters from the fallback font are drawn on the same baseline as this font, with this font's line height.
+    ///
+    /// ```rust,no_run
+    /// # #![no_std]
+    /// # #![no_main]
+    /// use agb::{display::Font, include_font};
+    ///
+    /// static JAPANESE: Font = include_font!("examples/font/ark-pixel-10px-proportional-ja.ttf", 10);
+    /// static FONT: Font = include_font!("examples/font/yoster.ttf", 12).with_fallback(&JAPANESE);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the fallback font has a different amount of antialiasing to this font.
+    pub const fn with_fallback(self, fallback: &'static Font) -> Self {
+        assert!(
+            fallback.bits_per_pixel == self.bits_per_pixel,
+            "fallback fonts must have the same antialiasing"
+        );
+
+        Self {
+            fallback: Some(fallback),
+            ..self
+        }
+    }
+
+    #[must_use]
+    /// Sets the character to draw in place of any characters which are in neither this font nor its
+    /// fallbacks, such as `'?'` or `'□'`. By default, the first character in the font is used.
+    pub const fn with_replacement_character(self, replacement_character: char) -> Self {
+        Self {
+            replacement_character: Some(replacement_character),
+            ..self
+        }
+    }
 
-        match letter {
-            Ok(index) => &self.letters[index],
-            Err(_) => &self.letters[0],
+    #[must_use]
+    /// Whether this font or one of its fallbacks can draw the given character.
+    pub fn has_letter(&self, letter: char) -> bool {
+        self.find_letter(letter).is_some()
+    }
+
+    #[must_use]
+    /// Sets the number of bits of coverage stored for each pixel of the letters. This is used by
+    /// [`include_font!`](crate::include_font) for antialiased fonts.
+    pub const fn with_bits_per_pixel(self, bits_per_pixel: u8) -> Self {
+        assert!(
+            matches!(bits_per_pixel, 1 | 2 | 4),
+            "bits per pixel must be 1, 2 or 4"
+        );
+
+        Self {
+            bits_per_pixel,
+            ..self
+        }
+    }
+
+    #[must_use]
+    /// The number of palette indices needed to draw this font. This is 1 for fonts which aren't antialiased,
+    /// 3 for fonts with `antialias = 2` and 15 for fonts with `antialias = 4`.
+    ///
+    /// An antialiased font is drawn using a ramp of palette indices which ends at the foreground colour.
+    /// So the faintest pixels are drawn with index `foreground_colour + 1 - colour_ramp_length()` and the
+    /// fully covered pixels with `foreground_colour`. Use [`Font::fill_colour_ramp`] to fill these in.
+    pub const fn colour_ramp_length(&self) -> usize {
+        (1 << self.bits_per_pixel) - 1
+    }
+
+    /// Fills in the palette entries used to draw this font with `foreground_colour` as the foreground index,
+    /// blending from `background` to `foreground` so the text blends smoothly into the given background colour.
+    /// See [`Font::colour_ramp_length`].
+    ///
+    /// ```rust,no_run
+    /// # #![no_std]
+    /// # #![no_main]
+    /// use agb::display::{palette16::Palette16, Font};
+    ///
+    /// static FONT: Font = agb::include_font!("examples/font/yoster.ttf", 12, antialias = 2);
+    ///
+    /// # fn foo() {
+    /// let mut palette = Palette16::new([0; 16]);
+    /// // draws the text in white using palette indices 1, 2 and 3 blending to a black background
+    /// FONT.fill_colour_ramp(&mut palette, 3, 0x7fff, 0x0000);
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ramp doesn't fit in the palette before `foreground_colour`.
+    pub fn fill_colour_ramp(
+        &self,
+        palette: &mut Palette16,
+        foreground_colour: usize,
+        foreground: u16,
+        background: u16,
+    ) {
+        let levels = self.colour_ramp_length();
+        assert!(
+            foreground_colour < 16 && foreground_colour + 1 >= levels,
+            "colour ramp of length {levels} must fit in the palette ending at index {foreground_colour}"
+        );
+
+        for level in 1..=levels {
+            palette.update_colour(
+                foreground_colour + level - levels,
+                blend_colours(background, foreground, level as u16, levels as u16),
+            );
         }
     }
 
+    /// The palette index to draw a pixel with the given coverage, see [`Font::colour_ramp_length`]
+    pub(crate) fn colour_for_coverage(&self, foreground_colour: u8, coverage: u8) -> u8 {
+        let levels = self.colour_ramp_length() as u8;
+        assert!(
+            foreground_colour + 1 >= levels,
+            "foreground colour {foreground_colour} can't fit a colour ramp of length {levels} before it"
+        );
+
+        foreground_colour + coverage - levels
+    }
+
+    pub(crate) fn bits_per_pixel(&self) -> u8 {
+        self.bits_per_pixel
+    }
+
+    fn find_letter(&self, letter: char) -> Option<&'static FontLetter> {
+        match self
+            .letters
+            .binary_search_by_key(&letter, |letter| letter.character)
+        {
+            Ok(index) => Some(&self.letters[index]),
+            Err(_) => self.fallback?.find_letter(letter),
+        }
+    }
+
+    pub(crate) fn letter(&self, letter: char) -> &'static FontLetter {
+        // drawn for letters which aren't in a font with no letters at all
+        static EMPTY_LETTER: FontLetter = FontLetter::new('\0', 0, 0, &[], 0, 0, 0, &[]);
+
+        self.find_letter(letter)
+            .or_else(|| self.find_letter(self.replacement_character?))
+            .or_else(|| self.letters.first())
+            .unwrap_or(&EMPTY_LETTER)
+    }
+
     pub(crate) fn ascent(&self) -> i32 {
         self.ascent
     }
@@ -100,6 +260,19 @@ impl Font {
     }
 }
 
+// Blends each 5 bit component of the two colours by `amount / total`
+fn blend_colours(from: u16, to: u16, amount: u16, total: u16) -> u16 {
+    (0..3)
+        .map(|component| {
+            let shift = component * 5;
+            let from = (from >> shift) & 0x1f;
+            let to = (to >> shift) & 0x1f;
+
+            ((from * (total - amount) + to * amount + total / 2) / total) << shift
+        })
+        .fold(0, |colour, component| colour | component)
+}
+
 impl Font {
     #[must_use]
     /// Create renderer starting at the given tile co-ordinates.
@@ -111,6 +284,28 @@ impl Font {
             font: self,
             tile_pos: tile_pos.into(),
             tiles: Default::default(),
+            right_to_left: None,
+        }
+    }
+
+    #[must_use]
+    /// Create renderer for right to left text such as Arabic and Hebrew, starting at the given tile co-ordinates.
+    /// Each line starts `width` pixels to the right of the starting tile and goes to the left.
+    ///
+    /// Any left to right text, such as numbers, is drawn left to right as part of the line. For this to work, each
+    /// line is only drawn once it has been finished with a newline or the renderer is committed.
+    pub fn render_text_right_to_left(
+        &self,
+        tile_pos: impl Into<Vector2D<u16>>,
+        width: u16,
+    ) -> TextRenderer<'_> {
+        TextRenderer {
+            right_to_left: Some(RightToLeftLine {
+                width: width.into(),
+                line_end: width.into(),
+                pending: Vec::new(),
+            }),
+            ..self.render_text(tile_pos)
         }
     }
 }
@@ -123,6 +318,15 @@ pub struct TextRenderer<'a> {
     font: &'a Font,
     tile_pos: Vector2D<u16>,
     tiles: HashMap<(i32, i32), DynamicTile<'a>>,
+    right_to_left: Option<RightToLeftLine>,
+}
+
+struct RightToLeftLine {
+    width: i32,
+    // where the right of the next text to be drawn on this line goes
+    line_end: i32,
+    // characters in the current line along with their foreground and background colours
+    pending: Vec<(char, u8, u8)>,
 }
 
 /// Generated from the renderer for use
@@ -178,7 +382,8 @@ impl<'a, 'b> TextRenderer<'b> {
     }
 
     /// Renders a single character creating as many dynamic tiles as needed.
-    /// The foreground and background colour are palette indicies.
+    /// The foreground and background colour are palette indicies. Antialiased fonts
+    /// also use the colour ramp before the foreground colour, see [`Font::colour_ramp_length`].
     fn render_letter(
         &mut self,
         letter: &FontLetter,
@@ -189,6 +394,8 @@ impl<'a, 'b> TextRenderer<'b> {
         assert!(foreground_colour < 16);
         assert!(background_colour < 16);
 
+        let bits_per_pixel = self.font.bits_per_pixel();
+
         let x_start = (self.current_x_pos + i32::from(letter.xmin)).max(0);
         let y_start = self.current_y_pos + self.font.ascent
             - i32::from(letter.height)
@@ -218,6 +425,7 @@ impl<'a, 'b> TextRenderer<'b> {
                 let tile_x = x_tile_start + letter_x_tile;
 
                 let mut masks = [0u32; 8];
+                let mut covered = [0u32; 8];
                 let mut zero = true;
 
                 for letter_y in letter_y_start..letter_y_end {
@@ -225,13 +433,14 @@ impl<'a, 'b> TextRenderer<'b> {
 
                     for letter_x in letter_x_start..letter_x_end {
                         let x = letter_x - letter_offset_x;
-                        let pos = x + y * i32::from(letter.width);
-                        let px_line = letter.data[(pos / 8) as usize];
-                        let px = (px_line >> (pos & 7)) & 1;
+                        let coverage = letter.coverage(x as usize, y as usize, bits_per_pixel);
 
-                        if px != 0 {
-                            masks[(letter_y & 7) as usize] |=
-                                u32::from(foreground_colour) << ((letter_x & 7) * 4);
+                        if coverage != 0 {
+                            let colour = self.font.colour_for_coverage(foreground_colour, coverage);
+                            let shift = (letter_x & 7) * 4;
+
+                            masks[(letter_y & 7) as usize] |= u32::from(colour) << shift;
+                            covered[(letter_y & 7) as usize] |= 0xf << shift;
                             zero = false;
                         }
                     }
@@ -243,6 +452,11 @@ impl<'a, 'b> TextRenderer<'b> {
                     });
 
                     for (i, tile_data_line) in tile.tile_data.iter_mut().enumerate() {
+                        // Non antialiased letters get combined with what's already there, but the colour
+                        // ramp of an antialiased font only makes sense if the pixels are replaced.
+                        if bits_per_pixel != 1 {
+                            *tile_data_line &= !covered[i];
+                        }
                         *tile_data_line |= masks[i];
                     }
                 }
@@ -251,7 +465,9 @@ impl<'a, 'b> TextRenderer<'b> {
     }
 
     /// Commit the dynamic tiles that contain the text to the background.
-    pub fn commit(&self, bg: &'a mut RegularMap, vram_manager: &'a mut VRamManager) {
+    pub fn commit(&mut self, bg: &'a mut RegularMap, vram_manager: &'a mut VRamManager) {
+        self.render_right_to_left_line(vram_manager);
+
         for ((x, y), tile) in self.tiles.iter() {
             bg.set_tile(
                 vram_manager,
@@ -271,18 +487,69 @@ impl<'a, 'b> TextRenderer<'b> {
         background_colour: u8,
     ) {
         if c == '\n' {
+            self.render_right_to_left_line(vram_manager);
+            if let Some(line) = &mut self.right_to_left {
+                line.line_end = line.width;
+            }
+
             self.current_y_pos += self.font.line_height;
             self.current_x_pos = 0;
+        } else if let Some(line) = &mut self.right_to_left {
+            line.pending.push((c, foreground_colour, background_colour));
         } else {
-            let letter = self.font.letter(c);
+            self.render_char(c, vram_manager, foreground_colour, background_colour);
+        }
+    }
 
-            if let Some(previous_character) = self.previous_character {
-                self.current_x_pos += letter.kerning_amount(previous_character);
-            }
-            self.previous_character = Some(c);
+    fn render_char(
+        &mut self,
+        c: char,
+        vram_manager: &mut VRamManager,
+        foreground_colour: u8,
+        background_colour: u8,
+    ) {
+        let letter = self.font.letter(c);
+
+        if let Some(previous_character) = self.previous_character {
+            self.current_x_pos += letter.kerning_amount(previous_character);
+        }
+        self.previous_character = Some(c);
+
+        self.render_letter(letter, vram_manager, foreground_colour, background_colour);
+        self.current_x_pos += i32::from(letter.advance_width);
+    }
+
+    fn render_right_to_left_line(&mut self, vram_manager: &mut VRamManager) {
+        let Some(line) = &mut self.right_to_left else {
+            return;
+        };
+
+        let mut pending = core::mem::take(&mut line.pending);
+        if pending.is_empty() {
+            return;
+        }
+
+        unicode::reorder_for_display(&mut pending, TextDirection::RightToLeft, |&(c, _, _)| c);
+
+        let mut previous_character = None;
+        let text_width: i32 = pending
+            .iter()
+            .map(|&(c, _, _)| {
+                let letter = self.font.letter(c);
+                let kerning =
+                    previous_character.map_or(0, |previous| letter.kerning_amount(previous));
+                previous_character = Some(c);
+
+                kerning + i32::from(letter.advance_width)
+            })
+            .sum();
+
+        line.line_end -= text_width;
+        self.current_x_pos = line.line_end;
+        self.previous_character = None;
 
-            self.render_letter(letter, vram_manager, foreground_colour, background_colour);
-            self.current_x_pos += i32::from(letter.advance_width);
+        for (c, foreground_colour, background_colour) in pending {
+            self.render_char(c, vram_manager, foreground_colour, background_colour);
         }
     }
 
@@ -290,6 +557,10 @@ impl<'a, 'b> TextRenderer<'b> {
     pub fn clear(&mut self, vram_manager: &mut VRamManager) {
         self.current_x_pos = 0;
         self.current_y_pos = 0;
+        if let Some(line) = &mut self.right_to_left {
+            line.pending.clear();
+            line.line_end = line.width;
+        }
         let tiles = core::mem::take(&mut self.tiles);
 
         for (_, tile) in tiles.into_iter() {
@@ -360,4 +631,152 @@ mod tests {
             renderer.clear(&mut vram);
         }
     }
+
+    static ANTIALIASED_FONT: Font =
+        crate::include_font!("examples/font/yoster.ttf", 12, antialias = 4);
+
+    #[test_case]
+    fn antialiased_font_stores_coverage(_gba: &mut crate::Gba) {
+        assert_eq!(ANTIALIASED_FONT.colour_ramp_length(), 15);
+
+        let letter = ANTIALIASED_FONT.letter('a');
+        let mut seen_partial_coverage = false;
+        for y in 0..letter.height as usize {
+            for x in 0..letter.width as usize {
+                let coverage = letter.coverage(x, y, 4);
+                seen_partial_coverage |= coverage != 0 && coverage != 15;
+
+                // 1bpp fonts threshold the coverage, so should roughly agree
+                let solid = FONT.letter('a').coverage(x, y, 1) != 0;
+                assert!(!solid || coverage > 4);
+            }
+        }
+
+        assert!(seen_partial_coverage);
+    }
+
+    #[test_case]
+    fn charset_only_includes_given_characters(_gba: &mut crate::Gba) {
+        static SUBSET_FONT: Font =
+            crate::include_font!("examples/font/yoster.ttf", 12, charset = "Hello");
+
+        let characters: alloc::vec::Vec<_> = SUBSET_FONT
+            .letters
+            .iter()
+            .map(|letter| letter.character)
+            .collect();
+        assert_eq!(characters, [' ', 'H', 'e', 'l', 'o']);
+    }
+
+    #[test_case]
+    fn bitmap_fonts_load(_gba: &mut crate::Gba) {
+        static BDF_FONT: Font = crate::include_font!("examples/font/test-font.bdf");
+        static GRID_FONT: Font = crate::include_font!(
+            "examples/font/test-grid-font.png",
+            charset = "Hi!",
+            cell_width = 6,
+            cell_height = 8,
+            baseline = 5
+        );
+
+        let g = BDF_FONT.letter('g');
+        assert_eq!((g.width, g.height, g.ymin, g.advance_width), (4, 5, -2, 5));
+        assert_eq!(BDF_FONT.line_height(), 8);
+
+        let h = GRID_FONT.letter('H');
+        assert_eq!((h.width, h.height, h.ymin, h.advance_width), (5, 5, 0, 6));
+        assert!(h.coverage(0, 0, 1) != 0 && h.coverage(1, 0, 1) == 0);
+        assert_eq!(GRID_FONT.letter(' ').advance_width, 3);
+    }
+
+    #[test_case]
+    fn missing_letters_use_fallback_fonts(_gba: &mut crate::Gba) {
+        static BDF_FONT: Font = crate::include_font!("examples/font/test-font.bdf");
+        static FALLBACK_FONT: Font =
+            crate::include_font!("examples/font/yoster.ttf", 12, charset = "Hb?")
+                .with_fallback(&BDF_FONT)
+                .with_replacement_character('?');
+
+        assert_eq!(FALLBACK_FONT.letter('H').character, 'H');
+        assert_eq!(FALLBACK_FONT.letter('g').character, 'g');
+        assert_eq!(FALLBACK_FONT.letter('A').character, 'A');
+        assert_eq!(FALLBACK_FONT.letter('z').character, '?');
+
+        assert!(FALLBACK_FONT.has_letter('g'));
+        assert!(!FALLBACK_FONT.has_letter('z'));
+        assert!(!BDF_FONT.has_letter('H'));
+    }
+
+    #[test_case]
+    fn fonts_with_no_letters_draw_nothing(_gba: &mut crate::Gba) {
+        static EMPTY_FONT: Font = Font::new(&[], 8, 6);
+
+        let letter = EMPTY_FONT.letter('a');
+        assert_eq!(
+            (letter.width, letter.height, letter.advance_width),
+            (0, 0, 0)
+        );
+    }
+
+    #[test_case]
+    fn right_to_left_text_is_drawn_from_the_right(gba: &mut crate::Gba) {
+        let (gfx, mut vram) = gba.display.video.tiled0();
+        let mut bg = gfx.background(
+            crate::display::Priority::P0,
+            crate::display::tiled::RegularBackgroundSize::Background32x32,
+            TileFormat::FourBpp,
+        );
+
+        let mut renderer = FONT.render_text_right_to_left((0u16, 0u16), 64);
+        let mut writer = renderer.writer(1, 0, &mut bg, &mut vram);
+        write!(&mut writer, "I").unwrap();
+        writer.commit();
+
+        // a single narrow letter should only use tiles at the right hand edge
+        assert!(!renderer.tiles.is_empty());
+        assert!(renderer.tiles.keys().all(|&(x, _)| x == 7));
+
+        renderer.clear(&mut vram);
+    }
+
+    #[test_case]
+    fn translations_set_font_charset(_gba: &mut crate::Gba) {
+        crate::include_strings!(strings, "examples/strings/strings.csv");
+        static FRENCH_FONT: Font = crate::include_font!(
+            "examples/font/yoster.ttf",
+            12,
+            strings = "examples/strings/strings.csv",
+            language = "fr"
+        );
+
+        let characters: alloc::string::String = FRENCH_FONT
+            .letters
+            .iter()
+            .map(|letter| letter.character)
+            .collect();
+        assert_eq!(characters, strings::Language::Fr.charset());
+
+        let mut greeting = alloc::string::String::new();
+        strings::write_text(
+            &mut greeting,
+            strings::Language::Fr,
+            strings::StringId::Greeting,
+            &[("name", &"agb")],
+        )
+        .unwrap();
+        assert_eq!(greeting, "Bonjour, agb !");
+    }
+
+    #[test_case]
+    fn colour_ramp_blends_to_background(_gba: &mut crate::Gba) {
+        let mut palette = Palette16::new([0; 16]);
+        ANTIALIASED_FONT.fill_colour_ramp(&mut palette, 15, 0x7fff, 0x0000);
+
+        assert_eq!(palette.colour(0), 0);
+        assert_eq!(palette.colour(15), 0x7fff);
+        assert_eq!(palette.colour(8), 17 | 17 << 5 | 17 << 10);
+
+        assert_eq!(ANTIALIASED_FONT.colour_for_coverage(15, 1), 1);
+        assert_eq!(FONT.colour_for_coverage(4, 1), 4);
+    }
 }
@@ -0,0 +1,296 @@
+//! Just enough of the unicode line breaking (UAX #14) and bidirectional (UAX #9) algorithms to lay
+//! out text in the most common languages. There is no support for shaping, so Arabic will be drawn
+//! using whatever form of each letter is in the font.
+
+use super::TextDirection;
+
+/// A zero width space, which marks somewhere a line is allowed to break
+pub(crate) const ZERO_WIDTH_SPACE: char = '\u{200B}';
+
+/// Hebrew, Arabic, Syriac, Thaana, N'Ko and the Arabic presentation forms
+pub(crate) fn is_right_to_left(c: char) -> bool {
+    matches!(c, '\u{0590}'..='\u{08FF}' | '\u{FB1D}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFF}')
+}
+
+fn is_left_to_right(c: char) -> bool {
+    c.is_alphabetic() && !is_right_to_left(c)
+}
+
+// Numbers are always displayed left to right, even in right to left text
+fn is_left_to_right_run(c: char) -> bool {
+    c.is_ascii_digit() || is_left_to_right(c)
+}
+
+/// The direction of the first strongly directional character, which decides the direction of a paragraph
+pub(crate) fn direction_of(text: impl IntoIterator<Item = char>) -> Option<TextDirection> {
+    text.into_iter().find_map(|c| {
+        if is_right_to_left(c) {
+            Some(TextDirection::RightToLeft)
+        } else if is_left_to_right(c) {
+            Some(TextDirection::LeftToRight)
+        } else {
+            None
+        }
+    })
+}
+
+/// The direction of a single word, where words made up only of numbers are left to right
+pub(crate) fn word_direction(
+    word: impl IntoIterator<Item = char> + Clone,
+) -> Option<TextDirection> {
+    direction_of(word.clone()).or_else(|| {
+        word.into_iter()
+            .any(|c| c.is_ascii_digit())
+            .then_some(TextDirection::LeftToRight)
+    })
+}
+
+/// Reorders text stored in logical order into the order it should be drawn from left to right, given
+/// the direction of the paragraph it is in. Runs of right to left text (with the punctuation and spaces
+/// between them) are reversed in left to right paragraphs, and the opposite for right to left paragraphs.
+pub(crate) fn reorder_for_display<T>(
+    text: &mut [T],
+    direction: TextDirection,
+    char_of: impl Fn(&T) -> char,
+) {
+    if !text.iter().any(|c| is_right_to_left(char_of(c))) {
+        return;
+    }
+
+    match direction {
+        TextDirection::LeftToRight => {
+            reverse_runs(
+                text,
+                |c| is_right_to_left(char_of(c)),
+                |c| is_left_to_right_run(char_of(c)),
+            );
+        }
+        TextDirection::RightToLeft => {
+            text.reverse();
+            reverse_runs(
+                text,
+                |c| is_left_to_right_run(char_of(c)),
+                |c| is_right_to_left(char_of(c)),
+            );
+        }
+    }
+}
+
+// Reverses each run which starts and ends with a character matching `in_run` and doesn't contain
+// any characters matching `ends_run`.
+fn reverse_runs<T>(text: &mut [T], in_run: impl Fn(&T) -> bool, ends_run: impl Fn(&T) -> bool) {
+    let mut start = 0;
+    while start < text.len() {
+        if !in_run(&text[start]) {
+            start += 1;
+            continue;
+        }
+
+        let mut end = start;
+        for (i, c) in text.iter().enumerate().skip(start + 1) {
+            if ends_run(c) {
+                break;
+            }
+            if in_run(c) {
+                end = i;
+            }
+        }
+
+        text[start..=end].reverse();
+        start = end + 1;
+    }
+}
+
+fn is_ideographic(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{11FF}' // hangul jamo
+        | '\u{2E80}'..='\u{2FFF}' // cjk radicals
+        | '\u{3000}'..='\u{9FFF}' // cjk symbols and punctuation, kana, cjk ideographs
+        | '\u{AC00}'..='\u{D7AF}' // hangul syllables
+        | '\u{F900}'..='\u{FAFF}' // cjk compatibility ideographs
+        | '\u{FF00}'..='\u{FFEF}' // halfwidth and fullwidth forms
+        | '\u{20000}'..='\u{3FFFF}' // supplementary ideographs
+    )
+}
+
+// Closing punctuation and characters which can't start a line, such as small kana
+fn prohibits_break_before(c: char) -> bool {
+    matches!(
+        c,
+        '!' | ')'
+            | ','
+            | '.'
+            | ':'
+            | ';'
+            | '?'
+            | ']'
+            | '}'
+            | '…'
+            | '‥'
+            | '、'
+            | '。'
+            | '〉'
+            | '》'
+            | '」'
+            | '』'
+            | '】'
+            | '〕'
+            | '〗'
+            | '〙'
+            | '〛'
+            | '〞'
+            | '・'
+            | 'ー'
+            | '々'
+            | '〻'
+            | 'ぁ'
+            | 'ぃ'
+            | 'ぅ'
+            | 'ぇ'
+            | 'ぉ'
+            | 'っ'
+            | 'ゃ'
+            | 'ゅ'
+            | 'ょ'
+            | 'ゎ'
+            | 'ゕ'
+            | 'ゖ'
+            | 'ゝ'
+            | 'ゞ'
+            | 'ァ'
+            | 'ィ'
+            | 'ゥ'
+            | 'ェ'
+            | 'ォ'
+            | 'ッ'
+            | 'ャ'
+            | 'ュ'
+            | 'ョ'
+            | 'ヮ'
+            | 'ヵ'
+            | 'ヶ'
+            | 'ヽ'
+            | 'ヾ'
+            | '！'
+            | '）'
+            | '，'
+            | '．'
+            | '：'
+            | '；'
+            | '？'
+            | '］'
+            | '｝'
+            | '｡'
+            | '｣'
+            | '､'
+            | '･'
+            | 'ｰ'
+    )
+}
+
+// Opening punctuation
+fn prohibits_break_after(c: char) -> bool {
+    matches!(
+        c,
+        '(' | '['
+            | '{'
+            | '¡'
+            | '¿'
+            | '〈'
+            | '《'
+            | '「'
+            | '『'
+            | '【'
+            | '〔'
+            | '〖'
+            | '〘'
+            | '〚'
+            | '〝'
+            | '（'
+            | '［'
+            | '｛'
+            | '｢'
+    )
+}
+
+/// Whether a line can be broken between these two characters, which aren't separated by a space.
+/// Lines can break either side of CJK characters, except before closing punctuation or after opening
+/// punctuation.
+pub(crate) fn can_break_between(before: char, after: char) -> bool {
+    if prohibits_break_after(before) || prohibits_break_before(after) {
+        return false;
+    }
+
+    before == ZERO_WIDTH_SPACE || is_ideographic(before) || is_ideographic(after)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Gba;
+    use alloc::{string::String, vec::Vec};
+
+    fn reordered(text: &str, direction: TextDirection) -> String {
+        let mut chars: Vec<_> = text.chars().collect();
+        reorder_for_display(&mut chars, direction, |&c| c);
+        chars.into_iter().collect()
+    }
+
+    #[test_case]
+    fn left_to_right_text_is_unchanged(_gba: &mut Gba) {
+        assert_eq!(
+            reordered("Hello, world!", TextDirection::LeftToRight),
+            "Hello, world!"
+        );
+        assert_eq!(
+            reordered("Hello, world!", TextDirection::RightToLeft),
+            "Hello, world!"
+        );
+    }
+
+    #[test_case]
+    fn right_to_left_text_is_reversed(_gba: &mut Gba) {
+        assert_eq!(reordered("שלום!", TextDirection::RightToLeft), "!םולש");
+        assert_eq!(
+            reordered("שלום 123 עולם", TextDirection::RightToLeft),
+            "םלוע 123 םולש"
+        );
+        assert_eq!(
+            reordered("I said שלום עולם!", TextDirection::LeftToRight),
+            "I said םלוע םולש!"
+        );
+        assert_eq!(
+            reordered("אני Super Mario", TextDirection::RightToLeft),
+            "Super Mario ינא"
+        );
+    }
+
+    #[test_case]
+    fn paragraph_direction_uses_first_strong_character(_gba: &mut Gba) {
+        assert_eq!(
+            direction_of("123 שלום abc".chars()),
+            Some(TextDirection::RightToLeft)
+        );
+        assert_eq!(
+            direction_of("(abc) שלום".chars()),
+            Some(TextDirection::LeftToRight)
+        );
+        assert_eq!(direction_of("123 !".chars()), None);
+        assert_eq!(
+            word_direction("123".chars()),
+            Some(TextDirection::LeftToRight)
+        );
+        assert_eq!(word_direction("!".chars()), None);
+    }
+
+    #[test_case]
+    fn line_breaks_follow_cjk_rules(_gba: &mut Gba) {
+        assert!(can_break_between('日', '本'));
+        assert!(can_break_between('a', '日'));
+        assert!(!can_break_between('a', 'b'));
+        assert!(!can_break_between('本', '。'));
+        assert!(!can_break_between('「', '日'));
+        assert!(!can_break_between('か', 'っ'));
+        assert!(can_break_between(ZERO_WIDTH_SPACE, 'a'));
+    }
+}
@@ -8,6 +8,7 @@ use video::Video;
 use self::{
     blend::Blend,
     object::{initilise_oam, OamManaged, OamUnmanaged, SpriteLoader},
+    upload::UploadDist,
     window::Windows,
 };
 
@@ -24,6 +25,7 @@ pub mod palette16;
 pub mod tile_data;
 /// Graphics mode 0. Four regular backgrounds.
 pub mod tiled;
+pub mod upload;
 /// Giving out graphics mode.
 pub mod video;
 
@@ -32,7 +34,7 @@ pub mod blend;
 pub mod window;
 
 pub mod font;
-pub use font::{Font, FontLetter};
+pub use font::{Font, FontLetter, TextDirection};
 
 const DISPLAY_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0000) };
 pub(crate) const DISPLAY_STATUS: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0004) };
@@ -78,6 +80,7 @@ pub struct Display {
     pub object: ObjectDistribution,
     pub window: WindowDist,
     pub blend: BlendDist,
+    pub upload: UploadDist,
 }
 
 #[non_exhaustive]
@@ -127,6 +130,7 @@ impl Display {
             object: ObjectDistribution,
             window: WindowDist,
             blend: BlendDist,
+            upload: UploadDist,
         }
     }
 }
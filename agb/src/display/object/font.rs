@@ -1,12 +1,15 @@
 use core::fmt::{Display, Write};
 
 use agb_fixnum::{Num, Vector2D};
-use alloc::{collections::VecDeque, vec::Vec};
+use alloc::{collections::VecDeque, string::String, vec::Vec};
 
-use crate::display::Font;
+use crate::display::{
+    font::unicode::{self, ZERO_WIDTH_SPACE},
+    Font, TextDirection,
+};
 
 use self::{
-    preprocess::{Line, Preprocessed, PreprocessedElement},
+    preprocess::{parse_tag, Line, Preprocessed, PreprocessedElement, Tag},
     renderer::{Configuration, WordRender},
 };
 
@@ -20,6 +23,8 @@ mod renderer;
 pub(crate) enum WhiteSpace {
     NewLine,
     Space,
+    /// Somewhere a line can break without a space, such as between CJK characters
+    BreakOpportunity,
 }
 
 impl WhiteSpace {
@@ -27,28 +32,136 @@ impl WhiteSpace {
         match c {
             ' ' => WhiteSpace::Space,
             '\n' => WhiteSpace::NewLine,
+            ZERO_WIDTH_SPACE => WhiteSpace::BreakOpportunity,
             _ => panic!("char not supported whitespace"),
         }
     }
 }
 
+/// An animation applied to each letter, set with the `{wave}` and `{shake}` tags
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub(crate) enum TextEffect {
+    #[default]
+    None,
+    Wave,
+    Shake,
+}
+
+impl TextEffect {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '\u{E010}' => Some(TextEffect::None),
+            '\u{E011}' => Some(TextEffect::Wave),
+            '\u{E012}' => Some(TextEffect::Shake),
+            _ => None,
+        }
+    }
+
+    fn to_char(self) -> char {
+        match self {
+            TextEffect::None => '\u{E010}',
+            TextEffect::Wave => '\u{E011}',
+            TextEffect::Shake => '\u{E012}',
+        }
+    }
+
+    fn offset(self, frame: u32, index: usize) -> Vector2D<i32> {
+        match self {
+            TextEffect::None => (0, 0).into(),
+            TextEffect::Wave => {
+                let phase: Num<i32, 8> =
+                    Num::new((frame % 32) as i32) / 32 + Num::new((index % 8) as i32) / 8;
+                (0, (phase.sin() * 2).floor()).into()
+            }
+            TextEffect::Shake => {
+                let seed = (frame / 4).wrapping_mul(0x9E37_79B9)
+                    ^ (index as u32).wrapping_mul(0x85EB_CA6B);
+                let seed = seed ^ (seed >> 15);
+                ((seed % 3) as i32 - 1, ((seed >> 8) % 3) as i32 - 1).into()
+            }
+        }
+    }
+}
+
+// Marks where the next command from `BufferedRender::commands` should run
+const COMMAND: char = '\u{E020}';
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+    Pause(u16),
+    Speed(u16),
+    Icon(usize),
+    Tag(TextEvent),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+/// Something which happened while text was being revealed by [`ObjectTextRender::tick`].
+pub enum TextEvent {
+    /// A `{pause:frames}` tag was reached
+    Pause {
+        /// The number of frames until the next letter will be shown
+        frames: u16,
+    },
+    /// A tag which isn't one of the built in tags was reached, such as `{sound:beep}` or `{portrait}`
+    Tag {
+        /// The part of the tag before the `:`
+        name: String,
+        /// The part of the tag after the `:`, if there is one
+        argument: Option<String>,
+    },
+}
+
+struct WordItem {
+    character: char,
+    colour: u8,
+    effect: TextEffect,
+    command: Option<Command>,
+}
+
 struct BufferedRender<'font> {
     char_render: WordRender,
     preprocessor: Preprocessed,
     buffered_chars: VecDeque<char>,
     letters: Letters,
     font: &'font Font,
+    icons: Vec<(&'static str, SpriteVram)>,
+
+    // the word currently being written, which can only be laid out once it is complete
+    word: Vec<WordItem>,
+    previous_character: Option<char>,
+    input_colour: u8,
+    output_colour: u8,
+    input_effect: TextEffect,
+    output_effect: TextEffect,
+    paragraph_direction: Option<TextDirection>,
+    // the contents of the markup tag currently being written
+    tag: Option<String>,
+
+    commands: VecDeque<Command>,
+    // commands which have been processed which will run before the next letter group is shown
+    pending_commands: Vec<Command>,
+    render_effect: TextEffect,
 }
 
 #[derive(Debug, Default)]
 struct Letters {
     letters: VecDeque<SpriteVram>,
+    // the effect of and commands to run before each letter group, in the same order as letters
+    groups: VecDeque<LetterGroupInfo>,
     number_of_groups: usize,
 }
 
+#[derive(Debug, Default)]
+struct LetterGroupInfo {
+    effect: TextEffect,
+    commands: Vec<Command>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 #[non_exhaustive]
-/// The text alignment of the layout
+/// The text alignment of the layout. In right to left paragraphs, such as those in Arabic or Hebrew,
+/// the alignment is mirrored so [`Left`][TextAlignment::Left] aligns the text to the right edge.
 pub enum TextAlignment {
     #[default]
     /// Left aligned, the left edge of the text lines up
@@ -108,6 +221,18 @@ impl<'font> BufferedRender<'font> {
             buffered_chars: VecDeque::new(),
             letters: Default::default(),
             font,
+            icons: Vec::new(),
+            word: Vec::new(),
+            previous_character: None,
+            input_colour: 1,
+            output_colour: 1,
+            input_effect: TextEffect::None,
+            output_effect: TextEffect::None,
+            paragraph_direction: None,
+            tag: None,
+            commands: VecDeque::new(),
+            pending_commands: Vec::new(),
+            render_effect: TextEffect::None,
         }
     }
 }
@@ -172,31 +297,204 @@ impl Display for ChangeColour {
 }
 
 impl BufferedRender<'_> {
-    fn input_character(&mut self, character: char) {
-        if !is_private_use(character) {
-            self.preprocessor
-                .add_character(self.font, character, self.char_render.sprite_width());
+    fn input_character(&mut self, character: char) -> core::fmt::Result {
+        if let Some(tag) = &mut self.tag {
+            match character {
+                // {{ is an escaped {
+                '{' if tag.is_empty() => {
+                    self.tag = None;
+                    self.input_letter('{');
+                }
+                '}' => {
+                    let tag = self.tag.take().unwrap_or_default();
+                    self.input_tag(&tag)?;
+                }
+                c => tag.push(c),
+            }
+
+            return Ok(());
+        }
+
+        if character == '{' {
+            self.tag = Some(String::new());
+            return Ok(());
+        }
+
+        if is_private_use(character) {
+            if let Some(colour) = ChangeColour::try_from_char(character) {
+                self.input_colour = colour.0;
+            }
+            return Ok(());
+        }
+
+        match character {
+            ' ' | '\n' | ZERO_WIDTH_SPACE => {
+                self.finish_word();
+                self.output_character(character);
+                self.previous_character = None;
+
+                if character == '\n' {
+                    self.paragraph_direction = None;
+                }
+            }
+            letter => self.input_letter(letter),
+        }
+
+        Ok(())
+    }
+
+    fn input_letter(&mut self, character: char) {
+        if let Some(previous_character) = self.previous_character {
+            if unicode::can_break_between(previous_character, character) {
+                self.finish_word();
+                self.output_character(ZERO_WIDTH_SPACE);
+            }
         }
+
+        self.word.push(WordItem {
+            character,
+            colour: self.input_colour,
+            effect: self.input_effect,
+            command: None,
+        });
+        self.previous_character = Some(character);
+    }
+
+    // Fails if the tag has an invalid argument or is for an icon which hasn't been added
+    fn input_tag(&mut self, tag: &str) -> core::fmt::Result {
+        let command = match parse_tag(tag).ok_or(core::fmt::Error)? {
+            Tag::Colour(colour) => {
+                self.input_colour = colour;
+                return Ok(());
+            }
+            Tag::Effect(effect) => {
+                self.input_effect = effect;
+                return Ok(());
+            }
+            Tag::Pause(frames) => Command::Pause(frames),
+            Tag::Speed(frames) => Command::Speed(frames),
+            Tag::Icon(name) => Command::Icon(
+                self.icons
+                    .iter()
+                    .position(|&(icon, _)| icon == name)
+                    .ok_or(core::fmt::Error)?,
+            ),
+            Tag::Custom { name, argument } => Command::Tag(TextEvent::Tag {
+                name: name.into(),
+                argument: argument.map(Into::into),
+            }),
+        };
+
+        self.word.push(WordItem {
+            character: COMMAND,
+            colour: self.input_colour,
+            effect: self.input_effect,
+            command: Some(command),
+        });
+
+        Ok(())
+    }
+
+    // Reorders the current word for display and sends it on to be laid out and rendered
+    fn finish_word(&mut self) {
+        if self.word.is_empty() {
+            return;
+        }
+
+        let word_direction = unicode::word_direction(self.word.iter().map(|item| item.character));
+        if self.paragraph_direction.is_none() {
+            if let Some(direction) =
+                unicode::direction_of(self.word.iter().map(|item| item.character))
+            {
+                self.paragraph_direction = Some(direction);
+                self.preprocessor.set_paragraph_direction(direction);
+            }
+        }
+
+        let paragraph_direction = self.paragraph_direction.unwrap_or_default();
+        unicode::reorder_for_display(&mut self.word, paragraph_direction, |item| item.character);
+        self.preprocessor
+            .start_word(word_direction.is_some_and(|direction| direction != paragraph_direction));
+
+        let mut word = core::mem::take(&mut self.word);
+        for item in word.drain(..) {
+            if item.colour != self.output_colour {
+                self.buffered_chars
+                    .push_back(ChangeColour::new(item.colour.into()).to_char());
+                self.output_colour = item.colour;
+            }
+
+            if item.effect != self.output_effect {
+                self.preprocessor
+                    .set_per_letter(item.effect != TextEffect::None);
+                self.buffered_chars.push_back(item.effect.to_char());
+                self.output_effect = item.effect;
+            }
+
+            match item.command {
+                Some(command) => {
+                    if let Command::Icon(icon) = command {
+                        let width = self.icons[icon].1.size().to_width_height().0;
+                        self.preprocessor.add_icon(width as i32);
+                    } else {
+                        self.preprocessor.finish_group();
+                    }
+
+                    self.commands.push_back(command);
+                    self.buffered_chars.push_back(COMMAND);
+                }
+                None => self.output_character(item.character),
+            }
+        }
+
+        self.word = word;
+    }
+
+    fn output_character(&mut self, character: char) {
+        self.preprocessor
+            .add_character(self.font, character, self.char_render.sprite_width());
         self.buffered_chars.push_back(character);
     }
 
+    fn push_group(&mut self, group: SpriteVram) {
+        self.letters.letters.push_back(group);
+        self.letters.groups.push_back(LetterGroupInfo {
+            effect: self.render_effect,
+            commands: core::mem::take(&mut self.pending_commands),
+        });
+        self.letters.number_of_groups += 1;
+    }
+
+    fn finish_group(&mut self) {
+        if let Some(group) = self.char_render.finalise_letter() {
+            self.push_group(group);
+        }
+    }
+
     fn process(&mut self) {
         let Some(c) = self.buffered_chars.pop_front() else {
             return;
         };
         match c {
-            ' ' | '\n' => {
-                if let Some(group) = self.char_render.finalise_letter() {
-                    self.letters.letters.push_back(group);
-                    self.letters.number_of_groups += 1;
-                }
-
+            ' ' | '\n' | ZERO_WIDTH_SPACE => {
+                self.finish_group();
                 self.letters.number_of_groups += 1;
             }
+            COMMAND => {
+                self.finish_group();
+                match self.commands.pop_front() {
+                    Some(Command::Icon(icon)) => self.push_group(self.icons[icon].1.clone()),
+                    Some(command) => self.pending_commands.push(command),
+                    None => {}
+                }
+            }
             letter => {
-                if let Some(group) = self.char_render.render_char(self.font, letter) {
-                    self.letters.letters.push_back(group);
-                    self.letters.number_of_groups += 1;
+                if let Some(effect) = TextEffect::from_char(letter) {
+                    self.finish_group();
+                    self.render_effect = effect;
+                    self.char_render.set_per_letter(effect != TextEffect::None);
+                } else if let Some(group) = self.char_render.render_char(self.font, letter) {
+                    self.push_group(group);
                 }
             }
         }
@@ -204,7 +502,25 @@ impl BufferedRender<'_> {
 }
 
 /// The object text renderer. Uses objects to render and layout text. It's use is non trivial.
-/// Changes the palette to use to draw characters.
+///
+/// Lines are broken at spaces, and also between CJK characters following the usual rules for
+/// punctuation. A zero width space (`'\u{200B}'`) can be used to allow a break anywhere else.
+/// Paragraphs of Arabic or Hebrew are laid out right to left, but letters are not joined together
+/// so the font should contain the forms of each letter you need.
+///
+/// Text can contain markup tags in braces, and `{{` writes a literal `{`:
+///
+/// * `{c:2}` draws the following text with palette colour 2, and `{/c}` goes back to colour 1.
+/// * `{wave}` and `{shake}` animate each following letter until `{/wave}` or `{/shake}`. Each animated
+///   letter uses an object of its own, so use these sparingly.
+/// * `{icon:name}` draws a sprite added with [`add_icon`][ObjectTextRender::add_icon] inline with the text.
+/// * `{pause:30}` waits 30 frames and `{speed:2}` shows a new letter group every 2 frames (or all at once
+///   for `{speed:0}`) when text is revealed with [`tick`][ObjectTextRender::tick].
+/// * Any other tag, such as `{sound:beep}`, is returned by [`next_event`][ObjectTextRender::next_event] when
+///   [`tick`][ObjectTextRender::tick] reaches it.
+///
+/// Writing a built in tag with an invalid argument, such as `{c:16}` or `{pause:abc}`, returns an error.
+///
 /// ```rust,no_run
 /// #![no_std]
 /// #![no_main]
@@ -244,6 +560,14 @@ pub struct ObjectTextRender<'font> {
     buffer: BufferedRender<'font>,
     layout: LayoutCache,
     number_of_objects: usize,
+    typewriter: Typewriter,
+    frame: u32,
+}
+
+struct Typewriter {
+    frames_per_group: u16,
+    frames_until_next: u16,
+    events: VecDeque<TextEvent>,
 }
 
 impl<'font> ObjectTextRender<'font> {
@@ -255,10 +579,17 @@ impl<'font> ObjectTextRender<'font> {
         Self {
             buffer: BufferedRender::new(font, sprite_size, palette),
             number_of_objects: 0,
+            typewriter: Typewriter {
+                frames_per_group: 1,
+                frames_until_next: 0,
+                events: VecDeque::new(),
+            },
+            frame: 0,
             layout: LayoutCache {
                 positions: VecDeque::new(),
                 line_capacity: VecDeque::new(),
                 objects: Vec::new(),
+                effects: Vec::new(),
                 objects_are_at_origin: (0, 0).into(),
                 area: (0, 0).into(),
             },
@@ -269,7 +600,7 @@ impl<'font> ObjectTextRender<'font> {
 impl Write for ObjectTextRender<'_> {
     fn write_str(&mut self, s: &str) -> core::fmt::Result {
         for c in s.chars() {
-            self.buffer.input_character(c);
+            self.buffer.input_character(c)?;
         }
 
         Ok(())
@@ -277,13 +608,90 @@ impl Write for ObjectTextRender<'_> {
 }
 
 impl ObjectTextRender<'_> {
+    /// Adds a sprite which can be drawn inline with the text using `{icon:name}`. Icons must be
+    /// added before any text which uses them is written, and writing an `{icon:name}` tag for an
+    /// icon which hasn't been added returns an error.
+    pub fn add_icon(&mut self, name: &'static str, sprite: SpriteVram) {
+        self.buffer.icons.push((name, sprite));
+    }
+
     /// Commits work already done to screen. You can commit to multiple places in the same frame.
     pub fn commit(&mut self, oam: &mut OamIterator) {
-        for (object, slot) in self.layout.objects.iter().zip(oam) {
-            slot.set(object);
+        for (index, ((object, effect), slot)) in self
+            .layout
+            .objects
+            .iter()
+            .zip(&self.layout.effects)
+            .zip(oam)
+            .enumerate()
+        {
+            if *effect == TextEffect::None {
+                slot.set(object);
+            } else {
+                let mut object = object.clone();
+                object.set_position(object.position() + effect.offset(self.frame, index));
+                slot.set(&object);
+            }
         }
     }
 
+    /// Reveals the text like a typewriter, showing the next letter group at the speed set by `{speed}` tags and
+    /// waiting at `{pause}` tags. Call this once per frame instead of
+    /// [`next_letter_group`][ObjectTextRender::next_letter_group], and then call [`update`][ObjectTextRender::update].
+    ///
+    /// Pauses and custom tags which are reached can be found with [`next_event`][ObjectTextRender::next_event].
+    pub fn tick(&mut self) {
+        loop {
+            if self.typewriter.frames_until_next > 0 {
+                self.typewriter.frames_until_next -= 1;
+                return;
+            }
+
+            let next = self.number_of_objects;
+            self.at_least_n_letter_groups(next);
+
+            let can_render_another_element = self.can_render_another_element();
+            let commands = match self.buffer.letters.groups.get_mut(next) {
+                Some(_) if !can_render_another_element => return,
+                Some(group) => core::mem::take(&mut group.commands),
+                // commands after the last letter group run once it has been shown
+                None if self.buffer.buffered_chars.is_empty() => {
+                    core::mem::take(&mut self.buffer.pending_commands)
+                }
+                None => Vec::new(),
+            };
+
+            for command in commands {
+                match command {
+                    Command::Pause(frames) => {
+                        self.typewriter.frames_until_next =
+                            self.typewriter.frames_until_next.saturating_add(frames);
+                        self.typewriter
+                            .events
+                            .push_back(TextEvent::Pause { frames });
+                    }
+                    Command::Speed(frames) => self.typewriter.frames_per_group = frames,
+                    Command::Tag(event) => self.typewriter.events.push_back(event),
+                    Command::Icon(_) => {}
+                }
+            }
+
+            if self.typewriter.frames_until_next > 0 || !self.next_letter_group() {
+                return;
+            }
+
+            if self.typewriter.frames_per_group != 0 {
+                self.typewriter.frames_until_next = self.typewriter.frames_per_group - 1;
+                return;
+            }
+        }
+    }
+
+    /// The next pause or custom tag reached by [`tick`][ObjectTextRender::tick], in the order they were reached.
+    pub fn next_event(&mut self) -> Option<TextEvent> {
+        self.typewriter.events.pop_front()
+    }
+
     /// Force a relayout, must be called after writing.
     pub fn layout(
         &mut self,
@@ -291,6 +699,8 @@ impl ObjectTextRender<'_> {
         alignment: TextAlignment,
         paragraph_spacing: i32,
     ) {
+        self.buffer.finish_word();
+
         self.layout.create_positions(
             self.buffer.font,
             &self.buffer.preprocessor,
@@ -314,10 +724,12 @@ impl ObjectTextRender<'_> {
                 self.number_of_objects -= line.number_of_letter_groups();
                 for _ in 0..line.number_of_letter_groups() {
                     self.buffer.letters.letters.pop_front();
+                    self.buffer.letters.groups.pop_front();
                     self.layout.positions.pop_front();
                 }
                 self.layout.line_capacity.pop_front();
                 self.layout.objects.clear();
+                self.layout.effects.clear();
                 self.buffer.preprocessor.pop(&line);
                 for position in self.layout.positions.iter_mut() {
                     position.y -= line_height as i16;
@@ -332,6 +744,8 @@ impl ObjectTextRender<'_> {
     /// line. Should be called in the same frame as and after
     /// [`next_letter_group`][ObjectTextRender::next_letter_group], [`next_line`][ObjectTextRender::next_line], and [`pop_line`][ObjectTextRender::pop_line].
     pub fn update(&mut self, position: impl Into<Vector2D<i32>>) {
+        self.frame = self.frame.wrapping_add(1);
+
         if !self.buffer.buffered_chars.is_empty()
             && self.buffer.letters.letters.len() <= self.number_of_objects + 5
         {
@@ -340,7 +754,11 @@ impl ObjectTextRender<'_> {
 
         self.layout.update_objects_to_display_at_position(
             position.into(),
-            self.buffer.letters.letters.iter(),
+            self.buffer
+                .letters
+                .letters
+                .iter()
+                .zip(self.buffer.letters.groups.iter().map(|group| group.effect)),
             self.number_of_objects,
         );
     }
@@ -409,6 +827,8 @@ struct LayoutCache {
     positions: VecDeque<Vector2D<i16>>,
     line_capacity: VecDeque<usize>,
     objects: Vec<ObjectUnmanaged>,
+    // the effect of each object in objects
+    effects: Vec<TextEffect>,
     objects_are_at_origin: Vector2D<i32>,
     area: Vector2D<i32>,
 }
@@ -417,29 +837,31 @@ impl LayoutCache {
     fn update_objects_to_display_at_position<'a>(
         &mut self,
         position: Vector2D<i32>,
-        letters: impl Iterator<Item = &'a SpriteVram>,
+        letters: impl Iterator<Item = (&'a SpriteVram, TextEffect)>,
         number_of_objects: usize,
     ) {
         let already_done = if position == self.objects_are_at_origin {
             self.objects.len()
         } else {
             self.objects.clear();
+            self.effects.clear();
             0
         };
-        self.objects.extend(
-            self.positions
-                .iter()
-                .zip(letters)
-                .take(number_of_objects)
-                .skip(already_done)
-                .map(|(offset, letter)| {
-                    let position = offset.change_base() + position;
-                    let mut object = ObjectUnmanaged::new(letter.clone());
-                    object.show().set_position(position);
-                    object
-                }),
-        );
+        for (offset, (letter, effect)) in self
+            .positions
+            .iter()
+            .zip(letters)
+            .take(number_of_objects)
+            .skip(already_done)
+        {
+            let position = offset.change_base() + position;
+            let mut object = ObjectUnmanaged::new(letter.clone());
+            object.show().set_position(position);
+            self.objects.push(object);
+            self.effects.push(effect);
+        }
         self.objects.truncate(number_of_objects);
+        self.effects.truncate(number_of_objects);
         self.objects_are_at_origin = position;
     }
 
@@ -463,7 +885,7 @@ impl LayoutCache {
         font: &Font,
         preprocessed: &'a Preprocessed,
         settings: &'a LayoutSettings,
-    ) -> impl Iterator<Item = (Line, impl Iterator<Item = Vector2D<i32>> + 'a)> + 'a {
+    ) -> impl Iterator<Item = (Line, impl Iterator<Item = Vector2D<i32>>)> + 'a {
         let minimum_space_width = font.letter(' ').advance_width as i32;
         let width = settings.area.x;
         let line_height = font.line_height();
@@ -480,32 +902,249 @@ impl LayoutCache {
                 head_position.y += line_height;
                 head_position.x = line_settings.start_x.into();
 
-                (
-                    line,
-                    line_elements.filter_map(move |element| match element.decode() {
-                        PreprocessedElement::LetterGroup { width } => {
-                            let this_position = head_position;
+                let mut groups = Vec::with_capacity(line.number_of_letter_groups());
+                let mut word = 0;
+                let mut previous_was_group = false;
+
+                for element in line_elements {
+                    match element.decode() {
+                        PreprocessedElement::LetterGroup {
+                            width,
+                            opposite_direction,
+                        } => {
+                            if !previous_was_group {
+                                word += 1;
+                            }
+                            previous_was_group = true;
+
+                            let start = head_position.x;
                             head_position.x += width as i32;
-                            Some(this_position.floor())
+                            groups.push(PlacedGroup {
+                                position: Vector2D::new(start.floor(), head_position.y.floor()),
+                                end: head_position.x.floor(),
+                                word,
+                                opposite_direction,
+                            });
                         }
                         PreprocessedElement::WhiteSpace(space) => {
+                            previous_was_group = false;
                             match space {
                                 WhiteSpace::NewLine => {
                                     head_position.y += settings.paragraph_spacing;
                                 }
                                 WhiteSpace::Space => head_position.x += line_settings.space_width,
+                                WhiteSpace::BreakOpportunity => {}
                             }
-                            None
                         }
-                    }),
-                )
+                    }
+                }
+
+                reorder_words(&mut groups, line.direction(), width);
+
+                (line, groups.into_iter().map(|group| group.position))
             })
     }
 }
 
+struct PlacedGroup {
+    position: Vector2D<i32>,
+    end: i32,
+    word: usize,
+    opposite_direction: bool,
+}
+
+// Groups are laid out left to right, so runs of words which go in the opposite direction to the
+// paragraph need their order reversing, and right to left lines need mirroring.
+fn reorder_words(groups: &mut [PlacedGroup], direction: TextDirection, width: i32) {
+    // reverses the order of the words in groups, keeping each word in the same place within the span
+    fn mirror_words(groups: &mut [PlacedGroup], span_start: i32, span_end: i32) {
+        let mut start = 0;
+        while start < groups.len() {
+            let word = groups[start].word;
+            let length = groups[start..]
+                .iter()
+                .take_while(|group| group.word == word)
+                .count();
+            let word_groups = &mut groups[start..start + length];
+
+            let word_start = word_groups[0].position.x;
+            let word_end = word_groups[length - 1].end;
+            let offset = span_start + span_end - word_end - word_start;
+            for group in word_groups {
+                group.position.x += offset;
+                group.end += offset;
+            }
+
+            start += length;
+        }
+    }
+
+    let mut start = 0;
+    while start < groups.len() {
+        if !groups[start].opposite_direction {
+            start += 1;
+            continue;
+        }
+
+        let length = groups[start..]
+            .iter()
+            .take_while(|group| group.opposite_direction)
+            .count();
+        let run = &mut groups[start..start + length];
+        let (run_start, run_end) = (run[0].position.x, run[length - 1].end);
+        mirror_words(run, run_start, run_end);
+
+        start += length;
+    }
+
+    if direction == TextDirection::RightToLeft {
+        mirror_words(groups, 0, width);
+    }
+}
+
 #[derive(PartialEq, Eq, Default)]
 struct LayoutSettings {
     area: Vector2D<i32>,
     alignment: TextAlignment,
     paragraph_spacing: i32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::palette16::Palette16;
+
+    static FONT: Font = crate::include_font!("examples/font/yoster.ttf", 12);
+
+    fn text_render(text: &str) -> ObjectTextRender<'static> {
+        let palette = PaletteVram::new(&Palette16::new([0xffff; 16])).unwrap();
+        let mut writer = ObjectTextRender::new(&FONT, Size::S16x16, palette);
+        writer.write_str(text).unwrap();
+        writer.layout((100, 40), TextAlignment::Left, 0);
+        writer
+    }
+
+    #[test_case]
+    fn typewriter_pauses_and_reports_tags(_gba: &mut crate::Gba) {
+        let mut writer = text_render("a {pause:3}b {sound:beep}\n");
+
+        writer.tick();
+        assert_eq!(writer.number_of_objects, 1);
+
+        writer.tick();
+        assert_eq!(writer.next_event(), Some(TextEvent::Pause { frames: 3 }));
+        for _ in 0..3 {
+            writer.tick();
+            assert_eq!(writer.number_of_objects, 1);
+        }
+
+        writer.tick();
+        assert_eq!(writer.number_of_objects, 2);
+        assert_eq!(writer.next_event(), None);
+
+        writer.tick();
+        assert_eq!(
+            writer.next_event(),
+            Some(TextEvent::Tag {
+                name: "sound".into(),
+                argument: Some("beep".into())
+            })
+        );
+    }
+
+    #[test_case]
+    fn long_pauses_do_not_overflow(_gba: &mut crate::Gba) {
+        let mut writer = text_render("a {pause:60000}{pause:60000}b\n");
+
+        writer.tick();
+        writer.tick();
+        assert_eq!(writer.typewriter.frames_until_next, u16::MAX);
+    }
+
+    #[test_case]
+    fn unknown_icons_are_an_error(_gba: &mut crate::Gba) {
+        let palette = PaletteVram::new(&Palette16::new([0xffff; 16])).unwrap();
+        let mut writer = ObjectTextRender::new(&FONT, Size::S16x16, palette);
+
+        assert!(writer.write_str("a {icon:coin}").is_err());
+    }
+
+    #[test_case]
+    fn invalid_tag_arguments_are_an_error(_gba: &mut crate::Gba) {
+        let palette = PaletteVram::new(&Palette16::new([0xffff; 16])).unwrap();
+
+        for text in ["a {c:99}", "a {c:x}", "a {pause:abc}"] {
+            let mut writer = ObjectTextRender::new(&FONT, Size::S16x16, palette.clone());
+            assert!(writer.write_str(text).is_err(), "{text}");
+        }
+    }
+
+    #[test_case]
+    fn animated_letters_are_separate_groups(_gba: &mut crate::Gba) {
+        let mut writer = text_render("ab {wave}cd{/wave} {{ef\n");
+        writer.at_least_n_letter_groups(usize::MAX - 1);
+
+        let effects: Vec<_> = writer
+            .buffer
+            .letters
+            .groups
+            .iter()
+            .map(|group| group.effect)
+            .collect();
+        assert_eq!(
+            effects,
+            [
+                TextEffect::None,
+                TextEffect::Wave,
+                TextEffect::Wave,
+                TextEffect::None
+            ]
+        );
+        assert_eq!(writer.layout.line_capacity, [4]);
+    }
+
+    fn word(word: usize, start: i32, end: i32, opposite_direction: bool) -> PlacedGroup {
+        PlacedGroup {
+            position: (start, 0).into(),
+            end,
+            word,
+            opposite_direction,
+        }
+    }
+
+    fn starts(groups: &[PlacedGroup]) -> Vec<i32> {
+        groups.iter().map(|group| group.position.x).collect()
+    }
+
+    #[test_case]
+    fn right_to_left_lines_are_mirrored(_gba: &mut crate::Gba) {
+        // a word split over two groups, a space, then a second word
+        let mut groups = [
+            word(1, 0, 16, false),
+            word(1, 16, 20, false),
+            word(2, 24, 30, false),
+        ];
+        reorder_words(&mut groups, TextDirection::RightToLeft, 100);
+
+        assert_eq!(starts(&groups), [80, 96, 70]);
+    }
+
+    #[test_case]
+    fn opposite_direction_words_keep_their_order(_gba: &mut crate::Gba) {
+        let mut groups = [
+            word(1, 0, 10, false),
+            word(2, 14, 20, true),
+            word(3, 24, 34, true),
+        ];
+        reorder_words(&mut groups, TextDirection::LeftToRight, 100);
+        assert_eq!(starts(&groups), [0, 28, 14]);
+
+        let mut groups = [
+            word(1, 0, 10, false),
+            word(2, 14, 20, true),
+            word(3, 24, 34, true),
+        ];
+        reorder_words(&mut groups, TextDirection::RightToLeft, 100);
+        assert_eq!(starts(&groups), [90, 66, 76]);
+    }
+}
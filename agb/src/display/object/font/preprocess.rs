@@ -1,8 +1,10 @@
 use alloc::collections::VecDeque;
+#[cfg(test)]
+use alloc::vec::Vec;
 
-use crate::display::Font;
+use crate::display::{font::unicode::ZERO_WIDTH_SPACE, Font, TextDirection};
 
-use super::WhiteSpace;
+use super::{TextEffect, WhiteSpace};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) struct PreprocessedElementEncoded(u8);
@@ -12,7 +14,11 @@ impl PreprocessedElementEncoded {
         match self.0 {
             255 => PreprocessedElement::WhiteSpace(WhiteSpace::NewLine),
             254 => PreprocessedElement::WhiteSpace(WhiteSpace::Space),
-            width => PreprocessedElement::LetterGroup { width },
+            253 => PreprocessedElement::WhiteSpace(WhiteSpace::BreakOpportunity),
+            group => PreprocessedElement::LetterGroup {
+                width: group & !OPPOSITE_DIRECTION,
+                opposite_direction: group & OPPOSITE_DIRECTION != 0,
+            },
         }
     }
 }
@@ -20,17 +26,35 @@ impl PreprocessedElementEncoded {
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 
 pub(crate) enum PreprocessedElement {
-    LetterGroup { width: u8 },
+    /// Part of a word which fits in a single sprite. `opposite_direction` is set for
+    /// words which go in the opposite direction to their paragraph.
+    LetterGroup {
+        width: u8,
+        opposite_direction: bool,
+    },
     WhiteSpace(WhiteSpace),
 }
 
+// sprites are at most 64 pixels wide, so the top bit of the width is free
+const OPPOSITE_DIRECTION: u8 = 0x80;
+
 impl PreprocessedElement {
     fn encode(self) -> PreprocessedElementEncoded {
         PreprocessedElementEncoded(match self {
-            PreprocessedElement::LetterGroup { width } => width,
+            PreprocessedElement::LetterGroup {
+                width,
+                opposite_direction,
+            } => {
+                if opposite_direction {
+                    width | OPPOSITE_DIRECTION
+                } else {
+                    width
+                }
+            }
             PreprocessedElement::WhiteSpace(space) => match space {
                 WhiteSpace::NewLine => 255,
                 WhiteSpace::Space => 254,
+                WhiteSpace::BreakOpportunity => 253,
             },
         })
     }
@@ -40,12 +64,18 @@ impl PreprocessedElement {
 pub(crate) struct Preprocessed {
     widths: VecDeque<PreprocessedElementEncoded>,
     preprocessor: Preprocessor,
+    // the direction of each paragraph which has been finished with a new line
+    paragraph_directions: VecDeque<TextDirection>,
+    current_paragraph_direction: Option<TextDirection>,
 }
 
 #[derive(Debug, Default)]
 struct Preprocessor {
     previous_character: Option<char>,
     width_in_sprite: i32,
+    opposite_direction: bool,
+    // whether each letter needs its own group so it can be animated separately
+    per_letter: bool,
 }
 
 impl Preprocessor {
@@ -57,16 +87,8 @@ impl Preprocessor {
         widths: &mut VecDeque<PreprocessedElementEncoded>,
     ) {
         match character {
-            space @ (' ' | '\n') => {
-                if self.width_in_sprite != 0 {
-                    widths.push_back(
-                        PreprocessedElement::LetterGroup {
-                            width: self.width_in_sprite as u8,
-                        }
-                        .encode(),
-                    );
-                    self.width_in_sprite = 0;
-                }
+            space @ (' ' | '\n' | ZERO_WIDTH_SPACE) => {
+                self.finish_group(widths);
                 widths.push_back(
                     PreprocessedElement::WhiteSpace(WhiteSpace::from_char(space)).encode(),
                 );
@@ -77,14 +99,10 @@ impl Preprocessor {
                     self.width_in_sprite += letter.kerning_amount(previous_character);
                 }
 
-                if self.width_in_sprite + letter.width as i32 > sprite_width {
-                    widths.push_back(
-                        PreprocessedElement::LetterGroup {
-                            width: self.width_in_sprite as u8,
-                        }
-                        .encode(),
-                    );
-                    self.width_in_sprite = 0;
+                if self.width_in_sprite + letter.width as i32 > sprite_width
+                    || (self.per_letter && self.width_in_sprite != 0)
+                {
+                    self.finish_group(widths);
                 }
                 if self.width_in_sprite != 0 {
                     self.width_in_sprite += letter.xmin as i32;
@@ -95,6 +113,19 @@ impl Preprocessor {
 
         self.previous_character = Some(character);
     }
+
+    fn finish_group(&mut self, widths: &mut VecDeque<PreprocessedElementEncoded>) {
+        if self.width_in_sprite != 0 {
+            widths.push_back(
+                PreprocessedElement::LetterGroup {
+                    width: self.width_in_sprite as u8,
+                    opposite_direction: self.opposite_direction,
+                }
+                .encode(),
+            );
+            self.width_in_sprite = 0;
+        }
+    }
 }
 
 pub(crate) struct Lines<'preprocess> {
@@ -102,6 +133,9 @@ pub(crate) struct Lines<'preprocess> {
     layout_width: i32,
     data: &'preprocess VecDeque<PreprocessedElementEncoded>,
     current_start_idx: usize,
+    paragraph_directions: &'preprocess VecDeque<TextDirection>,
+    current_paragraph_direction: Option<TextDirection>,
+    paragraph: usize,
 }
 
 pub(crate) struct Line {
@@ -109,6 +143,8 @@ pub(crate) struct Line {
     number_of_text_elements: usize,
     number_of_spaces: usize,
     number_of_letter_groups: usize,
+    direction: TextDirection,
+    ends_paragraph: bool,
 }
 
 impl Line {
@@ -128,6 +164,10 @@ impl Line {
     pub(crate) fn number_of_letter_groups(&self) -> usize {
         self.number_of_letter_groups
     }
+    #[inline(always)]
+    pub(crate) fn direction(&self) -> TextDirection {
+        self.direction
+    }
 }
 
 impl<'pre> Iterator for Lines<'pre> {
@@ -146,10 +186,11 @@ impl<'pre> Iterator for Lines<'pre> {
         let mut length_of_current_word = 0;
         let mut number_of_spaces = 0;
         let mut number_of_letter_groups = 0;
+        let mut ends_paragraph = false;
 
         while let Some(next) = self.data.get(self.current_start_idx + line_idx_length) {
             match next.decode() {
-                PreprocessedElement::LetterGroup { width } => {
+                PreprocessedElement::LetterGroup { width, .. } => {
                     if start_of_current_word == usize::MAX {
                         start_of_current_word = line_idx_length;
                     }
@@ -182,11 +223,13 @@ impl<'pre> Iterator for Lines<'pre> {
                     match space {
                         WhiteSpace::NewLine => {
                             line_idx_length += 1;
+                            ends_paragraph = true;
                             break;
                         }
                         WhiteSpace::Space => {
                             spaces_after_last_word_count += 1;
                         }
+                        WhiteSpace::BreakOpportunity => {}
                     }
                 }
             };
@@ -196,11 +239,23 @@ impl<'pre> Iterator for Lines<'pre> {
 
         self.current_start_idx += line_idx_length;
 
+        let direction = self
+            .paragraph_directions
+            .get(self.paragraph)
+            .copied()
+            .or(self.current_paragraph_direction)
+            .unwrap_or_default();
+        if ends_paragraph {
+            self.paragraph += 1;
+        }
+
         Some(Line {
             width: current_line_width_pixels,
             number_of_text_elements: line_idx_length,
             number_of_spaces,
             number_of_letter_groups,
+            direction,
+            ends_paragraph,
         })
     }
 }
@@ -213,6 +268,40 @@ impl Preprocessed {
     pub(crate) fn add_character(&mut self, font: &Font, c: char, sprite_width: i32) {
         self.preprocessor
             .add_character(font, c, sprite_width, &mut self.widths);
+
+        if c == '\n' {
+            self.paragraph_directions
+                .push_back(self.current_paragraph_direction.take().unwrap_or_default());
+        }
+    }
+
+    /// Sets the direction of the paragraph currently being written
+    pub(crate) fn set_paragraph_direction(&mut self, direction: TextDirection) {
+        self.current_paragraph_direction = Some(direction);
+    }
+
+    /// Ends the current letter group, for example so a command can run before the next letter is shown
+    pub(crate) fn finish_group(&mut self) {
+        self.preprocessor.finish_group(&mut self.widths);
+    }
+
+    /// Sets whether each following letter should be in its own letter group
+    pub(crate) fn set_per_letter(&mut self, per_letter: bool) {
+        self.finish_group();
+        self.preprocessor.per_letter = per_letter;
+    }
+
+    /// Adds an icon, which is a letter group of its own
+    pub(crate) fn add_icon(&mut self, width: i32) {
+        self.finish_group();
+        self.preprocessor.width_in_sprite = width;
+        self.finish_group();
+    }
+
+    /// Sets whether the following characters are part of a word which goes in the opposite
+    /// direction to the paragraph it is in
+    pub(crate) fn start_word(&mut self, opposite_direction: bool) {
+        self.preprocessor.opposite_direction = opposite_direction;
     }
 
     pub(crate) fn pop(&mut self, line: &Line) {
@@ -220,6 +309,10 @@ impl Preprocessed {
         for _ in 0..elements {
             self.widths.pop_front();
         }
+
+        if line.ends_paragraph {
+            self.paragraph_directions.pop_front();
+        }
     }
 
     pub(crate) fn lines(&self, layout_width: i32, minimum_space_width: i32) -> Lines<'_> {
@@ -228,6 +321,9 @@ impl Preprocessed {
             layout_width,
             data: &self.widths,
             current_start_idx: 0,
+            paragraph_directions: &self.paragraph_directions,
+            current_paragraph_direction: self.current_paragraph_direction,
+            paragraph: 0,
         }
     }
 
@@ -246,3 +342,107 @@ impl Preprocessed {
         })
     }
 }
+
+/// A markup tag such as `{wave}` or `{c:2}`
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Tag<'a> {
+    Colour(u8),
+    Effect(TextEffect),
+    Pause(u16),
+    Speed(u16),
+    Icon(&'a str),
+    Custom {
+        name: &'a str,
+        argument: Option<&'a str>,
+    },
+}
+
+/// Parses the contents of a tag, without the surrounding braces. Returns `None` if a built in tag
+/// has an invalid argument, such as `{c:16}` or `{pause:abc}`.
+pub(crate) fn parse_tag(tag: &str) -> Option<Tag<'_>> {
+    let (name, argument) = match tag.split_once(':') {
+        Some((name, argument)) => (name.trim(), Some(argument.trim())),
+        None => (tag.trim(), None),
+    };
+
+    Some(match (name, argument) {
+        ("c", Some(colour)) => Tag::Colour(colour.parse().ok().filter(|&colour| colour < 16)?),
+        ("/c", None) => Tag::Colour(1),
+        ("wave", None) => Tag::Effect(TextEffect::Wave),
+        ("shake", None) => Tag::Effect(TextEffect::Shake),
+        ("/wave" | "/shake", None) => Tag::Effect(TextEffect::None),
+        ("pause", Some(frames)) => Tag::Pause(frames.parse().ok()?),
+        ("speed", Some(frames)) => Tag::Speed(frames.parse().ok()?),
+        ("icon", Some(icon)) => Tag::Icon(icon),
+        (name, argument) => Tag::Custom { name, argument },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn tags_are_parsed(_gba: &mut crate::Gba) {
+        assert_eq!(parse_tag("c:2"), Some(Tag::Colour(2)));
+        assert_eq!(parse_tag("/c"), Some(Tag::Colour(1)));
+        assert_eq!(parse_tag("wave"), Some(Tag::Effect(TextEffect::Wave)));
+        assert_eq!(parse_tag("/shake"), Some(Tag::Effect(TextEffect::None)));
+        assert_eq!(parse_tag("pause: 30"), Some(Tag::Pause(30)));
+        assert_eq!(parse_tag("speed:2"), Some(Tag::Speed(2)));
+        assert_eq!(parse_tag("icon:coin"), Some(Tag::Icon("coin")));
+        assert_eq!(
+            parse_tag("sound:beep"),
+            Some(Tag::Custom {
+                name: "sound",
+                argument: Some("beep")
+            })
+        );
+        assert_eq!(
+            parse_tag("next"),
+            Some(Tag::Custom {
+                name: "next",
+                argument: None
+            })
+        );
+    }
+
+    #[test_case]
+    fn invalid_tag_arguments_are_rejected(_gba: &mut crate::Gba) {
+        assert_eq!(parse_tag("c:16"), None);
+        assert_eq!(parse_tag("c:x"), None);
+        assert_eq!(parse_tag("pause:abc"), None);
+        assert_eq!(parse_tag("pause:-1"), None);
+        assert_eq!(parse_tag("speed:70000"), None);
+    }
+
+    #[test_case]
+    fn per_letter_groups_split_words(_gba: &mut crate::Gba) {
+        static FONT: Font = crate::include_font!("examples/font/yoster.ttf", 12);
+
+        let mut preprocessed = Preprocessed::new();
+        for c in "ab".chars() {
+            preprocessed.add_character(&FONT, c, 32);
+        }
+        preprocessed.set_per_letter(true);
+        for c in "cd".chars() {
+            preprocessed.add_character(&FONT, c, 32);
+        }
+        preprocessed.set_per_letter(false);
+        preprocessed.add_icon(16);
+
+        let groups: Vec<_> = preprocessed
+            .widths
+            .iter()
+            .map(|element| element.decode())
+            .collect();
+        assert_eq!(groups.len(), 4);
+        assert_eq!(
+            groups[3],
+            PreprocessedElement::LetterGroup {
+                width: 16,
+                opposite_direction: false
+            }
+        );
+    }
+}
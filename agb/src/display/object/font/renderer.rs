@@ -43,6 +43,7 @@ pub(crate) struct WordRender {
     working: WorkingLetter,
     config: Configuration,
     colour: usize,
+    per_letter: bool,
 
     previous_character: Option<char>,
 }
@@ -58,10 +59,16 @@ impl WordRender {
             working: WorkingLetter::new(config.sprite_size),
             config,
             colour: 1,
+            per_letter: false,
             previous_character: None,
         }
     }
 
+    /// Sets whether each letter should be in its own sprite so it can be animated separately
+    pub(crate) fn set_per_letter(&mut self, per_letter: bool) {
+        self.per_letter = per_letter;
+    }
+
     #[must_use]
     pub(crate) fn finalise_letter(&mut self) -> Option<SpriteVram> {
         if self.working.x_offset == 0 {
@@ -93,6 +100,7 @@ impl WordRender {
         // uses more than the sprite can hold
         let group = if self.working.x_offset + font_letter.width as i32
             > self.config.sprite_size.to_width_height().0 as i32
+            || (self.per_letter && self.working.x_offset != 0)
         {
             self.finalise_letter()
         } else {
@@ -104,15 +112,16 @@ impl WordRender {
         }
 
         let y_position = font.ascent() - font_letter.height as i32 - font_letter.ymin as i32;
+        let bits_per_pixel = font.bits_per_pixel();
 
         for y in 0..font_letter.height as usize {
             for x in 0..font_letter.width as usize {
-                let rendered = font_letter.bit_absolute(x, y);
-                if rendered {
+                let coverage = font_letter.coverage(x, y, bits_per_pixel);
+                if coverage != 0 {
                     self.working.dynamic.set_pixel(
                         x + self.working.x_offset as usize,
                         (y_position + y as i32) as usize,
-                        self.colour,
+                        font.colour_for_coverage(self.colour as u8, coverage) as usize,
                     );
                 }
             }
@@ -7,7 +7,10 @@ use alloc::{
 
 use crate::{
     agb_alloc::{block_allocator::BlockAllocator, bump_allocator::StartEnd, impl_zst_allocator},
-    display::palette16::Palette16,
+    display::{
+        palette16::Palette16,
+        upload::{self, UploadKind},
+    },
     hash_map::HashMap,
 };
 
@@ -111,10 +114,10 @@ impl PaletteVram {
             .ok_or(LoaderError::PaletteFull)?;
 
         unsafe {
-            allocated
-                .as_ptr()
-                .cast::<u16>()
-                .copy_from_nonoverlapping(palette.colours.as_ptr(), palette.colours.len());
+            let location = allocated.as_ptr().cast::<u16>();
+            if !upload::enqueue(UploadKind::Palette, location, &palette.colours) {
+                location.copy_from_nonoverlapping(palette.colours.as_ptr(), palette.colours.len());
+            }
         }
 
         Ok(PaletteVram {
@@ -164,9 +167,11 @@ impl SpriteVram {
         let allocated =
             unsafe { SPRITE_ALLOCATOR.alloc(size.layout()) }.ok_or(LoaderError::SpriteFull)?;
         unsafe {
-            allocated
-                .as_ptr()
-                .copy_from_nonoverlapping(data.as_ptr(), data.len());
+            if !upload::enqueue(UploadKind::Tiles, allocated.as_ptr(), data) {
+                allocated
+                    .as_ptr()
+                    .copy_from_nonoverlapping(data.as_ptr(), data.len());
+            }
         }
         Ok(unsafe { Self::from_location_size(allocated, size, palette) })
     }
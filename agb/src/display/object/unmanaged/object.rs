@@ -1,13 +1,14 @@
 use core::{cell::UnsafeCell, marker::PhantomData};
 
 use agb_fixnum::Vector2D;
-use alloc::vec::Vec;
+use alloc::{boxed::Box, vec::Vec};
 
 use crate::display::{
     object::{
         affine::AffineMatrixVram, sprites::SpriteVram, AffineMatrixInstance,
         OBJECT_ATTRIBUTE_MEMORY,
     },
+    upload::{self, UploadKind},
     Priority,
 };
 
@@ -19,6 +20,18 @@ struct OamFrameModifyables {
     frame: u32,
     affine_matrix_count: u32,
     previous_index: usize,
+    // A copy of object attribute memory to write to while there is an upload queue,
+    // which is queued in one go at the end of the frame
+    shadow: Option<Box<[u16; 512]>>,
+}
+
+impl OamFrameModifyables {
+    fn object_attribute_memory(&mut self) -> *mut u16 {
+        match &mut self.shadow {
+            Some(shadow) => shadow.as_mut_ptr(),
+            None => OBJECT_ATTRIBUTE_MEMORY,
+        }
+    }
 }
 
 /// This handles the unmanaged oam system which gives more control to the OAM slots.
@@ -109,7 +122,7 @@ impl OamSlot<'_> {
         if let Some(affine_matrix) = &object.affine_matrix {
             Self::handle_affine(&mut attributes, frame_data, affine_matrix);
         }
-        attributes.write(unsafe { OBJECT_ATTRIBUTE_MEMORY.add(self.slot * 4) });
+        attributes.write(unsafe { frame_data.object_attribute_memory().add(self.slot * 4) });
 
         frame_data.this_frame_sprites.push(object.sprite.clone());
     }
@@ -127,7 +140,7 @@ impl OamSlot<'_> {
             );
             affine_matrix.set_location(frame_data.affine_matrix_count);
             frame_data.affine_matrix_count += 1;
-            affine_matrix.write_to_location(OBJECT_ATTRIBUTE_MEMORY);
+            affine_matrix.write_to_location(frame_data.object_attribute_memory());
         }
 
         attributes.set_affine_matrix(affine_matrix.location() as u16);
@@ -155,15 +168,20 @@ impl<'oam> Iterator for OamIterator<'oam> {
 impl Drop for OamIterator<'_> {
     fn drop(&mut self) {
         let number_writen = self.index;
-        let last_frame_written = unsafe { &mut (*self.frame_data.get()).previous_index };
+        let frame_data = unsafe { &mut *self.frame_data.get() };
+        let object_attribute_memory = frame_data.object_attribute_memory();
 
-        for idx in number_writen..*last_frame_written {
+        for idx in number_writen..frame_data.previous_index {
             unsafe {
-                let ptr = OBJECT_ATTRIBUTE_MEMORY.add(idx * 4);
+                let ptr = object_attribute_memory.add(idx * 4);
                 ptr.write_volatile(0b10 << 8);
             }
         }
-        *last_frame_written = number_writen;
+        frame_data.previous_index = number_writen;
+
+        if let Some(shadow) = &frame_data.shadow {
+            unsafe { upload::enqueue(UploadKind::Objects, OBJECT_ATTRIBUTE_MEMORY, &shadow[..]) };
+        }
     }
 }
 
@@ -174,6 +192,16 @@ impl OamUnmanaged<'_> {
         frame_data.frame = frame_data.frame.wrapping_add(1);
         frame_data.affine_matrix_count = 0;
 
+        if !upload::is_active() {
+            frame_data.shadow = None;
+        } else if frame_data.shadow.is_none() {
+            let mut shadow = Box::new([0; 512]);
+            for (i, value) in shadow.iter_mut().enumerate() {
+                *value = unsafe { OBJECT_ATTRIBUTE_MEMORY.add(i).read_volatile() };
+            }
+            frame_data.shadow = Some(shadow);
+        }
+
         // We drain the previous frame sprites here to reuse the Vecs allocation and remove the now unused sprites.
         // Any sprites currently being shown will now be put in the new Vec.
         self.previous_frame_sprites.clear();
@@ -195,6 +223,7 @@ impl OamUnmanaged<'_> {
                 frame: 0,
                 affine_matrix_count: 0,
                 previous_index: 0,
+                shadow: None,
             }),
             phantom: PhantomData,
             previous_frame_sprites: Default::default(),
@@ -4,6 +4,7 @@ use core::ops::{Deref, DerefMut};
 use crate::bitarray::Bitarray;
 use crate::display::affine::AffineMatrixBackground;
 use crate::display::tile_data::TileData;
+use crate::display::upload::{self, UploadKind};
 use crate::display::{Priority, DISPLAY_CONTROL};
 use crate::dma;
 use crate::fixnum::Vector2D;
@@ -92,11 +93,15 @@ where
         let screenblock_memory = self.screenblock_memory();
 
         if *self.tiles_dirty() {
-            unsafe {
-                screenblock_memory.copy_from(
-                    self.tiles_mut().as_ptr() as *const u16,
-                    self.map_size().num_tiles(),
-                );
+            let num_tiles = self.map_size().num_tiles();
+            let tiles = unsafe {
+                core::slice::from_raw_parts(self.tiles_mut().as_ptr() as *const u16, num_tiles)
+            };
+
+            if !unsafe { upload::enqueue(UploadKind::Map, screenblock_memory, tiles) } {
+                unsafe {
+                    screenblock_memory.copy_from(tiles.as_ptr(), num_tiles);
+                }
             }
         }
 
@@ -4,7 +4,10 @@ use alloc::{slice, vec::Vec};
 
 use crate::{
     agb_alloc::{block_allocator::BlockAllocator, bump_allocator::StartEnd},
-    display::palette16,
+    display::{
+        palette16,
+        upload::{self, UploadKind},
+    },
     dma,
     hash_map::{Entry, HashMap},
     memory_mapped::MemoryMapped1DArray,
@@ -380,6 +383,11 @@ impl VRamManager {
 
         let target_location = tile_reference.0.as_ptr() as *mut _;
 
+        let tile_data = &tile_set.tiles[tile_offset..tile_offset + tile_size];
+        if unsafe { upload::enqueue(UploadKind::Tiles, target_location, tile_data) } {
+            return;
+        }
+
         unsafe {
             match tile_format {
                 TileFormat::FourBpp => core::arch::asm!(
@@ -412,6 +420,10 @@ impl VRamManager {
 
     /// Copies raw palettes to the background palette without any checks.
     pub fn set_background_palette_raw(&mut self, palette: &[u16]) {
+        if unsafe { upload::enqueue(UploadKind::Palette, PALETTE_BACKGROUND.as_ptr(), palette) } {
+            return;
+        }
+
         unsafe {
             PALETTE_BACKGROUND
                 .as_ptr()
@@ -420,6 +432,11 @@ impl VRamManager {
     }
 
     fn set_background_palette(&mut self, pal_index: u8, palette: &palette16::Palette16) {
+        let location = unsafe { PALETTE_BACKGROUND.as_ptr().add(16 * pal_index as usize) };
+        if unsafe { upload::enqueue(UploadKind::Palette, location, &palette.colours) } {
+            return;
+        }
+
         for (colour_index, &colour) in palette.colours.iter().enumerate() {
             PALETTE_BACKGROUND.set(colour_index + 16 * pal_index as usize, colour);
         }
@@ -442,7 +459,8 @@ impl VRamManager {
         })
     }
 
-    /// Sets a single colour for a given background palette. Takes effect immediately
+    /// Sets a single colour for a given background palette. Takes effect immediately, or at the
+    /// next vertical blank if there is an [`UploadQueue`](crate::display::upload::UploadQueue).
     pub fn set_background_palette_colour(
         &mut self,
         pal_index: usize,
@@ -452,6 +470,15 @@ impl VRamManager {
         assert!(pal_index < 16);
         assert!(colour_index < 16);
 
+        let location = unsafe {
+            PALETTE_BACKGROUND
+                .as_ptr()
+                .add(colour_index + 16 * pal_index)
+        };
+        if unsafe { upload::enqueue(UploadKind::Palette, location, &[colour]) } {
+            return;
+        }
+
         PALETTE_BACKGROUND.set(colour_index + 16 * pal_index, colour);
     }
 
@@ -0,0 +1,372 @@
+#![warn(missing_docs)]
+//! Queues writes to video memory so they happen during the vertical blank.
+//!
+//! Normally tile data, maps, palettes and objects are written to video memory
+//! as soon as you load or commit them. If that happens while the screen is
+//! being drawn, you can see tearing where half of the screen shows the old
+//! data and half the new. While an [`UploadQueue`] exists, these writes are
+//! instead queued up during the frame and copied with DMA at the start of the
+//! next vertical blank.
+//!
+//! Uploads are copied in the order of [`UploadKind`], so that a map or object
+//! is never shown before the tiles it uses. Only [`budget`](UploadQueue::budget)
+//! bytes are copied each vertical blank, and anything which doesn't fit is
+//! carried over to the next one.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! # fn foo(gba: &mut agb::Gba) {
+//! let mut upload_queue = gba.display.upload.get();
+//! upload_queue.set_budget(8 * 1024);
+//!
+//! let vblank = agb::interrupt::VBlank::get();
+//!
+//! loop {
+//!     // load sprites, commit maps and objects as usual
+//!
+//!     vblank.wait_for_vblank();
+//! }
+//! # }
+//! ```
+
+use core::{cell::RefCell, marker::PhantomData, mem::size_of_val, slice};
+
+use alloc::vec::Vec;
+use critical_section::{CriticalSection, Mutex};
+
+use crate::{
+    dma,
+    interrupt::{add_interrupt_handler, Interrupt, InterruptHandler},
+};
+
+/// The number of bytes copied each vertical blank by default. This leaves
+/// plenty of the vertical blank for the rest of your game.
+pub const DEFAULT_BUDGET: usize = 16 * 1024;
+
+// The most halfwords to copy with a single DMA transfer
+const MAX_TRANSFER: usize = 0x8000;
+
+/// What an upload is for. Uploads are copied in this order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum UploadKind {
+    /// Background and object palettes.
+    Palette,
+    /// Background tiles and sprites.
+    Tiles,
+    /// Background map screenblocks.
+    Map,
+    /// The object attribute memory.
+    Objects,
+}
+
+struct Upload {
+    kind: UploadKind,
+    destination: usize,
+    data: Vec<u16>,
+    transferred: usize,
+}
+
+impl Upload {
+    fn is_complete(&self) -> bool {
+        self.transferred == self.data.len()
+    }
+
+    fn remaining_bytes(&self) -> usize {
+        (self.data.len() - self.transferred) * 2
+    }
+
+    // Copies up to `max_bytes` of the upload, returning how many bytes were copied
+    fn transfer(&mut self, max_bytes: usize) -> usize {
+        let count = (self.data.len() - self.transferred).min(max_bytes / 2);
+
+        let mut copied = 0;
+        while copied < count {
+            let source = self.data[self.transferred..].as_ptr();
+            let destination = (self.destination + self.transferred * 2) as *mut u16;
+            let chunk = (count - copied).min(MAX_TRANSFER);
+
+            let halfwords = unsafe { copy(source, destination, chunk) };
+            self.transferred += halfwords;
+            copied += halfwords;
+        }
+
+        copied * 2
+    }
+}
+
+// Copies `count` halfwords with DMA3, a word at a time if possible. Returns how many
+// halfwords were copied, which is only the first one if that is needed to align the rest.
+unsafe fn copy(source: *const u16, destination: *mut u16, count: usize) -> usize {
+    if (source as usize ^ destination as usize) % 4 != 0 {
+        dma::dma_copy16(source, destination, count);
+        count
+    } else if source as usize % 4 != 0 || count == 1 {
+        dma::dma_copy16(source, destination, 1);
+        1
+    } else {
+        let words = count / 2;
+        dma::dma_copy32(source.cast(), destination.cast(), words);
+        words * 2
+    }
+}
+
+struct QueueState {
+    active: bool,
+    budget: usize,
+    uploads: Vec<Upload>,
+}
+
+impl QueueState {
+    const fn new() -> Self {
+        Self {
+            active: false,
+            budget: DEFAULT_BUDGET,
+            uploads: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, kind: UploadKind, destination: usize, bytes: &[u8]) {
+        assert!(
+            bytes.len() % 2 == 0 && destination % 2 == 0,
+            "video memory can only be written a halfword at a time"
+        );
+
+        // this is the only place uploads get freed, since we can't free them in the interrupt
+        self.uploads.retain(|upload| !upload.is_complete());
+
+        let halfwords = bytes
+            .chunks_exact(2)
+            .map(|halfword| u16::from_le_bytes([halfword[0], halfword[1]]));
+
+        // If the last upload of this kind is to the same place and hasn't started yet, such as
+        // the objects from a frame which couldn't fit in the budget, replace it rather than
+        // copying both.
+        if let Some(previous) = self
+            .uploads
+            .iter_mut()
+            .rev()
+            .find(|upload| upload.kind == kind)
+        {
+            if previous.destination == destination
+                && previous.data.len() * 2 == bytes.len()
+                && previous.transferred == 0
+            {
+                for (old, new) in previous.data.iter_mut().zip(halfwords) {
+                    *old = new;
+                }
+                return;
+            }
+        }
+
+        let position = self
+            .uploads
+            .iter()
+            .rposition(|upload| upload.kind <= kind)
+            .map_or(0, |index| index + 1);
+
+        self.uploads.insert(
+            position,
+            Upload {
+                kind,
+                destination,
+                data: halfwords.collect(),
+                transferred: 0,
+            },
+        );
+    }
+
+    fn flush(&mut self, mut budget: usize) {
+        for upload in &mut self.uploads {
+            if budget == 0 {
+                break;
+            }
+
+            budget -= upload.transfer(budget);
+        }
+    }
+
+    fn pending_bytes(&self) -> usize {
+        self.uploads.iter().map(Upload::remaining_bytes).sum()
+    }
+}
+
+static QUEUE: Mutex<RefCell<QueueState>> = Mutex::new(RefCell::new(QueueState::new()));
+
+fn with_state<R>(f: impl FnOnce(&mut QueueState) -> R) -> R {
+    critical_section::with(|cs| f(&mut QUEUE.borrow_ref_mut(cs)))
+}
+
+/// Whether writes to video memory are currently being queued.
+pub(crate) fn is_active() -> bool {
+    with_state(|state| state.active)
+}
+
+/// Queues `data` to be copied to `destination` if there is an active [`UploadQueue`],
+/// returning false if there isn't one and you should write it yourself.
+///
+/// # Safety
+/// `destination` must be in video memory and valid for writing `data.len()` values.
+pub(crate) unsafe fn enqueue<T: Copy>(kind: UploadKind, destination: *mut T, data: &[T]) -> bool {
+    let bytes = slice::from_raw_parts(data.as_ptr().cast::<u8>(), size_of_val(data));
+
+    with_state(|state| {
+        if state.active {
+            state.push(kind, destination as usize, bytes);
+        }
+
+        state.active
+    })
+}
+
+/// Queues writes to video memory while it exists, see the [module level documentation](self).
+///
+/// Dropping the queue copies everything that is still waiting immediately.
+pub struct UploadQueue<'gba> {
+    _handler: InterruptHandler,
+    phantom: PhantomData<&'gba ()>,
+}
+
+impl UploadQueue<'_> {
+    fn new() -> Self {
+        with_state(|state| {
+            state.active = true;
+            state.budget = DEFAULT_BUDGET;
+        });
+
+        // safety: flushing doesn't allocate or free
+        let handler = unsafe {
+            add_interrupt_handler(Interrupt::VBlank, |cs: CriticalSection| {
+                let mut state = QUEUE.borrow_ref_mut(cs);
+                let budget = state.budget;
+                state.flush(budget);
+            })
+        };
+
+        Self {
+            _handler: handler,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets the number of bytes to copy each vertical blank.
+    pub fn set_budget(&mut self, bytes: usize) {
+        with_state(|state| state.budget = bytes);
+    }
+
+    /// The number of bytes copied each vertical blank, which starts as [`DEFAULT_BUDGET`].
+    #[must_use]
+    pub fn budget(&self) -> usize {
+        with_state(|state| state.budget)
+    }
+
+    /// The number of bytes still waiting to be copied.
+    #[must_use]
+    pub fn pending_bytes(&self) -> usize {
+        with_state(|state| state.pending_bytes())
+    }
+
+    /// Whether everything which has been queued has been copied.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pending_bytes() == 0
+    }
+
+    /// Copies everything which is waiting right now, ignoring the budget. This is useful
+    /// while the screen is blank, such as when loading a level.
+    pub fn flush(&mut self) {
+        with_state(|state| {
+            state.flush(usize::MAX);
+            state.uploads.clear();
+        });
+    }
+
+    /// Queues `data` to be copied to `destination` in video memory.
+    ///
+    /// # Safety
+    /// `destination` must be in video memory and valid for writing `data.len()` values.
+    ///
+    /// # Panics
+    /// Panics if `data` or `destination` isn't a whole number of halfwords.
+    pub unsafe fn upload<T: Copy>(&mut self, kind: UploadKind, destination: *mut T, data: &[T]) {
+        enqueue(kind, destination, data);
+    }
+}
+
+impl Drop for UploadQueue<'_> {
+    fn drop(&mut self) {
+        self.flush();
+        with_state(|state| state.active = false);
+    }
+}
+
+/// Gives out the [`UploadQueue`], obtained from the [`Display`](super::Display).
+#[non_exhaustive]
+pub struct UploadDist;
+
+impl UploadDist {
+    /// Starts queuing writes to video memory until the returned [`UploadQueue`] is dropped.
+    pub fn get(&mut self) -> UploadQueue<'_> {
+        UploadQueue::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interrupt::VBlank;
+
+    use super::*;
+
+    const TEST_LOCATION: *mut u16 = 0x0600_f000 as *mut u16;
+
+    #[test_case]
+    fn uploads_happen_in_vblank(gba: &mut crate::Gba) {
+        let vblank = VBlank::get();
+        let mut queue = gba.display.upload.get();
+
+        unsafe { TEST_LOCATION.write_volatile(0) };
+
+        vblank.wait_for_vblank();
+        unsafe { queue.upload(UploadKind::Tiles, TEST_LOCATION, &[1, 2, 3, 4]) };
+
+        assert_eq!(unsafe { TEST_LOCATION.read_volatile() }, 0);
+        assert_eq!(queue.pending_bytes(), 8);
+
+        vblank.wait_for_vblank();
+
+        assert!(queue.is_empty());
+        assert_eq!(unsafe { TEST_LOCATION.add(3).read_volatile() }, 4);
+    }
+
+    #[test_case]
+    fn uploads_over_budget_are_carried_over(gba: &mut crate::Gba) {
+        let vblank = VBlank::get();
+        let mut queue = gba.display.upload.get();
+        queue.set_budget(8);
+
+        vblank.wait_for_vblank();
+        unsafe {
+            queue.upload(UploadKind::Map, TEST_LOCATION.add(4), &[5, 6, 7, 8]);
+            queue.upload(UploadKind::Palette, TEST_LOCATION, &[1, 2, 3, 4]);
+        }
+
+        vblank.wait_for_vblank();
+        // the palette goes first, even though it was queued later
+        assert_eq!(unsafe { TEST_LOCATION.read_volatile() }, 1);
+        assert_eq!(queue.pending_bytes(), 8);
+
+        vblank.wait_for_vblank();
+        assert!(queue.is_empty());
+        assert_eq!(unsafe { TEST_LOCATION.add(7).read_volatile() }, 8);
+    }
+
+    #[test_case]
+    fn dropping_the_queue_flushes_it(gba: &mut crate::Gba) {
+        let mut queue = gba.display.upload.get();
+        unsafe { queue.upload(UploadKind::Objects, TEST_LOCATION, &[9, 10]) };
+        drop(queue);
+
+        assert_eq!(unsafe { TEST_LOCATION.add(1).read_volatile() }, 10);
+        assert!(!is_active());
+    }
+}
@@ -1,8 +1,14 @@
-use core::{marker::PhantomData, mem::size_of, pin::Pin};
+use core::{
+    future::Future,
+    marker::PhantomData,
+    mem::size_of,
+    pin::Pin,
+    task::{Context, Poll},
+};
 
 use alloc::boxed::Box;
 
-use crate::memory_mapped::MemoryMapped;
+use crate::{memory_mapped::MemoryMapped, sound::mixer::MixerController};
 
 #[non_exhaustive]
 pub struct DmaController {}
@@ -12,9 +18,17 @@ impl DmaController {
         Self {}
     }
 
+    /// Gets DMA channels 0 and 3. Channels 1 and 2 are used by the [`Mixer`](crate::sound::mixer::Mixer) to play
+    /// sound, so you can only get those with [`all_dma`](DmaController::all_dma).
     pub fn dma(&mut self) -> Dmas<'_> {
         unsafe { Dmas::new() }
     }
+
+    /// Gets all 4 DMA channels. This borrows the [`MixerController`] for as long as you are using the channels,
+    /// since the mixer needs channels 1 and 2, so you can only do this in games which don't use the mixer.
+    pub fn all_dma<'a>(&'a mut self, _mixer: &'a mut MixerController) -> AllDmas<'a> {
+        unsafe { AllDmas::new() }
+    }
 }
 
 pub struct Dmas<'gba> {
@@ -35,6 +49,56 @@ impl<'gba> Dmas<'gba> {
     }
 }
 
+/// All 4 DMA channels, see [`DmaController::all_dma`].
+pub struct AllDmas<'gba> {
+    phantom: PhantomData<&'gba ()>,
+
+    pub dma0: Dma,
+    pub dma1: Dma,
+    pub dma2: Dma,
+    pub dma3: Dma,
+}
+
+impl<'gba> AllDmas<'gba> {
+    unsafe fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+
+            dma0: Dma::new(0),
+            dma1: Dma::new(1),
+            dma2: Dma::new(2),
+            dma3: Dma::new(3),
+        }
+    }
+}
+
+// bits of the upper half of the control register
+const DEST_FIXED: u32 = 0b10 << 0x15;
+const SOURCE_FIXED: u32 = 0b10 << 0x17;
+const REPEAT: u32 = 1 << 0x19;
+const TRANSFER_32_BIT: u32 = 1 << 0x1a;
+const START_VBLANK: u32 = 0b01 << 0x1c;
+const START_HBLANK: u32 = 0b10 << 0x1c;
+const ENABLE: u32 = 1 << 0x1f;
+
+/// Works out whether a transfer of `T`s between these addresses can be done 32 bits at a time,
+/// or otherwise 16 bits at a time. Returns the control bits and number of bytes in each unit.
+fn transfer_unit<T>(source: usize, destination: usize) -> (u32, usize) {
+    let aligned_to = |alignment: usize| {
+        size_of::<T>() % alignment == 0 && source % alignment == 0 && destination % alignment == 0
+    };
+
+    if aligned_to(4) {
+        (TRANSFER_32_BIT, 4)
+    } else if aligned_to(2) {
+        (0, 2)
+    } else {
+        panic!(
+            "dma can only transfer types which are a multiple of 2 bytes and aligned to 2 bytes"
+        );
+    }
+}
+
 pub struct Dma {
     number: usize,
 
@@ -57,6 +121,188 @@ impl Dma {
         unsafe { MemoryMapped::new(dma_control_addr(self.number)) }.set(0);
     }
 
+    // the maximum number of units in a single transfer, a count of 0 in the control register means this many
+    fn max_transfer_count(&self) -> usize {
+        if self.number == 3 {
+            0x1_0000
+        } else {
+            0x4000
+        }
+    }
+
+    fn assert_can_read(&self, source: usize) {
+        assert!(
+            self.number != 0 || !(0x0800_0000..0x1000_0000).contains(&source),
+            "dma0 can't read from the cartridge"
+        );
+    }
+
+    /// Copies `source` to `destination` using this DMA channel, which is much faster than copying with the CPU.
+    /// The CPU is paused until the copy is finished.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `source` and `destination` are different lengths, if `T` isn't a multiple of 2 bytes in size,
+    /// or if this is DMA channel 0 and `source` is in the ROM.
+    pub fn copy<T: Copy>(&mut self, source: &[T], destination: &mut [T]) {
+        assert_eq!(
+            source.len(),
+            destination.len(),
+            "source and destination must be the same length"
+        );
+
+        unsafe { self.copy_raw(source.as_ptr(), destination.as_mut_ptr(), source.len()) }
+    }
+
+    /// Fills `destination` with copies of `value` using this DMA channel. The CPU is paused until the fill is finished.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` isn't 2 or 4 bytes in size, or if `T` is 4 bytes and `destination` isn't aligned to 4 bytes.
+    pub fn fill<T: Copy>(&mut self, value: T, destination: &mut [T]) {
+        unsafe { self.fill_raw(value, destination.as_mut_ptr(), destination.len()) }
+    }
+
+    /// Copies `count` values from `source` to `destination`, see [`copy`](Dma::copy).
+    ///
+    /// # Safety
+    ///
+    /// `source` must be valid for reading `count` values and `destination` must be valid for writing `count`
+    /// values. These can be hardware memory such as video ram.
+    pub unsafe fn copy_raw<T: Copy>(
+        &mut self,
+        source: *const T,
+        destination: *mut T,
+        count: usize,
+    ) {
+        let (size_bits, unit) = transfer_unit::<T>(source as usize, destination as usize);
+        self.assert_can_read(source as usize);
+
+        unsafe {
+            self.immediate_transfer(
+                source as usize,
+                destination as usize,
+                count * size_of::<T>() / unit,
+                unit,
+                size_bits,
+            );
+        }
+    }
+
+    /// Fills `count` values at `destination` with `value`, see [`fill`](Dma::fill).
+    ///
+    /// # Safety
+    ///
+    /// `destination` must be valid for writing `count` values. This can be hardware memory such as video ram.
+    pub unsafe fn fill_raw<T: Copy>(&mut self, value: T, destination: *mut T, count: usize) {
+        assert!(
+            size_of::<T>() == 2 || size_of::<T>() == 4,
+            "dma can only fill with values which are 2 or 4 bytes"
+        );
+
+        // the source is kept aligned to 4 bytes so 4 byte values can always be read as a single word
+        #[repr(align(4))]
+        struct Aligned<T>(T);
+
+        let value = Aligned(value);
+        let source = &value.0 as *const T as usize;
+        let (size_bits, unit) = transfer_unit::<T>(source, destination as usize);
+        // each unit reads the same fixed source, so half words would repeat half of the value
+        assert_eq!(
+            unit,
+            size_of::<T>(),
+            "dma can only fill with 4 byte values when the destination is aligned to 4 bytes"
+        );
+
+        unsafe {
+            self.immediate_transfer(
+                source,
+                destination as usize,
+                count,
+                0,
+                size_bits | SOURCE_FIXED,
+            );
+        }
+    }
+
+    // Transfers `count` units in as many transfers as needed, moving the source along by `source_step`
+    // bytes per unit (which is 0 for a fixed source).
+    unsafe fn immediate_transfer(
+        &mut self,
+        mut source: usize,
+        mut destination: usize,
+        mut count: usize,
+        source_step: usize,
+        control: u32,
+    ) {
+        let unit = if control & TRANSFER_32_BIT != 0 { 4 } else { 2 };
+
+        while count > 0 {
+            let chunk = count.min(self.max_transfer_count());
+
+            // an interrupt could use the same channel, so don't let one happen part way through
+            critical_section::with(|_| {
+                self.source_addr.set(source as u32);
+                self.dest_addr.set(destination as u32);
+                self.ctrl_addr
+                    .set(control | ENABLE | (chunk % self.max_transfer_count()) as u32);
+
+                // the CPU is paused during the transfer, but make sure it has finished before continuing
+                while self.ctrl_addr.get() & ENABLE != 0 {}
+            });
+
+            source += chunk * source_step;
+            destination += chunk * unit;
+            count -= chunk;
+        }
+    }
+
+    /// Copies `values` to `destination` at the start of the next vertical blank, which is when it is safe
+    /// to change video memory without causing tearing. The values are copied into the returned handle,
+    /// so you can reuse `values` straight away.
+    ///
+    /// The transfer is cancelled if the handle is dropped before the vertical blank. You can check whether
+    /// it has happened with [`DmaTransferHandle::is_complete`], or `.await` the handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` and `destination` are different lengths, if `T` isn't a multiple of 2 bytes in size,
+    /// or if there are too many values to copy in one transfer.
+    ///
+    /// # Safety
+    ///
+    /// The returned handle must be dropped or waited on rather than leaked, for example with
+    /// [`core::mem::forget`]. Otherwise the borrow of `destination` ends while the transfer is still
+    /// waiting for the vertical blank, and it would write to memory which may have been reused.
+    pub unsafe fn vblank_transfer<'a, T: Copy>(
+        &'a mut self,
+        values: &[T],
+        destination: &'a mut [T],
+    ) -> DmaTransferHandle<'a, T> {
+        assert_eq!(
+            values.len(),
+            destination.len(),
+            "values and destination must be the same length"
+        );
+
+        let handle = unsafe { DmaTransferHandle::new(self.number, values) };
+
+        let source = handle.data.as_ptr() as usize;
+        let (size_bits, unit) = transfer_unit::<T>(source, destination.as_ptr() as usize);
+        let count = core::mem::size_of_val(values) / unit;
+        assert!(
+            count <= self.max_transfer_count(),
+            "too many values for a single dma transfer"
+        );
+
+        self.source_addr.set(source as u32);
+        self.dest_addr.set(destination.as_mut_ptr() as u32);
+        self.ctrl_addr
+            .set(size_bits | START_VBLANK | ENABLE | (count % self.max_transfer_count()) as u32);
+
+        handle
+    }
+
     /// Triggers a transfer from `values` to `location` to happen on every horizontal blank.
     ///
     /// This is useful for doing things like circular windows, wobbly backgrounds or fades
@@ -99,12 +345,10 @@ impl Dma {
         location.memory_location.write_volatile(values[0]);
 
         self.ctrl_addr.set(
-            (0b10 << 0x15) | // keep destination address fixed
-            // (0b00 << 0x17) | // increment the source address each time
-            1 << 0x19 | // repeat the copy each hblank
-            // 0 << 0x1a | // copy in half words (see n_transfers above)
-            0b10 << 0x1c | // copy each hblank
-            1 << 0x1f | // enable the dma
+            DEST_FIXED | // keep destination address fixed
+            REPEAT | // repeat the copy each hblank
+            START_HBLANK | // copy each hblank
+            ENABLE | // enable the dma
             n_transfers, // the number of halfwords to copy
         );
 
@@ -150,6 +394,43 @@ where
     }
 }
 
+impl<'a, T> DmaTransferHandle<'a, T>
+where
+    T: Copy,
+{
+    /// Whether the transfer has finished. Transfers which repeat, such as
+    /// [`hblank_transfer`](Dma::hblank_transfer), never finish.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        let control: MemoryMapped<u32> =
+            unsafe { MemoryMapped::new(dma_control_addr(self.number)) };
+        control.get() & ENABLE == 0
+    }
+
+    /// Waits until the transfer has finished.
+    pub fn wait(self) {
+        while !self.is_complete() {}
+    }
+}
+
+impl<'a, T> Future for DmaTransferHandle<'a, T>
+where
+    T: Copy,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.is_complete() {
+            Poll::Ready(())
+        } else {
+            // vblank transfers finish at the start of the vblank, so make sure there is an interrupt then
+            crate::interrupt::vblank_count();
+            crate::executor::wake_after_interrupt(cx.waker());
+            Poll::Pending
+        }
+    }
+}
+
 impl<'a, T> Drop for DmaTransferHandle<'a, T>
 where
     T: Copy,
@@ -186,6 +467,15 @@ pub(crate) unsafe fn dma_copy16(src: *const u16, dest: *mut u16, count: usize) {
     DMA3_CONTROL.set(count as u32 | (1 << 31));
 }
 
+pub(crate) unsafe fn dma_copy32(src: *const u32, dest: *mut u32, count: usize) {
+    assert!(count < u16::MAX as usize);
+
+    DMA3_SOURCE_ADDR.set(src as u32);
+    DMA3_DEST_ADDR.set(dest as u32);
+
+    DMA3_CONTROL.set(count as u32 | TRANSFER_32_BIT | ENABLE);
+}
+
 pub(crate) fn dma3_exclusive<R>(f: impl FnOnce() -> R) -> R {
     const DMA0_CTRL_HI: MemoryMapped<u16> = unsafe { MemoryMapped::new(dma_control_addr(0) + 2) };
     const DMA1_CTRL_HI: MemoryMapped<u16> = unsafe { MemoryMapped::new(dma_control_addr(1) + 2) };
@@ -211,3 +501,53 @@ pub(crate) fn dma3_exclusive<R>(f: impl FnOnce() -> R) -> R {
         ret
     })
 }
+
+#[cfg(test)]
+mod test {
+    use alloc::vec;
+
+    #[test_case]
+    fn copy_and_fill(gba: &mut crate::Gba) {
+        let mut dmas = gba.dma.dma();
+
+        let source: alloc::vec::Vec<u32> = (0..100).collect();
+        let mut destination = vec![0u32; 100];
+        dmas.dma3.copy(&source, &mut destination);
+        assert_eq!(source, destination);
+
+        let mut halfwords = vec![0u16; 0x5000];
+        dmas.dma0.fill(0x1234, &mut halfwords);
+        assert!(halfwords.iter().all(|&x| x == 0x1234));
+
+        // 4 byte values which are only aligned to 2 bytes still fill whole words
+        #[repr(align(4))]
+        struct Pairs([[u16; 2]; 10]);
+        let mut pairs = Pairs([[0; 2]; 10]);
+        dmas.dma0.fill([1, 2], &mut pairs.0);
+        assert!(pairs.0.iter().all(|&x| x == [1, 2]));
+
+        let source = [1u16, 2, 3];
+        let mut destination = [0u16; 3];
+        dmas.dma0.copy(&source, &mut destination);
+        assert_eq!(destination, [1, 2, 3]);
+    }
+
+    #[test_case]
+    fn vblank_transfer_happens_at_vblank(gba: &mut crate::Gba) {
+        let mut dmas = gba.dma.all_dma(&mut gba.mixer);
+        let vblank = crate::interrupt::VBlank::get();
+
+        let mut destination = [0u32; 4];
+        vblank.wait_for_vblank();
+
+        // SAFETY: the transfer is dropped below
+        let transfer = unsafe { dmas.dma1.vblank_transfer(&[1, 2, 3, 4], &mut destination) };
+        assert!(!transfer.is_complete());
+
+        vblank.wait_for_vblank();
+        assert!(transfer.is_complete());
+        drop(transfer);
+
+        assert_eq!(destination, [1, 2, 3, 4]);
+    }
+}
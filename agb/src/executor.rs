@@ -0,0 +1,554 @@
+#![deny(missing_docs)]
+//! A simple executor for writing game logic with `async` functions.
+//!
+//! Sequences which last several frames, like cutscenes or animations, are
+//! usually written as state machines which are advanced a little each frame.
+//! With the executor you can instead write them as `async` functions, and
+//! `.await` the next frame, a number of frames, a timer or a button press.
+//!
+//! Only one executor can run at a time, on the main thread. Tasks are only
+//! polled when they have been woken, and the CPU is halted until the next
+//! interrupt when there is nothing to do.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::{executor, input::Button};
+//!
+//! async fn cutscene() {
+//!     agb::println!("Press A to continue");
+//!     executor::button_pressed(Button::A).await;
+//!
+//!     for _ in 0..3 {
+//!         agb::println!("...");
+//!         executor::wait_frames(30).await;
+//!     }
+//! }
+//!
+//! # fn foo() {
+//! executor::block_on(cutscene());
+//! # }
+//! ```
+
+use core::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+};
+
+use alloc::{boxed::Box, vec::Vec};
+use critical_section::Mutex;
+use portable_atomic::{AtomicBool, Ordering};
+
+use crate::{
+    input::{Button, ButtonController},
+    interrupt::{add_interrupt_handler, vblank_count, InterruptHandler},
+    syscall,
+    timer::Timer,
+};
+
+// Each waker is the id of its executor and the index of its task packed into one word, so waking
+// one never allocates and is safe in interrupts
+const TASK_BITS: usize = 16;
+// The task index used for the future passed to block_on
+const MAIN_TASK: usize = (1 << TASK_BITS) - 1;
+
+#[derive(Default)]
+struct Wakeups {
+    main: bool,
+    tasks: Vec<bool>,
+}
+
+impl Wakeups {
+    fn wake(&mut self, task: usize) {
+        if task == MAIN_TASK {
+            self.main = true;
+        } else if let Some(woken) = self.tasks.get_mut(task) {
+            *woken = true;
+        }
+    }
+
+    fn take(&mut self, task: usize) -> bool {
+        if task == MAIN_TASK {
+            core::mem::take(&mut self.main)
+        } else {
+            self.tasks.get_mut(task).map_or(false, core::mem::take)
+        }
+    }
+
+    fn any_woken(&self) -> bool {
+        self.main || self.tasks.contains(&true)
+    }
+}
+
+struct Executors {
+    next_id: usize,
+    // the wakeups of every executor which hasn't been dropped, along with its id
+    wakeups: Vec<(usize, Wakeups)>,
+    // wakers of futures which are waiting for something an interrupt does
+    waiting_for_interrupt: Vec<Waker>,
+}
+
+impl Executors {
+    fn wakeups(&mut self, id: usize) -> Option<&mut Wakeups> {
+        self.wakeups
+            .iter_mut()
+            .find(|(executor, _)| *executor == id)
+            .map(|(_, wakeups)| wakeups)
+    }
+}
+
+static EXECUTORS: Mutex<RefCell<Executors>> = Mutex::new(RefCell::new(Executors {
+    next_id: 0,
+    wakeups: Vec::new(),
+    waiting_for_interrupt: Vec::new(),
+}));
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+fn with_executors<R>(f: impl FnOnce(&mut Executors) -> R) -> R {
+    critical_section::with(|cs| f(&mut EXECUTORS.borrow_ref_mut(cs)))
+}
+
+fn with_wakeups<R>(id: usize, f: impl FnOnce(&mut Wakeups) -> R) -> R {
+    with_executors(|executors| {
+        f(executors
+            .wakeups(id)
+            .expect("an executor's wakeups are only removed when it is dropped"))
+    })
+}
+
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone_waker, wake, wake, drop_waker);
+
+fn clone_waker(data: *const ()) -> RawWaker {
+    RawWaker::new(data, &VTABLE)
+}
+
+fn wake(data: *const ()) {
+    let (id, task) = (data as usize >> TASK_BITS, data as usize & MAIN_TASK);
+
+    // the executor might have been dropped, in which case there's nothing to wake
+    with_executors(|executors| {
+        if let Some(wakeups) = executors.wakeups(id) {
+            wakeups.wake(task);
+        }
+    });
+}
+
+fn drop_waker(_data: *const ()) {}
+
+fn waker_for(id: usize, task: usize) -> Waker {
+    let data = (id << TASK_BITS | task) as *const ();
+    unsafe { Waker::from_raw(RawWaker::new(data, &VTABLE)) }
+}
+
+/// Wakes the task which owns `waker` after the next interrupt. Futures which are waiting for
+/// something that an interrupt handler does, such as counting vblanks, should call this
+/// whenever they return [`Poll::Pending`].
+pub(crate) fn wake_after_interrupt(waker: &Waker) {
+    let waker = waker.clone();
+    with_executors(|executors| executors.waiting_for_interrupt.push(waker));
+}
+
+type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+/// Runs `async` tasks, see the [module level documentation](self).
+pub struct Executor {
+    id: usize,
+    tasks: Vec<Option<Task>>,
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Executor {
+    /// Creates an executor with no tasks.
+    #[must_use]
+    pub fn new() -> Self {
+        let id = with_executors(|executors| {
+            let id = executors.next_id;
+            // ids only need to be unique among the executors which exist at the same time
+            executors.next_id = (id + 1) & (usize::MAX >> TASK_BITS);
+            executors.wakeups.push((id, Wakeups::default()));
+            id
+        });
+
+        Self {
+            id,
+            tasks: Vec::new(),
+        }
+    }
+
+    /// Adds a task which will run alongside the future given to [`block_on`](Executor::block_on)
+    /// or when you call [`run`](Executor::run).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the executor already has 65535 tasks.
+    pub fn spawn(&mut self, future: impl Future<Output = ()> + 'static) {
+        let task = Some(Box::pin(future) as Task);
+
+        let index = match self.tasks.iter().position(Option::is_none) {
+            Some(index) => {
+                self.tasks[index] = task;
+                index
+            }
+            None => {
+                assert!(self.tasks.len() < MAIN_TASK, "too many tasks");
+                self.tasks.push(task);
+                self.tasks.len() - 1
+            }
+        };
+
+        with_wakeups(self.id, |wakeups| {
+            if wakeups.tasks.len() <= index {
+                wakeups.tasks.resize(index + 1, false);
+            }
+            wakeups.tasks[index] = true;
+        });
+    }
+
+    /// The number of spawned tasks which haven't finished.
+    #[must_use]
+    pub fn task_count(&self) -> usize {
+        self.tasks.iter().flatten().count()
+    }
+
+    /// Runs `future` along with any spawned tasks until `future` finishes, returning its output.
+    /// Spawned tasks which haven't finished are kept and carry on the next time the executor runs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an executor is already running, for example if you call this from inside a task.
+    pub fn block_on<F: Future>(&mut self, future: F) -> F::Output {
+        start_running();
+
+        let mut future = core::pin::pin!(future);
+        let main_waker = waker_for(self.id, MAIN_TASK);
+        with_wakeups(self.id, |wakeups| wakeups.main = true);
+
+        let output = loop {
+            if with_wakeups(self.id, |wakeups| wakeups.take(MAIN_TASK)) {
+                if let Poll::Ready(output) =
+                    future.as_mut().poll(&mut Context::from_waker(&main_waker))
+                {
+                    break output;
+                }
+            }
+
+            self.poll_tasks();
+            self.wait_for_wakeup();
+        };
+
+        RUNNING.store(false, Ordering::SeqCst);
+        output
+    }
+
+    /// Runs the spawned tasks until they have all finished.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an executor is already running.
+    pub fn run(&mut self) {
+        start_running();
+
+        while self.task_count() > 0 {
+            self.poll_tasks();
+            // a task finishing wakes the main task, which only block_on polls, so it would
+            // otherwise stay woken and stop us from ever halting
+            with_wakeups(self.id, |wakeups| wakeups.take(MAIN_TASK));
+            self.wait_for_wakeup();
+        }
+
+        RUNNING.store(false, Ordering::SeqCst);
+    }
+
+    fn poll_tasks(&mut self) {
+        for (index, slot) in self.tasks.iter_mut().enumerate() {
+            let Some(task) = slot else {
+                continue;
+            };
+
+            if !with_wakeups(self.id, |wakeups| wakeups.take(index)) {
+                continue;
+            }
+
+            let waker = waker_for(self.id, index);
+            if task
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_ready()
+            {
+                *slot = None;
+                // check whether everything has finished without waiting for an interrupt
+                with_wakeups(self.id, |wakeups| wakeups.wake(MAIN_TASK));
+            }
+        }
+    }
+
+    // Halts the CPU until an interrupt if nothing has been woken, and then wakes everything which
+    // was waiting for one. These are woken even if we didn't halt, since a task which is always
+    // awake mustn't stop the others from noticing interrupts. Wakers belonging to other executors
+    // set the flags of those executors, so their wakeups aren't lost.
+    fn wait_for_wakeup(&self) {
+        critical_section::with(|cs| {
+            let any_woken = EXECUTORS
+                .borrow_ref_mut(cs)
+                .wakeups(self.id)
+                .is_some_and(|wakeups| wakeups.any_woken());
+
+            if !any_woken {
+                // Interrupts are disabled here, but the CPU still wakes up when one is requested and
+                // the handler then runs when the critical section ends. Halting inside the critical
+                // section means we can't miss a wakeup between checking and halting.
+                syscall::halt();
+            }
+        });
+
+        for waker in
+            with_executors(|executors| core::mem::take(&mut executors.waiting_for_interrupt))
+        {
+            waker.wake();
+        }
+    }
+}
+
+impl Drop for Executor {
+    fn drop(&mut self) {
+        with_executors(|executors| executors.wakeups.retain(|(id, _)| *id != self.id));
+    }
+}
+
+fn start_running() {
+    assert!(
+        !RUNNING.swap(true, Ordering::SeqCst),
+        "an executor is already running"
+    );
+}
+
+/// Runs `future` until it finishes on a new [`Executor`], returning its output.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    Executor::new().block_on(future)
+}
+
+/// A future which finishes after a number of vblanks, see [`wait_frames`].
+#[must_use = "futures do nothing unless you `.await` them"]
+pub struct WaitFrames {
+    until: usize,
+}
+
+impl Future for WaitFrames {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if vblank_count() >= self.until {
+            Poll::Ready(())
+        } else {
+            wake_after_interrupt(cx.waker());
+            Poll::Pending
+        }
+    }
+}
+
+/// Waits until `frames` vblanks have happened. The count starts when this is called rather than
+/// when it is first awaited.
+pub fn wait_frames(frames: usize) -> WaitFrames {
+    WaitFrames {
+        until: vblank_count() + frames,
+    }
+}
+
+/// Waits until the start of the next vblank.
+pub fn vblank() -> WaitFrames {
+    wait_frames(1)
+}
+
+static TIMER_OVERFLOWED: [AtomicBool; 4] = [
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+    AtomicBool::new(false),
+];
+
+/// A future which finishes when a timer overflows, see [`timer`].
+#[must_use = "futures do nothing unless you `.await` them"]
+pub struct TimerOverflow<'a> {
+    timer_number: usize,
+    _timer: &'a mut Timer,
+    _handler: InterruptHandler,
+}
+
+impl Future for TimerOverflow<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if TIMER_OVERFLOWED[self.timer_number].load(Ordering::SeqCst) {
+            Poll::Ready(())
+        } else {
+            wake_after_interrupt(cx.waker());
+            Poll::Pending
+        }
+    }
+}
+
+/// Waits until `timer` next overflows. The timer's interrupt is enabled for you, but you need to
+/// set up and enable the timer itself.
+pub fn timer(timer: &mut Timer) -> TimerOverflow<'_> {
+    let timer_number = timer.interrupt() as usize - crate::interrupt::Interrupt::Timer0 as usize;
+    TIMER_OVERFLOWED[timer_number].store(false, Ordering::SeqCst);
+
+    timer.set_interrupt(true);
+
+    // safety: doesn't allocate
+    let handler = unsafe {
+        add_interrupt_handler(timer.interrupt(), move |_| {
+            TIMER_OVERFLOWED[timer_number].store(true, Ordering::SeqCst);
+        })
+    };
+
+    TimerOverflow {
+        timer_number,
+        _timer: timer,
+        _handler: handler,
+    }
+}
+
+/// A future which finishes when a button is pressed, see [`button_pressed`].
+#[must_use = "futures do nothing unless you `.await` them"]
+pub struct ButtonPressed {
+    button: Button,
+    input: ButtonController,
+}
+
+impl Future for ButtonPressed {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.input.update();
+
+        if self.input.is_just_pressed(self.button) {
+            Poll::Ready(())
+        } else {
+            // make sure there are vblank interrupts to check the buttons again after
+            vblank_count();
+            wake_after_interrupt(cx.waker());
+            Poll::Pending
+        }
+    }
+}
+
+/// Waits until `button` is pressed. If it is already held down when this is called, it has to be
+/// released and pressed again.
+pub fn button_pressed(button: Button) -> ButtonPressed {
+    ButtonPressed {
+        button,
+        input: ButtonController::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::{Cell, RefCell};
+
+    use alloc::{rc::Rc, vec};
+
+    use super::*;
+
+    #[test_case]
+    fn waits_for_frames(_gba: &mut crate::Gba) {
+        let start = vblank_count();
+        let result = block_on(async {
+            wait_frames(3).await;
+            vblank().await;
+            5
+        });
+
+        assert_eq!(result, 5);
+        assert_eq!(vblank_count() - start, 4);
+    }
+
+    #[test_case]
+    fn spawned_tasks_run_alongside(_gba: &mut crate::Gba) {
+        let order = Rc::new(RefCell::new(vec![]));
+        let mut executor = Executor::new();
+
+        for (task, frames) in [(1, 2), (2, 1)] {
+            let order = order.clone();
+            executor.spawn(async move {
+                order.borrow_mut().push(task);
+                wait_frames(frames).await;
+                order.borrow_mut().push(task * 10);
+            });
+        }
+
+        assert_eq!(executor.task_count(), 2);
+        executor.run();
+
+        assert_eq!(executor.task_count(), 0);
+        assert_eq!(*order.borrow(), [1, 2, 20, 10]);
+    }
+
+    #[test_case]
+    fn finished_tasks_do_not_keep_run_awake(_gba: &mut crate::Gba) {
+        let polls = Rc::new(Cell::new(0));
+        let mut executor = Executor::new();
+
+        executor.spawn(async {});
+        executor.spawn({
+            let polls = polls.clone();
+            let until = vblank_count() + 3;
+            core::future::poll_fn(move |cx| {
+                polls.set(polls.get() + 1);
+                if vblank_count() >= until {
+                    Poll::Ready(())
+                } else {
+                    wake_after_interrupt(cx.waker());
+                    Poll::Pending
+                }
+            })
+        });
+
+        executor.run();
+
+        // polled about once a frame rather than on every loop of an executor which never halts
+        assert!(polls.get() <= 10, "polled {} times", polls.get());
+    }
+
+    #[test_case]
+    fn executors_keep_their_own_wakeups(_gba: &mut crate::Gba) {
+        let mut first = Executor::new();
+        first.spawn(async { wait_frames(1).await });
+        // finishes straight away without polling the spawned task, so it is still woken
+        first.block_on(async {});
+
+        // this task is in the same slot, but mustn't take the first executor's wakeup
+        let mut second = Executor::new();
+        second.spawn(async { wait_frames(2).await });
+        second.run();
+
+        first.run();
+        assert_eq!(first.task_count(), 0);
+    }
+
+    #[test_case]
+    fn timers_wake_tasks(gba: &mut crate::Gba) {
+        let mut timers = gba.timers.timers();
+        let timer = &mut timers.timer2;
+        timer
+            .set_divider(crate::timer::Divider::Divider1024)
+            .set_overflow_amount(100)
+            .set_enabled(true);
+
+        block_on(async {
+            self::timer(timer).await;
+        });
+
+        timer.set_enabled(false);
+    }
+}
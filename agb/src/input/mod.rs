@@ -0,0 +1,909 @@
+#![deny(missing_docs)]
+use crate::fixnum::Vector2D;
+use bitflags::bitflags;
+
+mod replay;
+
+pub use replay::{ButtonRecorder, InputRecording, InputReplay};
+
+/// Tri-state enum. Allows for -1, 0 and +1.
+/// Useful if checking if the D-Pad is pointing left, right, or unpressed.
+///
+/// Note that [Tri] can be converted directly to a signed integer, so can easily be used to update positions of things in games
+///
+/// # Examples
+/// ```rust,no_run
+/// # #![no_std]
+/// use agb::input::Tri;
+///
+/// # fn main() {
+/// let x = 5;
+/// let tri = Tri::Positive; // e.g. from button_controller.x_tri()
+///
+/// assert_eq!(x + tri as i32, 6);
+/// # }
+/// ```
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub enum Tri {
+    /// Right or down
+    Positive = 1,
+    /// Unpressed
+    Zero = 0,
+    /// Left or up
+    Negative = -1,
+}
+
+impl From<(bool, bool)> for Tri {
+    fn from(a: (bool, bool)) -> Tri {
+        let b1 = i8::from(a.0);
+        let b2 = i8::from(a.1);
+        unsafe { core::mem::transmute(b2 - b1) }
+    }
+}
+
+bitflags! {
+    /// Represents a button on the GBA
+    #[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+    pub struct Button: u32 {
+        /// The A button
+        const A = 1 << 0;
+        /// The B button
+        const B = 1 << 1;
+        /// The SELECT button
+        const SELECT = 1 << 2;
+        /// The START button
+        const START = 1 << 3;
+        /// The RIGHT button on the D-Pad
+        const RIGHT = 1 << 4;
+        /// The LEFT button on the D-Pad
+        const LEFT = 1 << 5;
+        /// The UP button on the D-Pad
+        const UP = 1 << 6;
+        /// The DOWN button on the D-Pad
+        const DOWN = 1 << 7;
+        /// The R button on the D-Pad
+        const R = 1 << 8;
+        /// The L button on the D-Pad
+        const L = 1 << 9;
+    }
+}
+
+const BUTTON_INPUT: *mut u16 = (0x04000130) as *mut u16;
+
+// const BUTTON_INTERRUPT: *mut u16 = (0x04000132) as *mut u16;
+
+/// Helper to make it easy to get the current state of the GBA's buttons.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// use agb::input::{ButtonController, Tri};
+///
+/// # fn main() {
+/// let mut input = ButtonController::new();
+///
+/// loop {
+///     input.update(); // call update every loop
+///
+///     match input.x_tri() {
+///         Tri::Negative => { /* left is being pressed */ }
+///         Tri::Positive => { /* right is being pressed */ }
+///         Tri::Zero => { /* Neither left nor right (or both) are pressed */ }
+///     }
+/// }
+/// # }
+/// ```
+pub struct ButtonController {
+    previous: u16,
+    current: u16,
+}
+
+impl Default for ButtonController {
+    fn default() -> Self {
+        ButtonController::new()
+    }
+}
+
+impl ButtonController {
+    /// Create a new ButtonController.
+    /// This is the preferred way to create it.
+    #[must_use]
+    pub fn new() -> Self {
+        let pressed = !unsafe { BUTTON_INPUT.read_volatile() };
+        ButtonController {
+            previous: pressed,
+            current: pressed,
+        }
+    }
+
+    /// Updates the state of the button controller.
+    /// You should call this every frame (either at the start or the end) to ensure that you have the latest state of each button press.
+    /// Calls to any method won't change until you call this.
+    pub fn update(&mut self) {
+        self.previous = self.current;
+        self.current = !unsafe { BUTTON_INPUT.read_volatile() };
+    }
+
+    /// Updates the state of the button controller using the provided buttons rather than the
+    /// current state of the hardware. This behaves exactly like [`update()`](ButtonController::update())
+    /// as if `pressed` were the buttons currently held down.
+    ///
+    /// Useful for tests, or for substituting input from some other source.
+    pub fn update_with_state(&mut self, pressed: Button) {
+        self.previous = self.current;
+        self.current = pressed.bits() as u16;
+    }
+
+    /// Returns the buttons which are currently pressed.
+    #[must_use]
+    pub fn pressed_buttons(&self) -> Button {
+        Button::from_bits_truncate(u32::from(self.current))
+    }
+
+    /// Returns [Tri::Positive] if right is pressed, [Tri::Negative] if left is pressed and [Tri::Zero] if neither or both are pressed.
+    /// This is the normal behaviour you'll want if you're using orthogonal inputs.
+    #[must_use]
+    pub fn x_tri(&self) -> Tri {
+        let left = self.is_pressed(Button::LEFT);
+        let right = self.is_pressed(Button::RIGHT);
+
+        (left, right).into()
+    }
+
+    /// Returns [Tri::Positive] if down is pressed, [Tri::Negative] if up is pressed and [Tri::Zero] if neither or both are pressed.
+    /// This is the normal behaviour you'll want if you're using orthogonal inputs.
+    #[must_use]
+    pub fn y_tri(&self) -> Tri {
+        let up = self.is_pressed(Button::UP);
+        let down = self.is_pressed(Button::DOWN);
+
+        (up, down).into()
+    }
+
+    /// Returns true if all the buttons specified in `keys` are pressed.
+    #[must_use]
+    pub fn vector<T>(&self) -> Vector2D<T>
+    where
+        T: From<i32> + crate::fixnum::FixedWidthUnsignedInteger,
+    {
+        (self.x_tri() as i32, self.y_tri() as i32).into()
+    }
+
+    #[must_use]
+    /// Returns [Tri::Positive] if left was just pressed, [Tri::Negative] if right was just pressed and [Tri::Zero] if neither or both are just pressed.
+    ///
+    /// Also returns [Tri::Zero] after the call to [`update()`](ButtonController::update()) if the button is still held.
+    pub fn just_pressed_x_tri(&self) -> Tri {
+        let left = self.is_just_pressed(Button::LEFT);
+        let right = self.is_just_pressed(Button::RIGHT);
+
+        (left, right).into()
+    }
+
+    #[must_use]
+    /// Returns [Tri::Positive] if down was just pressed, [Tri::Negative] if up was just pressed and [Tri::Zero] if neither or both are just pressed.
+    ///
+    /// Also returns [Tri::Zero] after the call to [`update()`](ButtonController::update()) if the button is still held.
+    pub fn just_pressed_y_tri(&self) -> Tri {
+        let up = self.is_just_pressed(Button::UP);
+        let down = self.is_just_pressed(Button::DOWN);
+
+        (up, down).into()
+    }
+
+    #[must_use]
+    /// Returns a vector which represents the direction the button was just pressed in.
+    pub fn just_pressed_vector<T>(&self) -> Vector2D<T>
+    where
+        T: From<i32> + crate::fixnum::FixedWidthUnsignedInteger,
+    {
+        (
+            self.just_pressed_x_tri() as i32,
+            self.just_pressed_y_tri() as i32,
+        )
+            .into()
+    }
+
+    #[must_use]
+    /// Returns `true` if the provided keys are all pressed, and `false` if not.
+    pub fn is_pressed(&self, keys: Button) -> bool {
+        let currently_pressed = u32::from(self.current);
+        let keys = keys.bits();
+        (currently_pressed & keys) != 0
+    }
+
+    /// Returns true if all the buttons specified in `keys` are not pressed. Equivalent to `!is_pressed(keys)`.
+    #[must_use]
+    pub fn is_released(&self, keys: Button) -> bool {
+        !self.is_pressed(keys)
+    }
+
+    /// Returns true if all the buttons specified in `keys` went from not pressed to pressed in the last frame.
+    /// Very useful for menu navigation or selection if you want the players actions to only happen for one frame.
+    ///
+    /// # Example
+    /// ```no_run,rust
+    /// # #![no_std]
+    /// use agb::input::{Button, ButtonController};
+    ///
+    /// # fn main() {
+    /// let mut button_controller = ButtonController::new();
+    ///
+    /// loop {
+    ///     button_controller.update();
+    ///
+    ///     if button_controller.is_just_pressed(Button::A) {
+    ///         // A button was just pressed, maybe select the currently selected item
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn is_just_pressed(&self, keys: Button) -> bool {
+        let current = u32::from(self.current);
+        let previous = u32::from(self.previous);
+        let keys = keys.bits();
+        ((current & keys) != 0) && ((previous & keys) == 0)
+    }
+
+    /// Returns true if all the buttons specified in `keys` went from pressed to not pressed in the last frame.
+    /// Very useful for menu navigation or selection if you want players actions to only happen for one frame.
+    #[must_use]
+    pub fn is_just_released(&self, keys: Button) -> bool {
+        let current = u32::from(self.current);
+        let previous = u32::from(self.previous);
+        let keys = keys.bits();
+        ((current & keys) == 0) && ((previous & keys) != 0)
+    }
+}
+
+const NUM_BUTTONS: usize = 10;
+
+fn buttons(keys: Button) -> impl Iterator<Item = usize> {
+    (0..NUM_BUTTONS).filter(move |i| keys.bits() & (1 << i) != 0)
+}
+
+/// Provides auto-repeat of held buttons, as you would expect when holding a direction in a menu.
+///
+/// A button is considered _repeated_ on the frame it is first pressed, then once `delay` frames
+/// have passed with it still held, and then every `rate` frames after that for as long as it is held.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// use agb::input::{Button, ButtonController, ButtonRepeat};
+///
+/// # fn main() {
+/// let mut input = ButtonController::new();
+/// let mut repeat = ButtonRepeat::new(20, 5);
+///
+/// loop {
+///     input.update();
+///     repeat.update(&input);
+///
+///     if repeat.is_repeated(Button::DOWN) {
+///         // move the menu cursor down
+///     }
+/// }
+/// # }
+/// ```
+pub struct ButtonRepeat {
+    delay: u16,
+    rate: u16,
+    // wider than delay and rate so that delay + rate can't overflow
+    held_frames: [u32; NUM_BUTTONS],
+}
+
+impl ButtonRepeat {
+    /// Creates a new ButtonRepeat which first repeats after `delay` frames and then every `rate` frames.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either `delay` or `rate` are 0.
+    #[must_use]
+    pub const fn new(delay: u16, rate: u16) -> Self {
+        assert!(delay != 0, "delay must not be 0");
+        assert!(rate != 0, "rate must not be 0");
+
+        Self {
+            delay,
+            rate,
+            held_frames: [0; NUM_BUTTONS],
+        }
+    }
+
+    /// Updates the held durations of each button. This should be called once per frame after
+    /// [`ButtonController::update()`].
+    pub fn update(&mut self, input: &ButtonController) {
+        for (i, held) in self.held_frames.iter_mut().enumerate() {
+            if input.is_pressed(Button::from_bits_retain(1 << i)) {
+                *held += 1;
+                // keep the count in the repeating window so holding for a long time can't overflow
+                if *held > u32::from(self.delay) + u32::from(self.rate) {
+                    *held -= u32::from(self.rate);
+                }
+            } else {
+                *held = 0;
+            }
+        }
+    }
+
+    /// Returns `true` if any of the buttons specified in `keys` should trigger this frame.
+    #[must_use]
+    pub fn is_repeated(&self, keys: Button) -> bool {
+        buttons(keys).any(|i| {
+            let held = self.held_frames[i];
+            held == 1 || held == u32::from(self.delay) + 1
+        })
+    }
+
+    /// Returns [Tri::Positive] if right was repeated, [Tri::Negative] if left was repeated and [Tri::Zero] if neither or both were repeated.
+    #[must_use]
+    pub fn x_tri(&self) -> Tri {
+        (
+            self.is_repeated(Button::LEFT),
+            self.is_repeated(Button::RIGHT),
+        )
+            .into()
+    }
+
+    /// Returns [Tri::Positive] if down was repeated, [Tri::Negative] if up was repeated and [Tri::Zero] if neither or both were repeated.
+    #[must_use]
+    pub fn y_tri(&self) -> Tri {
+        (self.is_repeated(Button::UP), self.is_repeated(Button::DOWN)).into()
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct HistoryEntry {
+    pressed: u16,
+    just_pressed: u16,
+}
+
+/// Keeps a record of the last `N` frames of button input, which allows for input buffering.
+///
+/// For example, a platformer could allow a jump if A was pressed within the last few frames
+/// before the player landed, rather than requiring the press on exactly the right frame.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// use agb::input::{Button, ButtonController, ButtonHistory};
+///
+/// # fn main() {
+/// # let on_ground = true;
+/// let mut input = ButtonController::new();
+/// let mut history = ButtonHistory::<8>::new();
+///
+/// loop {
+///     input.update();
+///     history.update(&input);
+///
+///     if on_ground && history.is_just_pressed_within(Button::A, 4) {
+///         history.clear(); // so that the same press doesn't cause a second jump
+///         // jump
+///     }
+/// }
+/// # }
+/// ```
+pub struct ButtonHistory<const N: usize> {
+    entries: [HistoryEntry; N],
+    // index of the most recently written entry
+    head: usize,
+}
+
+impl<const N: usize> ButtonHistory<N> {
+    /// Creates a new, empty button history.
+    #[must_use]
+    pub const fn new() -> Self {
+        assert!(N != 0, "history must store at least one frame");
+
+        Self {
+            entries: [HistoryEntry {
+                pressed: 0,
+                just_pressed: 0,
+            }; N],
+            head: 0,
+        }
+    }
+
+    /// Records the current frame's input. This should be called once per frame after
+    /// [`ButtonController::update()`].
+    pub fn update(&mut self, input: &ButtonController) {
+        self.head = (self.head + 1) % N;
+        self.entries[self.head] = HistoryEntry {
+            pressed: input.current,
+            just_pressed: input.current & !input.previous,
+        };
+    }
+
+    /// Forgets all recorded input, for example once a buffered press has been acted upon.
+    pub fn clear(&mut self) {
+        self.entries = [HistoryEntry::default(); N];
+    }
+
+    fn last_frames(&self, frames: usize) -> impl Iterator<Item = &HistoryEntry> {
+        (0..frames.min(N)).map(move |frames_ago| &self.entries[(self.head + N - frames_ago) % N])
+    }
+
+    /// Returns `true` if any of the buttons in `keys` went from not pressed to pressed within the last `frames` frames.
+    /// A value of 1 for `frames` only considers the current frame. Anything beyond `N` frames ago has been forgotten.
+    #[must_use]
+    pub fn is_just_pressed_within(&self, keys: Button, frames: usize) -> bool {
+        let keys = keys.bits() as u16;
+        self.last_frames(frames)
+            .any(|entry| entry.just_pressed & keys != 0)
+    }
+
+    /// Returns `true` if any of the buttons in `keys` were held down at some point within the last `frames` frames.
+    /// A value of 1 for `frames` only considers the current frame. Anything beyond `N` frames ago has been forgotten.
+    #[must_use]
+    pub fn is_pressed_within(&self, keys: Button, frames: usize) -> bool {
+        let keys = keys.bits() as u16;
+        self.last_frames(frames)
+            .any(|entry| entry.pressed & keys != 0)
+    }
+
+    /// Returns the buttons which were pressed `frames_ago` frames ago, where 0 is the current frame.
+    /// Returns `None` if that frame is further back than the history stores.
+    #[must_use]
+    pub fn pressed_buttons(&self, frames_ago: usize) -> Option<Button> {
+        if frames_ago >= N {
+            return None;
+        }
+
+        let entry = &self.entries[(self.head + N - frames_ago) % N];
+        Some(Button::from_bits_truncate(u32::from(entry.pressed)))
+    }
+}
+
+impl<const N: usize> Default for ButtonHistory<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const DIRECTIONS: u16 =
+    (Button::UP.bits() | Button::DOWN.bits() | Button::LEFT.bits() | Button::RIGHT.bits()) as u16;
+
+/// Detects a sequence of inputs such as ↓↘→+A, where each step must happen within a given number of frames of the previous one.
+///
+/// Each step is a combination of [`Button`]s. A step matches on the frame where it becomes true, where
+/// * if the step contains any directions, exactly those directions must be held on the D-Pad, and
+/// * every other button in the step must be held.
+///
+/// So ↓↘→+A would be written as `[Button::DOWN, Button::DOWN | Button::RIGHT, Button::RIGHT | Button::A]`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// use agb::input::{Button, ButtonController, ButtonSequence};
+///
+/// const FIREBALL: &[Button] = &[
+///     Button::DOWN,
+///     Button::DOWN.union(Button::RIGHT),
+///     Button::RIGHT.union(Button::A),
+/// ];
+///
+/// # fn main() {
+/// let mut input = ButtonController::new();
+/// let mut fireball = ButtonSequence::new(FIREBALL, 8);
+///
+/// loop {
+///     input.update();
+///
+///     if fireball.update(&input) {
+///         // throw a fireball
+///     }
+/// }
+/// # }
+/// ```
+pub struct ButtonSequence<'a> {
+    steps: &'a [Button],
+    max_gap: u16,
+    progress: usize,
+    frames_since_step: u16,
+    previous: u16,
+}
+
+impl<'a> ButtonSequence<'a> {
+    /// Creates a new sequence detector. Each step in `steps` must be reached within `max_gap` frames of the previous step.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `steps` is empty.
+    #[must_use]
+    pub const fn new(steps: &'a [Button], max_gap: u16) -> Self {
+        assert!(!steps.is_empty(), "sequence must have at least one step");
+
+        Self {
+            steps,
+            max_gap,
+            progress: 0,
+            frames_since_step: 0,
+            previous: 0,
+        }
+    }
+
+    fn step_matches(step: Button, pressed: u16) -> bool {
+        let step = step.bits() as u16;
+        let directions = step & DIRECTIONS;
+
+        let directions_match = directions == 0 || pressed & DIRECTIONS == directions;
+        let buttons = step & !DIRECTIONS;
+
+        directions_match && pressed & buttons == buttons
+    }
+
+    fn step_reached(&self, step: Button, pressed: u16) -> bool {
+        Self::step_matches(step, pressed) && !Self::step_matches(step, self.previous)
+    }
+
+    /// Advances the sequence using the current frame's input. This should be called once per frame
+    /// after [`ButtonController::update()`]. Returns `true` on the frame the final step of the sequence is reached.
+    pub fn update(&mut self, input: &ButtonController) -> bool {
+        let pressed = input.current;
+
+        if self.progress > 0 {
+            self.frames_since_step = self.frames_since_step.saturating_add(1);
+            if self.frames_since_step > self.max_gap {
+                self.reset();
+            }
+        }
+
+        let mut completed = false;
+        if self.step_reached(self.steps[self.progress], pressed) {
+            self.progress += 1;
+            self.frames_since_step = 0;
+
+            if self.progress == self.steps.len() {
+                self.progress = 0;
+                completed = true;
+            }
+        } else if self.progress > 0 && self.step_reached(self.steps[0], pressed) {
+            // starting again from the beginning
+            self.progress = 1;
+            self.frames_since_step = 0;
+        }
+
+        self.previous = pressed;
+        completed
+    }
+
+    /// Forgets any progress made through the sequence.
+    pub fn reset(&mut self) {
+        self.progress = 0;
+        self.frames_since_step = 0;
+    }
+}
+
+/// Detects when a combination of buttons has been held down together for a number of frames.
+/// This is useful for things like a soft reset with A + B + START + SELECT.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// use agb::input::{Button, ButtonChord, ButtonController};
+///
+/// # fn main() {
+/// let mut input = ButtonController::new();
+/// let mut soft_reset = ButtonChord::new(Button::A | Button::B | Button::START | Button::SELECT, 30);
+///
+/// loop {
+///     input.update();
+///
+///     if soft_reset.update(&input) {
+///         // return to the title screen
+///     }
+/// }
+/// # }
+/// ```
+pub struct ButtonChord {
+    keys: Button,
+    hold_frames: u16,
+    held: u16,
+}
+
+impl ButtonChord {
+    /// Creates a new chord detector which triggers after all of `keys` have been held for `hold_frames` frames.
+    #[must_use]
+    pub const fn new(keys: Button, hold_frames: u16) -> Self {
+        Self {
+            keys,
+            hold_frames,
+            held: 0,
+        }
+    }
+
+    /// Updates how long the chord has been held. This should be called once per frame after [`ButtonController::update()`].
+    /// Returns `true` on the single frame where the chord has been held for long enough.
+    pub fn update(&mut self, input: &ButtonController) -> bool {
+        let was_held = self.is_held();
+
+        if input.pressed_buttons().contains(self.keys) {
+            self.held = self.held.saturating_add(1);
+        } else {
+            self.held = 0;
+        }
+
+        !was_held && self.is_held()
+    }
+
+    /// Returns `true` if every button in the chord is currently held, and has been for at least the required number of frames.
+    #[must_use]
+    pub fn is_held(&self) -> bool {
+        self.held >= self.hold_frames.max(1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Gba;
+
+    fn controller() -> ButtonController {
+        let mut input = ButtonController::new();
+        input.update_with_state(Button::empty());
+        input.update_with_state(Button::empty());
+        input
+    }
+
+    #[test_case]
+    fn update_with_state_sets_pressed_buttons(_gba: &mut Gba) {
+        let mut input = controller();
+
+        input.update_with_state(Button::A | Button::LEFT);
+        assert!(input.is_just_pressed(Button::A));
+        assert_eq!(input.x_tri(), Tri::Negative);
+        assert_eq!(input.pressed_buttons(), Button::A | Button::LEFT);
+
+        input.update_with_state(Button::LEFT);
+        assert!(input.is_just_released(Button::A));
+        assert!(!input.is_just_pressed(Button::LEFT));
+    }
+
+    #[test_case]
+    fn repeat_triggers_after_delay_then_at_rate(_gba: &mut Gba) {
+        let mut input = controller();
+        let mut repeat = ButtonRepeat::new(4, 2);
+
+        let mut triggered_frames = [false; 12];
+        for triggered in triggered_frames.iter_mut() {
+            input.update_with_state(Button::DOWN);
+            repeat.update(&input);
+            *triggered = repeat.is_repeated(Button::DOWN);
+        }
+
+        assert_eq!(
+            triggered_frames,
+            [true, false, false, false, true, false, true, false, true, false, true, false]
+        );
+        assert_eq!(repeat.y_tri(), Tri::Zero);
+        assert!(!repeat.is_repeated(Button::UP));
+    }
+
+    #[test_case]
+    fn repeat_restarts_on_release(_gba: &mut Gba) {
+        let mut input = controller();
+        let mut repeat = ButtonRepeat::new(3, 1);
+
+        for _ in 0..10 {
+            input.update_with_state(Button::RIGHT);
+            repeat.update(&input);
+        }
+
+        input.update_with_state(Button::empty());
+        repeat.update(&input);
+        assert!(!repeat.is_repeated(Button::RIGHT));
+
+        input.update_with_state(Button::RIGHT);
+        repeat.update(&input);
+        assert_eq!(repeat.x_tri(), Tri::Positive);
+
+        input.update_with_state(Button::RIGHT);
+        repeat.update(&input);
+        assert_eq!(repeat.x_tri(), Tri::Zero);
+    }
+
+    #[test_case]
+    fn repeat_handles_the_largest_delay_and_rate(_gba: &mut Gba) {
+        let mut input = controller();
+        let mut repeat = ButtonRepeat::new(u16::MAX, u16::MAX);
+
+        for _ in 0..u16::MAX {
+            input.update_with_state(Button::A);
+            repeat.update(&input);
+        }
+
+        input.update_with_state(Button::A);
+        repeat.update(&input);
+        assert!(repeat.is_repeated(Button::A));
+
+        input.update_with_state(Button::A);
+        repeat.update(&input);
+        assert!(!repeat.is_repeated(Button::A));
+    }
+
+    #[test_case]
+    fn history_buffers_presses(_gba: &mut Gba) {
+        let mut input = controller();
+        let mut history = ButtonHistory::<8>::new();
+
+        input.update_with_state(Button::A);
+        history.update(&input);
+
+        for _ in 0..3 {
+            input.update_with_state(Button::empty());
+            history.update(&input);
+        }
+
+        assert!(history.is_just_pressed_within(Button::A, 4));
+        assert!(!history.is_just_pressed_within(Button::A, 3));
+        assert!(history.is_pressed_within(Button::A, 4));
+        assert!(!history.is_just_pressed_within(Button::B, 8));
+        assert_eq!(history.pressed_buttons(3), Some(Button::A));
+        assert_eq!(history.pressed_buttons(8), None);
+
+        history.clear();
+        assert!(!history.is_just_pressed_within(Button::A, 8));
+    }
+
+    #[test_case]
+    fn history_forgets_old_frames(_gba: &mut Gba) {
+        let mut input = controller();
+        let mut history = ButtonHistory::<4>::new();
+
+        input.update_with_state(Button::B);
+        history.update(&input);
+
+        for _ in 0..4 {
+            input.update_with_state(Button::empty());
+            history.update(&input);
+        }
+
+        assert!(!history.is_just_pressed_within(Button::B, 100));
+    }
+
+    #[test_case]
+    fn holding_button_in_history_is_only_one_press(_gba: &mut Gba) {
+        let mut input = controller();
+        let mut history = ButtonHistory::<8>::new();
+
+        input.update_with_state(Button::A);
+        history.update(&input);
+
+        for _ in 0..3 {
+            input.update_with_state(Button::A);
+            history.update(&input);
+        }
+
+        assert!(!history.is_just_pressed_within(Button::A, 3));
+        assert!(history.is_pressed_within(Button::A, 1));
+    }
+
+    const FIREBALL: &[Button] = &[
+        Button::DOWN,
+        Button::DOWN.union(Button::RIGHT),
+        Button::RIGHT.union(Button::A),
+    ];
+
+    fn feed_sequence(
+        sequence: &mut ButtonSequence,
+        input: &mut ButtonController,
+        states: &[Button],
+    ) -> bool {
+        let mut completed = false;
+        for &state in states {
+            input.update_with_state(state);
+            completed = sequence.update(input);
+        }
+        completed
+    }
+
+    #[test_case]
+    fn sequence_detects_motion(_gba: &mut Gba) {
+        let mut input = controller();
+        let mut sequence = ButtonSequence::new(FIREBALL, 4);
+
+        assert!(feed_sequence(
+            &mut sequence,
+            &mut input,
+            &[
+                Button::DOWN,
+                Button::DOWN,
+                Button::DOWN | Button::RIGHT,
+                Button::RIGHT,
+                Button::RIGHT | Button::A,
+            ],
+        ));
+
+        // holding the final input doesn't trigger it again
+        assert!(!feed_sequence(
+            &mut sequence,
+            &mut input,
+            &[Button::RIGHT | Button::A],
+        ));
+    }
+
+    #[test_case]
+    fn sequence_times_out(_gba: &mut Gba) {
+        let mut input = controller();
+        let mut sequence = ButtonSequence::new(FIREBALL, 2);
+
+        assert!(!feed_sequence(
+            &mut sequence,
+            &mut input,
+            &[
+                Button::DOWN,
+                Button::DOWN | Button::RIGHT,
+                Button::RIGHT,
+                Button::RIGHT,
+                Button::RIGHT,
+                Button::RIGHT | Button::A,
+            ],
+        ));
+    }
+
+    #[test_case]
+    fn sequence_rejects_wrong_order(_gba: &mut Gba) {
+        let mut input = controller();
+        let mut sequence = ButtonSequence::new(FIREBALL, 4);
+
+        assert!(!feed_sequence(
+            &mut sequence,
+            &mut input,
+            &[
+                Button::RIGHT,
+                Button::DOWN | Button::RIGHT,
+                Button::DOWN,
+                Button::DOWN | Button::A,
+            ],
+        ));
+    }
+
+    #[test_case]
+    fn sequence_can_restart_part_way_through(_gba: &mut Gba) {
+        let mut input = controller();
+        let mut sequence = ButtonSequence::new(FIREBALL, 4);
+
+        assert!(feed_sequence(
+            &mut sequence,
+            &mut input,
+            &[
+                Button::DOWN,
+                Button::empty(),
+                Button::DOWN,
+                Button::DOWN | Button::RIGHT,
+                Button::RIGHT | Button::A,
+            ],
+        ));
+    }
+
+    #[test_case]
+    fn chord_triggers_once_after_hold(_gba: &mut Gba) {
+        let mut input = controller();
+        let soft_reset = Button::A | Button::B | Button::START | Button::SELECT;
+        let mut chord = ButtonChord::new(soft_reset, 3);
+
+        input.update_with_state(soft_reset);
+        assert!(!chord.update(&input));
+        input.update_with_state(soft_reset | Button::L);
+        assert!(!chord.update(&input));
+        input.update_with_state(soft_reset);
+        assert!(chord.update(&input));
+        assert!(chord.is_held());
+
+        input.update_with_state(soft_reset);
+        assert!(!chord.update(&input));
+        assert!(chord.is_held());
+
+        input.update_with_state(Button::A | Button::B | Button::START);
+        assert!(!chord.update(&input));
+        assert!(!chord.is_held());
+    }
+}
@@ -0,0 +1,264 @@
+use alloc::{borrow::Cow, vec::Vec};
+use core::{fmt, ops::Deref};
+
+use super::{Button, ButtonController};
+
+/// Wraps a [`ButtonController`] so that the buttons pressed on each frame can be recorded, or a
+/// previous recording played back in place of the hardware.
+///
+/// This dereferences to the wrapped [`ButtonController`], so it can be used anywhere a
+/// `&ButtonController` is expected.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// use agb::input::{ButtonRecorder, InputReplay};
+///
+/// # fn main() {
+/// static DEMO: &[u8] = &[0x00, 0xfc]; // normally you would use include_bytes!
+///
+/// let mut input = ButtonRecorder::new();
+/// input.start_replay(InputReplay::new(DEMO));
+///
+/// while input.is_replaying() {
+///     input.update();
+///     // run the game as normal
+/// }
+/// # }
+/// ```
+pub struct ButtonRecorder {
+    input: ButtonController,
+    recording: Option<InputRecording>,
+    replay: Option<InputReplay>,
+}
+
+impl Default for ButtonRecorder {
+    fn default() -> Self {
+        ButtonRecorder::new()
+    }
+}
+
+impl ButtonRecorder {
+    /// Creates a new ButtonRecorder which is neither recording nor replaying.
+    #[must_use]
+    pub fn new() -> Self {
+        ButtonRecorder {
+            input: ButtonController::new(),
+            recording: None,
+            replay: None,
+        }
+    }
+
+    /// Updates the state of the buttons, see [`ButtonController::update()`].
+    ///
+    /// If a replay is playing (see [`start_replay()`](ButtonRecorder::start_replay())), the next recorded
+    /// state is used instead of the hardware.
+    pub fn update(&mut self) {
+        match self.replay.as_mut().and_then(Iterator::next) {
+            Some(pressed) => self.input.update_with_state(pressed),
+            None => {
+                self.replay = None;
+                self.input.update();
+            }
+        }
+
+        self.record();
+    }
+
+    /// Updates the state of the buttons using the provided buttons rather than the current state of the
+    /// hardware, see [`ButtonController::update_with_state()`].
+    pub fn update_with_state(&mut self, pressed: Button) {
+        self.input.update_with_state(pressed);
+        self.record();
+    }
+
+    fn record(&mut self) {
+        if let Some(recording) = &mut self.recording {
+            recording.push(self.input.pressed_buttons());
+        }
+    }
+
+    /// Starts recording the state of the buttons on every call to [`update()`](ButtonRecorder::update())
+    /// or [`update_with_state()`](ButtonRecorder::update_with_state()). Any previous recording is discarded.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(InputRecording::new());
+    }
+
+    /// Stops recording and returns everything recorded since [`start_recording()`](ButtonRecorder::start_recording()),
+    /// or `None` if nothing was being recorded.
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        self.recording.take()
+    }
+
+    /// Plays back a previous recording. Each call to [`update()`](ButtonRecorder::update()) will use the
+    /// next recorded frame rather than reading the buttons from the hardware, until the replay runs out.
+    ///
+    /// Combined with a [`RandomNumberGenerator`](crate::rng::RandomNumberGenerator) with a fixed seed,
+    /// this can be used to play back a deterministic replay of a game, for example for an attract mode demo.
+    pub fn start_replay(&mut self, replay: InputReplay) {
+        self.replay = Some(replay);
+    }
+
+    /// Stops the currently playing replay, if any, and goes back to reading the buttons from the hardware.
+    pub fn stop_replay(&mut self) {
+        self.replay = None;
+    }
+
+    /// Returns `true` if a replay is still being played back.
+    #[must_use]
+    pub fn is_replaying(&self) -> bool {
+        self.replay
+            .as_ref()
+            .is_some_and(|replay| !replay.is_finished())
+    }
+}
+
+impl Deref for ButtonRecorder {
+    type Target = ButtonController;
+
+    fn deref(&self) -> &Self::Target {
+        &self.input
+    }
+}
+
+/// A compact, run length encoded recording of the buttons pressed on each frame.
+///
+/// You would normally get one of these from [`ButtonRecorder::stop_recording()`].
+/// The recording can be turned into bytes with [`to_bytes()`](InputRecording::to_bytes()) in order to store it in
+/// save media, and later played back with an [`InputReplay`]. `mgba-test-runner --replay` reads the same format.
+///
+/// The [`Display`](core::fmt::Display) implementation prints the bytes as a hex string, so you can dump a recording
+/// to the mgba logs with `agb::println!("{recording}")` and convert it back to a file with `xxd -r -p`.
+#[derive(Clone, Default)]
+pub struct InputRecording(agb_replay::InputRecording);
+
+impl InputRecording {
+    /// Creates a new, empty recording.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(agb_replay::InputRecording::new())
+    }
+
+    /// Records the buttons pressed for a single frame.
+    pub fn push(&mut self, pressed: Button) {
+        self.0.push(pressed.bits() as u16);
+    }
+
+    /// The number of frames which have been recorded.
+    #[must_use]
+    pub fn frames(&self) -> usize {
+        self.0.frames()
+    }
+
+    /// Returns the encoded recording, which can be played back by passing it to [`InputReplay::new()`].
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
+
+    /// Creates a replay of this recording, ready to be passed to [`ButtonRecorder::start_replay()`].
+    #[must_use]
+    pub fn to_replay(&self) -> InputReplay {
+        InputReplay::new(self.to_bytes())
+    }
+}
+
+impl fmt::Display for InputRecording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Plays back an [`InputRecording`] one frame at a time, see [`ButtonRecorder::start_replay()`].
+///
+/// The replay data can either be owned (such as data loaded from save media) or static (such as a demo
+/// included in the ROM with `include_bytes!`).
+#[derive(Clone)]
+pub struct InputReplay(agb_replay::InputReplay);
+
+impl InputReplay {
+    /// Creates a new replay from data previously produced by [`InputRecording::to_bytes()`].
+    ///
+    /// A trailing odd byte is ignored.
+    #[must_use]
+    pub fn new(data: impl Into<Cow<'static, [u8]>>) -> Self {
+        Self(agb_replay::InputReplay::new(data))
+    }
+
+    /// Returns `true` once every recorded frame has been played back.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.0.is_finished()
+    }
+}
+
+impl Iterator for InputReplay {
+    type Item = Button;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0
+            .next()
+            .map(|pressed| Button::from_bits_truncate(u32::from(pressed)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Gba;
+
+    #[test_case]
+    fn recording_round_trips(_gba: &mut Gba) {
+        let frames = [
+            Button::empty(),
+            Button::A,
+            Button::A | Button::RIGHT,
+            Button::A | Button::RIGHT,
+            Button::empty(),
+            Button::L | Button::R,
+        ];
+
+        let mut recording = InputRecording::new();
+        for &frame in &frames {
+            recording.push(frame);
+        }
+
+        assert_eq!(recording.frames(), frames.len());
+
+        let mut replay = recording.to_replay();
+        for &frame in &frames {
+            assert!(!replay.is_finished());
+            assert_eq!(replay.next(), Some(frame));
+        }
+
+        assert!(replay.is_finished());
+        assert_eq!(replay.next(), None);
+    }
+
+    #[test_case]
+    fn button_recorder_records_and_replays(_gba: &mut Gba) {
+        let mut input = ButtonRecorder::new();
+        input.start_recording();
+
+        input.update_with_state(Button::UP);
+        input.update_with_state(Button::UP | Button::B);
+        input.update_with_state(Button::empty());
+
+        let recording = input.stop_recording().unwrap();
+        assert_eq!(recording.frames(), 3);
+
+        input.start_replay(recording.to_replay());
+        assert!(input.is_replaying());
+
+        input.update();
+        assert_eq!(input.pressed_buttons(), Button::UP);
+        input.update();
+        assert!(input.is_just_pressed(Button::B));
+        assert!(!input.is_just_pressed(Button::UP));
+        input.update();
+        assert!(input.is_just_released(Button::UP | Button::B));
+
+        assert!(!input.is_replaying());
+    }
+}
@@ -302,6 +302,21 @@ unsafe impl critical_section::Impl for MyCriticalSection {
 static NUM_VBLANKS: AtomicUsize = AtomicUsize::new(0); // overflows after 2.27 years
 static HAS_CREATED_INTERRUPT: AtomicBool = AtomicBool::new(false);
 
+/// The number of vblanks there have been since this was first called.
+pub(crate) fn vblank_count() -> usize {
+    if !HAS_CREATED_INTERRUPT.swap(true, Ordering::SeqCst) {
+        // safety: we don't allocate in the interrupt
+        let handler = unsafe {
+            add_interrupt_handler(Interrupt::VBlank, |_| {
+                NUM_VBLANKS.store(NUM_VBLANKS.load(Ordering::SeqCst) + 1, Ordering::SeqCst);
+            })
+        };
+        core::mem::forget(handler);
+    }
+
+    NUM_VBLANKS.load(Ordering::SeqCst)
+}
+
 #[non_exhaustive]
 pub struct VBlank {
     last_waited_number: Cell<usize>,
@@ -312,18 +327,8 @@ impl VBlank {
     /// interrupt syscall.
     #[must_use]
     pub fn get() -> Self {
-        if !HAS_CREATED_INTERRUPT.swap(true, Ordering::SeqCst) {
-            // safety: we don't allocate in the interrupt
-            let handler = unsafe {
-                add_interrupt_handler(Interrupt::VBlank, |_| {
-                    NUM_VBLANKS.store(NUM_VBLANKS.load(Ordering::SeqCst) + 1, Ordering::SeqCst);
-                })
-            };
-            core::mem::forget(handler);
-        }
-
         VBlank {
-            last_waited_number: Cell::new(NUM_VBLANKS.load(Ordering::SeqCst)),
+            last_waited_number: Cell::new(vblank_count()),
         }
     }
     /// Pauses CPU until vblank interrupt is triggered where code execution is
@@ -342,14 +347,13 @@ impl VBlank {
 }
 
 #[must_use]
-/// The behaviour of this function is undefined in the sense that it will output
-/// some information in some way that can be interpreted in a way to give some
-/// profiling information. What it outputs, how it outputs it, and how to
-/// interpret it are all subject to change at any time.
+/// Samples the program counter every `period` ticks of `timer`, and prints each
+/// sample in mgba as a hex number on its own line.
 ///
-/// With that out of the way, the current version will, in mgba, output the
-/// program counter at regular intervals. This can be used to see hot functions
-/// using, for example, addr2line.
+/// Save mgba's log to a file and run `agb-debug --profile <elf> <log>` to turn the
+/// samples into the folded stack format, which tools like `inferno-flamegraph` can
+/// draw as a flamegraph. To time particular parts of your game each frame, use the
+/// [`profiler`](crate::profiler) instead.
 pub fn profiler(timer: &mut crate::timer::Timer, period: u16) -> InterruptHandler {
     timer.set_interrupt(true);
     timer.set_overflow_amount(period);
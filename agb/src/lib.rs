@@ -117,14 +117,99 @@ pub use agb_image_converter::include_font as include_font_inner;
 #[doc(hidden)]
 pub use agb_image_converter::include_colours_inner;
 
+/// Includes a TTF font at the given size in pixels per em for use with [`display::Font`].
+///
+/// By default each pixel is either drawn or not, which can look jagged for small fonts. Passing
+/// `antialias = 2` or `antialias = 4` stores 2 or 4 bits of coverage per pixel instead, which is
+/// drawn using a ramp of palette colours. See [`display::Font::colour_ramp_length`].
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{display::Font, include_font};
+///
+/// static FONT: Font = include_font!("examples/font/yoster.ttf", 12);
+/// static SMOOTH_FONT: Font = include_font!("examples/font/yoster.ttf", 12, antialias = 2);
+/// ```
+///
+/// Every character in the font is included by default, which can take up a lot of space for fonts
+/// with many glyphs. Use `charset = "..."` or `charset_file = "path/to/charset.txt"` to only include
+/// the given characters (newlines are ignored, and a space is always included). You can also include the
+/// characters needed by translations loaded with [`include_strings!`] using `strings` and `language`.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # use agb::{display::Font, include_font};
+/// static SCORE_FONT: Font = include_font!("examples/font/yoster.ttf", 12, charset = "0123456789");
+/// ```
+///
+/// Pixel fonts can also be loaded from BDF files, or from a PNG, BMP or aseprite image containing a grid
+/// of letters. Images need a `charset` giving the letters in the grid from left to right and top to bottom,
+/// along with `cell_width` and `cell_height`. Every non transparent pixel is part of the letter, and an
+/// optional `baseline` gives the row within each cell which the letters sit on (defaulting to the bottom).
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # use agb::{display::Font, include_font};
+/// static BDF_FONT: Font = include_font!("examples/font/test-font.bdf");
+/// static GRID_FONT: Font = include_font!(
+///     "examples/font/test-grid-font.png",
+///     charset = "Hi!",
+///     cell_width = 6,
+///     cell_height = 8,
+///     baseline = 5
+/// );
+/// ```
 #[macro_export]
 macro_rules! include_font {
-    ($font_path: literal, $font_size: literal) => {{
+    ($font_path: literal $(, $($options: tt)*)?) => {{
         use $crate::display;
-        $crate::include_font_inner!($font_path, $font_size)
+        $crate::include_font_inner!($font_path $(, $($options)*)?)
     }};
 }
 
+/// Includes translated strings, generating a module containing a `Language` enum with a variant for each language,
+/// a `StringId` enum with a variant for each string, and a `text(language, id)` function to look them up.
+///
+/// Translations can be given as a csv file, where the first row is `id` followed by the name of each language and
+/// each following row is the id of a string followed by its translations. Alternatively, give a directory containing
+/// a fluent (`.ftl`) or gettext (`.po`) file for each language, named after the language such as `fr.ftl`. Only
+/// simple fluent messages are supported, and po files use the `msgctxt` of each entry as its id if there is one or
+/// the `msgid` otherwise.
+///
+/// Strings can contain placeholders such as `{name}` (or `{ $name }` in fluent), which are filled in by the
+/// generated `write_text` function. It is a compile error for a string to be missing from a language, or for
+/// different languages to use different placeholders.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{display::Font, include_font, include_strings};
+///
+/// include_strings!(strings, "examples/strings/strings.csv");
+///
+/// // Only includes the characters needed for the Japanese translation
+/// static JAPANESE_FONT: Font = include_font!(
+///     "examples/font/ark-pixel-10px-proportional-ja.ttf",
+///     10,
+///     strings = "examples/strings/strings.csv",
+///     language = "ja"
+/// );
+///
+/// # fn foo(writer: &mut impl core::fmt::Write) {
+/// assert_eq!(strings::text(strings::Language::Fr, strings::StringId::Title), "Traduction");
+/// strings::write_text(writer, strings::Language::Ja, strings::StringId::Greeting, &[("name", &"agb")]).unwrap();
+/// # }
+/// ```
+///
+/// Each `Language` also has a `charset()` listing the characters its strings use, and `include_font!` accepts
+/// `strings` and `language` options to only include those characters in a font (or the characters for every
+/// language if `language` isn't given). Placeholders are filled in at runtime, so add any characters they need
+/// with the `charset` option.
+pub use agb_image_converter::include_strings;
+
 /// This macro declares the entry point to your game written using `agb`.
 ///
 /// It is already included in the template, but your `main` function must be annotated with `#[agb::entry]`, takes 1 argument and never returns.
@@ -153,10 +238,12 @@ mod agbabi;
 #[cfg(feature = "backtrace")]
 mod backtrace;
 mod bitarray;
+pub mod crash;
 /// Implements everything relating to things that are displayed on screen.
 pub mod display;
 /// Provides access to the GBA's direct memory access (DMA) which is used for advanced effects
 pub mod dma;
+pub mod executor;
 /// Button inputs to the system.
 pub mod input;
 /// Interacting with the GBA interrupts
@@ -170,10 +257,12 @@ pub use agb_fixnum as fixnum;
 pub use agb_hashmap as hash_map;
 #[cfg(feature = "backtrace")]
 mod panics_render;
+pub mod profiler;
 /// Simple random number generator
 pub mod rng;
 pub mod save;
 mod single;
+pub mod sleep;
 /// Implements sound output.
 pub mod sound;
 /// A module containing functions and utilities useful for synchronizing state.
@@ -207,6 +296,9 @@ fn panic_implementation(info: &core::panic::PanicInfo) -> ! {
         let _ = mgba.print(format_args!("{info}"), mgba::DebugLevel::Fatal);
     }
 
+    // there's no default crash screen without backtraces, so it halts either way
+    let _ = crash::run_crash_handler(&crash::Crash::new(info));
+
     #[allow(clippy::empty_loop)]
     loop {}
 }
@@ -295,11 +387,16 @@ impl Gba {
 ///
 /// You can run the tests using `cargo test`, but it will work better through `mgba-test-runner` by
 /// running something along the lines of `CARGO_TARGET_THUMBV4T_NONE_EABI_RUNNER=mgba-test-runner cargo test`.
+///
+/// As with normal tests, `cargo test -- <filter>` only runs the tests whose names contain the filter,
+/// which `mgba-test-runner` passes in by writing it into the ROM's memory. See `mgba-test-runner --help`
+/// for timeouts and JUnit or JSON reports.
 pub mod test_runner {
     use super::*;
 
     #[doc(hidden)]
     pub trait Testable {
+        fn name(&self) -> &'static str;
         fn run(&self, gba: &mut Gba);
     }
 
@@ -307,13 +404,14 @@ pub mod test_runner {
     where
         T: Fn(&mut Gba),
     {
+        fn name(&self) -> &'static str {
+            core::any::type_name::<T>()
+        }
+
         fn run(&self, gba: &mut Gba) {
             let mut mgba = mgba::Mgba::new().unwrap();
-            mgba.print(
-                format_args!("{}...", core::any::type_name::<T>()),
-                mgba::DebugLevel::Info,
-            )
-            .unwrap();
+            mgba.print(format_args!("{}...", self.name()), mgba::DebugLevel::Info)
+                .unwrap();
             mgba::test_runner_measure_cycles();
             self(gba);
             mgba::test_runner_measure_cycles();
@@ -332,10 +430,21 @@ pub mod test_runner {
             let _ = mgba.print(format_args!("[failed]"), mgba::DebugLevel::Error);
         }
 
+        let crash = crate::crash::Crash::new(
+            info,
+            #[cfg(feature = "backtrace")]
+            &frames,
+        );
+        let action = crate::crash::run_crash_handler(&crash);
+
         #[cfg(feature = "backtrace")]
-        crate::panics_render::render_backtrace(&frames, info);
+        if action == crate::crash::CrashAction::ShowDefaultScreen {
+            crate::panics_render::render_backtrace(&frames, info);
+        }
 
         #[cfg(not(feature = "backtrace"))]
+        let _ = action;
+
         loop {
             syscall::halt();
         }
@@ -343,18 +452,63 @@ pub mod test_runner {
 
     static mut TEST_GBA: Option<Gba> = None;
 
+    const TEST_FILTER_CAPACITY: usize = 252;
+
+    #[repr(C)]
+    struct TestFilter {
+        len: u32,
+        bytes: [u8; TEST_FILTER_CAPACITY],
+    }
+
+    // mgba-test-runner writes the filter from its command line in here when it sees the address
+    // printed, so only tests whose names contain the filter are run.
+    static mut TEST_FILTER: TestFilter = TestFilter {
+        len: 0,
+        bytes: [0; TEST_FILTER_CAPACITY],
+    };
+
+    fn read_test_filter(mgba: &mut mgba::Mgba) -> TestFilter {
+        let filter = unsafe { core::ptr::addr_of!(TEST_FILTER) };
+
+        mgba.print(
+            format_args!(
+                "test-filter:{:#010x}:{}",
+                filter as usize, TEST_FILTER_CAPACITY
+            ),
+            mgba::DebugLevel::Info,
+        )
+        .unwrap();
+
+        // safety: the filter is only ever written to by the emulator
+        unsafe { filter.read_volatile() }
+    }
+
     #[doc(hidden)]
     pub fn test_runner(tests: &[&dyn Testable]) {
         let mut mgba = mgba::Mgba::new().unwrap();
+
+        let filter = read_test_filter(&mut mgba);
+        let filter_len = (filter.len as usize).min(TEST_FILTER_CAPACITY);
+        let filter = core::str::from_utf8(&filter.bytes[..filter_len]).unwrap_or("");
+
+        let filtered_out = tests
+            .iter()
+            .filter(|test| !test.name().contains(filter))
+            .count();
+
         mgba.print(
-            format_args!("Running {} tests", tests.len()),
+            format_args!(
+                "Running {} tests, {} filtered out",
+                tests.len() - filtered_out,
+                filtered_out
+            ),
             mgba::DebugLevel::Info,
         )
         .unwrap();
 
         let gba = unsafe { TEST_GBA.as_mut() }.unwrap();
 
-        for test in tests {
+        for test in tests.iter().filter(|test| test.name().contains(filter)) {
             test.run(gba);
         }
 
@@ -388,14 +542,163 @@ pub mod test_runner {
         loop {}
     }
 
+    /// Checks that the screen matches the reference image at `image`, which is relative to where the
+    /// tests are run from. Run `mgba-test-runner --bless` to create or update the image.
     pub fn assert_image_output(image: &str) {
+        assert_image_output_with(image, &ImageOptions::new());
+    }
+
+    /// Checks that the screen matches the reference image at `image`, allowing for the differences
+    /// given in `options`.
+    pub fn assert_image_output_with(image: &str, options: &ImageOptions) {
         display::busy_wait_for_vblank();
         display::busy_wait_for_vblank();
         let mut mgba = crate::mgba::Mgba::new().unwrap();
-        mgba.print(format_args!("image:{image}"), crate::mgba::DebugLevel::Info)
-            .unwrap();
+        mgba.print(
+            format_args!("image:{image}{options}"),
+            crate::mgba::DebugLevel::Info,
+        )
+        .unwrap();
         display::busy_wait_for_vblank();
     }
+
+    /// How closely the screen needs to match a reference image in [`assert_image_output_with`].
+    ///
+    /// The whole message sent to `mgba-test-runner` must fit in 256 bytes, so only use a few masks.
+    #[derive(Clone, Debug, Default)]
+    pub struct ImageOptions {
+        tolerance: u8,
+        masks: alloc::vec::Vec<crate::fixnum::Rect<u8>>,
+    }
+
+    impl ImageOptions {
+        /// Requires the screen to exactly match the image.
+        #[must_use]
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Allows each colour channel to differ from the image by up to `tolerance`, out of 255.
+        #[must_use]
+        pub fn tolerance(mut self, tolerance: u8) -> Self {
+            self.tolerance = tolerance;
+            self
+        }
+
+        /// Ignores the pixels within `region`, for example a frame counter.
+        #[must_use]
+        pub fn mask(mut self, region: crate::fixnum::Rect<u8>) -> Self {
+            self.masks.push(region);
+            self
+        }
+    }
+
+    impl core::fmt::Display for ImageOptions {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            if self.tolerance != 0 {
+                write!(f, ";tolerance={}", self.tolerance)?;
+            }
+
+            for mask in &self.masks {
+                write!(
+                    f,
+                    ";mask={},{},{},{}",
+                    mask.position.x, mask.position.y, mask.size.x, mask.size.y
+                )?;
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Records the sound output for `frames` frames, calling `per_frame` once each frame to keep the
+    /// mixer going, and checks that it matches the reference WAV at `audio`. Run
+    /// `mgba-test-runner --bless` to create or update the WAV.
+    ///
+    /// ```rust,ignore
+    /// #[test_case]
+    /// fn jump_sound(gba: &mut Gba) {
+    ///     let mut mixer = gba.mixer.mixer(Frequency::Hz18157);
+    ///     mixer.enable();
+    ///     mixer.play_sound(SoundChannel::new(JUMP));
+    ///
+    ///     assert_audio_output("sfx/jump.wav", 30, || mixer.frame());
+    /// }
+    /// ```
+    pub fn assert_audio_output(audio: &str, frames: usize, per_frame: impl FnMut()) {
+        assert_audio_output_with(audio, frames, &AudioOptions::new(), per_frame);
+    }
+
+    /// Records the sound output like [`assert_audio_output`], allowing for the differences given in
+    /// `options`.
+    pub fn assert_audio_output_with(
+        audio: &str,
+        frames: usize,
+        options: &AudioOptions,
+        mut per_frame: impl FnMut(),
+    ) {
+        let vblank = crate::interrupt::VBlank::get();
+        vblank.wait_for_vblank();
+
+        let mut mgba = crate::mgba::Mgba::new().unwrap();
+        mgba.print(format_args!("audio-start"), crate::mgba::DebugLevel::Info)
+            .unwrap();
+
+        for _ in 0..frames {
+            per_frame();
+            vblank.wait_for_vblank();
+        }
+
+        mgba.print(
+            format_args!("audio:{audio}{options}"),
+            crate::mgba::DebugLevel::Info,
+        )
+        .unwrap();
+    }
+
+    /// How closely the sound output needs to match a reference WAV in [`assert_audio_output_with`].
+    #[derive(Clone, Debug, Default)]
+    pub struct AudioOptions {
+        tolerance: u16,
+        spectral: Option<u8>,
+    }
+
+    impl AudioOptions {
+        /// Requires the sound output to exactly match the WAV.
+        #[must_use]
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Allows each sample to differ from the WAV by up to `tolerance`.
+        #[must_use]
+        pub fn tolerance(mut self, tolerance: u16) -> Self {
+            self.tolerance = tolerance;
+            self
+        }
+
+        /// Compares the frequencies in the sound rather than the samples, so that small timing
+        /// changes don't fail the test, allowing the spectra to differ by up to `percent`%.
+        #[must_use]
+        pub fn spectral(mut self, percent: u8) -> Self {
+            self.spectral = Some(percent);
+            self
+        }
+    }
+
+    impl core::fmt::Display for AudioOptions {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            if self.tolerance != 0 {
+                write!(f, ";tolerance={}", self.tolerance)?;
+            }
+
+            if let Some(percent) = self.spectral {
+                write!(f, ";spectral={percent}")?;
+            }
+
+            Ok(())
+        }
+    }
 }
 
 #[inline(never)]
@@ -418,6 +721,28 @@ mod test {
         assert_eq!(1, 1);
     }
 
+    #[test_case]
+    fn image_options_are_sent_after_the_image_path(_gba: &mut Gba) {
+        use crate::fixnum::{Rect, Vector2D};
+        use crate::test_runner::ImageOptions;
+
+        let options = ImageOptions::new()
+            .tolerance(8)
+            .mask(Rect::new(Vector2D::new(1, 2), Vector2D::new(30, 40)));
+
+        assert_eq!(alloc::format!("{options}"), ";tolerance=8;mask=1,2,30,40");
+        assert_eq!(alloc::format!("{}", ImageOptions::new()), "");
+    }
+
+    #[test_case]
+    fn audio_options_are_sent_after_the_wav_path(_gba: &mut Gba) {
+        use crate::test_runner::AudioOptions;
+
+        let options = AudioOptions::new().tolerance(64).spectral(5);
+
+        assert_eq!(alloc::format!("{options}"), ";tolerance=64;spectral=5");
+    }
+
     #[test_case]
     fn gba_struct_is_zero_sized(_gba: &mut Gba) {
         use core::mem;
@@ -27,14 +27,17 @@ pub fn render_backtrace(trace: &backtrace::Frames, info: &PanicInfo) -> ! {
             gba.dma.dma().dma3.disable();
             let mut gfx = gba.display.video.bitmap3();
 
-            let qrcode_string_data = if WEBSITE.is_empty() {
-                format!("{trace}")
-            } else {
-                format!("{WEBSITE}{trace}")
-            };
-            crate::println!("Stack trace: {qrcode_string_data}");
-
-            let location = draw_qr_code(&mut gfx, &qrcode_string_data);
+            let crash_report = trace.crash_report(info);
+            crate::println!("Stack trace: {WEBSITE}{crash_report}");
+
+            // the registers and stack might not fit in the QR code, in which case it falls back to
+            // only containing the backtrace
+            let location = draw_qr_code(&mut gfx, &format!("{WEBSITE}{crash_report}"))
+                .or_else(|| draw_qr_code(&mut gfx, &format!("{WEBSITE}{trace}")))
+                .unwrap_or_else(|| {
+                    crate::println!("Error generating qr code: the backtrace is too long");
+                    8
+                });
 
             let mut trace_text_render =
                 text::BitmapTextRender::new(&mut gfx, (location, 8).into(), 0x0000);
@@ -63,14 +66,14 @@ pub fn render_backtrace(trace: &backtrace::Frames, info: &PanicInfo) -> ! {
     })
 }
 
-/// Returns the width / height of the QR code + padding in pixels
-fn draw_qr_code(gfx: &mut Bitmap3<'_>, qrcode_string_data: &str) -> i32 {
+/// Returns the width / height of the QR code + padding in pixels, or `None` if the data doesn't fit
+fn draw_qr_code(gfx: &mut Bitmap3<'_>, qrcode_string_data: &str) -> Option<i32> {
     const MAX_VERSION: qrcodegen_no_heap::Version = qrcodegen_no_heap::Version::new(6);
 
     let mut temp_buffer = vec![0; MAX_VERSION.buffer_len()];
     let mut out_buffer = vec![0; MAX_VERSION.buffer_len()];
 
-    let qr_code = match qrcodegen_no_heap::QrCode::encode_text(
+    let qr_code = qrcodegen_no_heap::QrCode::encode_text(
         qrcode_string_data,
         &mut temp_buffer,
         &mut out_buffer,
@@ -79,13 +82,8 @@ fn draw_qr_code(gfx: &mut Bitmap3<'_>, qrcode_string_data: &str) -> i32 {
         MAX_VERSION,
         None,
         true,
-    ) {
-        Ok(qr_code) => qr_code,
-        Err(e) => {
-            crate::println!("Error generating qr code: {e:?}");
-            return 8;
-        }
-    };
+    )
+    .ok()?;
 
     for y in 0..HEIGHT {
         for x in 0..WIDTH {
@@ -98,5 +96,5 @@ fn draw_qr_code(gfx: &mut Bitmap3<'_>, qrcode_string_data: &str) -> i32 {
         }
     }
 
-    qr_code.size() * 2 + 8 * 2
+    Some(qr_code.size() * 2 + 8 * 2)
 }
@@ -53,7 +53,7 @@ impl<'bitmap, 'gba> BitmapTextRender<'bitmap, 'gba> {
 
         for y in 0..letter.height as usize {
             for x in 0..letter.width as usize {
-                let rendered = letter.bit_absolute(x, y);
+                let rendered = letter.coverage(x, y, 1) != 0;
                 let x = x as i32 + self.head_position.x;
                 let y = y as i32 + y_position_start;
 
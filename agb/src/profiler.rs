@@ -0,0 +1,458 @@
+#![deny(missing_docs)]
+//! Measuring how long parts of your game take each frame.
+//!
+//! Wrap the parts of your game you want to measure in [`profile!`](crate::profile) with a name,
+//! and call [`Profiler::end_frame`] once per frame to get a [`FrameReport`] of how long each
+//! named scope took in total that frame. Scopes are timed with a [`Clock`], so they are accurate
+//! to the CPU cycle. When there is no [`Profiler`], `profile!` does nothing other than run the code.
+//!
+//! The [`Overlay`] draws the report as a bar along the top of the screen using sprites, where the
+//! full width of the screen is one frame.
+//!
+//! For finding out which functions are slow rather than timing scopes you've chosen, use
+//! [`interrupt::profiler`](crate::interrupt::profiler) to sample the program counter, and turn
+//! the samples into a flamegraph with `agb-debug --profile`.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! # fn foo(gba: &mut agb::Gba) {
+//! use agb::{profile, profiler::Profiler, timer::Clock};
+//!
+//! let timers = gba.timers.timers();
+//! let clock = Clock::new(timers.timer2);
+//! let mut profiler = Profiler::new(&clock);
+//!
+//! loop {
+//!     profile!("physics", {
+//!         // update the physics
+//!     });
+//!
+//!     let report = profiler.end_frame();
+//!     agb::println!("{report}");
+//! #   break;
+//! }
+//! # }
+//! ```
+
+use core::{cell::RefCell, fmt, marker::PhantomData};
+
+use alloc::vec::Vec;
+use critical_section::Mutex;
+
+use crate::{
+    display::{
+        object::{DynamicSprite, OamIterator, ObjectUnmanaged, PaletteVram, Size, SpriteVram},
+        palette16::Palette16,
+        WIDTH,
+    },
+    timer::{now_in, Clock, Duration, Instant},
+};
+
+/// The time between the start of one frame and the start of the next.
+pub const FRAME_BUDGET: Duration = Duration::from_cycles(280_896);
+
+/// Times the code inside it as part of the named scope when there is a [`Profiler`](crate::profiler::Profiler),
+/// and evaluates to the value of the code.
+///
+/// This must not be used in interrupt handlers, since the first time each scope finishes in a frame it
+/// allocates to store the scope's time.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # fn update_enemies() -> u32 { 0 }
+/// # fn foo() {
+/// let enemies_left = agb::profile!("enemies", { update_enemies() });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! profile {
+    ($name:expr, $body:expr) => {{
+        let _scope = $crate::profiler::Scope::enter($name);
+        $body
+    }};
+}
+
+/// The total time spent in a scope during a frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScopeReport {
+    /// The name given to [`profile!`](crate::profile).
+    pub name: &'static str,
+    /// The time spent in the scope, including any scopes inside it.
+    pub time: Duration,
+    /// The number of times the scope was entered.
+    pub calls: u32,
+}
+
+/// How long each scope took during a frame, see [`Profiler::end_frame`].
+#[derive(Clone, Debug, Default)]
+pub struct FrameReport {
+    frame_time: Duration,
+    scopes: Vec<ScopeReport>,
+}
+
+impl FrameReport {
+    /// The time between the start and end of the frame.
+    #[must_use]
+    pub fn frame_time(&self) -> Duration {
+        self.frame_time
+    }
+
+    /// The scopes which were entered during the frame, in the order they first finished.
+    #[must_use]
+    pub fn scopes(&self) -> &[ScopeReport] {
+        &self.scopes
+    }
+
+    /// The report for the scope called `name`, if it was entered during the frame.
+    #[must_use]
+    pub fn scope(&self, name: &str) -> Option<&ScopeReport> {
+        self.scopes.iter().find(|scope| scope.name == name)
+    }
+}
+
+fn percent_of_frame(time: Duration) -> u64 {
+    time.as_cycles() * 100 / FRAME_BUDGET.as_cycles()
+}
+
+impl fmt::Display for FrameReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "frame: {}us ({}%)",
+            self.frame_time.as_micros(),
+            percent_of_frame(self.frame_time)
+        )?;
+
+        for scope in &self.scopes {
+            write!(
+                f,
+                "\n  {}: {}us ({}%), {} calls",
+                scope.name,
+                scope.time.as_micros(),
+                percent_of_frame(scope.time),
+                scope.calls
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+struct ProfilerState {
+    clock_timer: usize,
+    frame_start: Instant,
+    scopes: Vec<ScopeReport>,
+}
+
+static PROFILER: Mutex<RefCell<Option<ProfilerState>>> = Mutex::new(RefCell::new(None));
+
+/// A scope being timed, created by [`profile!`](crate::profile). The time is recorded when this is dropped.
+#[must_use]
+pub struct Scope {
+    name: &'static str,
+    start: Option<Instant>,
+}
+
+impl Scope {
+    /// Starts timing the scope called `name`. You should normally use [`profile!`](crate::profile) instead.
+    ///
+    /// Like `profile!`, this must not be used in interrupt handlers since dropping the scope can allocate.
+    pub fn enter(name: &'static str) -> Self {
+        let start = critical_section::with(|cs| {
+            PROFILER
+                .borrow_ref(cs)
+                .as_ref()
+                .map(|state| now_in(cs, state.clock_timer))
+        });
+
+        Self { name, start }
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let Some(start) = self.start else {
+            return;
+        };
+
+        critical_section::with(|cs| {
+            let mut state = PROFILER.borrow_ref_mut(cs);
+            let Some(state) = state.as_mut() else {
+                return;
+            };
+
+            let time = now_in(cs, state.clock_timer).duration_since(start);
+
+            match state
+                .scopes
+                .iter_mut()
+                .find(|scope| scope.name == self.name)
+            {
+                Some(scope) => {
+                    scope.time += time;
+                    scope.calls += 1;
+                }
+                None => state.scopes.push(ScopeReport {
+                    name: self.name,
+                    time,
+                    calls: 1,
+                }),
+            }
+        });
+    }
+}
+
+/// Collects the time spent in each [`profile!`](crate::profile) scope, see the
+/// [module level documentation](self).
+pub struct Profiler<'clock> {
+    report: FrameReport,
+    phantom: PhantomData<&'clock Clock>,
+}
+
+impl<'clock> Profiler<'clock> {
+    /// Starts profiling, using `clock` to time scopes. The first frame starts now.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is already a profiler.
+    #[must_use]
+    pub fn new(clock: &'clock Clock) -> Self {
+        critical_section::with(|cs| {
+            let mut state = PROFILER.borrow_ref_mut(cs);
+            assert!(state.is_none(), "there can only be one profiler");
+
+            *state = Some(ProfilerState {
+                clock_timer: clock.timer_number(),
+                frame_start: now_in(cs, clock.timer_number()),
+                scopes: Vec::new(),
+            });
+        });
+
+        Self {
+            report: FrameReport::default(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Ends the current frame and starts the next one, returning the report for the frame which ended.
+    pub fn end_frame(&mut self) -> &FrameReport {
+        critical_section::with(|cs| {
+            let mut state = PROFILER.borrow_ref_mut(cs);
+            let state = state.as_mut().expect("profiler should exist");
+
+            let now = now_in(cs, state.clock_timer);
+            self.report.frame_time = now.duration_since(state.frame_start);
+            state.frame_start = now;
+
+            // reuse the allocations from the previous report
+            self.report.scopes.clear();
+            core::mem::swap(&mut self.report.scopes, &mut state.scopes);
+        });
+
+        &self.report
+    }
+
+    /// The report for the last frame which ended.
+    #[must_use]
+    pub fn last_frame(&self) -> &FrameReport {
+        &self.report
+    }
+}
+
+impl Drop for Profiler<'_> {
+    fn drop(&mut self) {
+        critical_section::with(|cs| PROFILER.borrow_ref_mut(cs).take());
+    }
+}
+
+const OVERLAY_PALETTE: Palette16 = Palette16::new([
+    0x0000, 0x1084, 0x001f, 0x03e0, 0x7c00, 0x03ff, 0x7fe0, 0x7c1f, 0x021f, 0x7e00, 0x43f0, 0x3dff,
+    0x7def, 0x2d6b, 0x5ad6, 0x7fff,
+]);
+
+// colour 1 is the background of the bar, and 2 is for frames which go over budget
+const BACKGROUND_COLOUR: usize = 1;
+const OVER_BUDGET_COLOUR: usize = 2;
+const FIRST_SCOPE_COLOUR: usize = 3;
+
+// The background always takes a row of sprites across the screen, and the scopes share the rest
+const MAX_SLOTS: usize = 62;
+const BACKGROUND_SLOTS: usize = WIDTH as usize / 8;
+
+/// Draws a [`FrameReport`] as a bar using sprites, where the width of the screen is one frame.
+///
+/// Each scope is shown in its own colour, in the order they appear in the report, on top of a
+/// grey background which turns red when the frame went over budget. The time in a nested scope
+/// is also counted in the scope around it, so it appears twice in the bar.
+pub struct Overlay {
+    colours: Vec<SpriteVram>,
+    y: i32,
+}
+
+impl Overlay {
+    /// Creates the sprites for the overlay, which is drawn at the top of the screen.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no space in video memory for the palette or sprites.
+    #[must_use]
+    pub fn new() -> Self {
+        let palette = PaletteVram::new(&OVERLAY_PALETTE).expect("no space for overlay palette");
+
+        let colours = (0..16)
+            .map(|colour| {
+                let mut sprite = DynamicSprite::new(Size::S8x8);
+                sprite.clear(colour);
+                sprite.to_vram(palette.clone())
+            })
+            .collect();
+
+        Self { colours, y: 0 }
+    }
+
+    /// Sets the vertical position of the bar on the screen.
+    pub fn set_y(&mut self, y: i32) {
+        self.y = y;
+    }
+
+    /// Draws `report` using slots from `oam`, returning how many slots were used. This is never more
+    /// than 62, so if there are lots of scopes then the ones at the end of the report may not be shown.
+    pub fn show(&self, report: &FrameReport, oam: &mut OamIterator) -> usize {
+        let width_of = |time: Duration| {
+            (time.as_cycles() * WIDTH as u64 / FRAME_BUDGET.as_cycles()).min(WIDTH as u64) as i32
+        };
+
+        // Each part of the bar is made of 8 pixel wide sprites, so it can spill over into the part
+        // after it. Earlier slots in oam are drawn on top, so the parts are drawn from the right.
+        let mut parts = Vec::with_capacity(report.scopes.len() + 1);
+        let mut x = 0;
+        let mut slots = BACKGROUND_SLOTS;
+        for (index, scope) in report.scopes.iter().enumerate() {
+            let width = width_of(scope.time).min(WIDTH - x);
+            let part_slots = (width as usize + 7) / 8;
+            if slots + part_slots > MAX_SLOTS {
+                break;
+            }
+
+            let colour = FIRST_SCOPE_COLOUR + index % (self.colours.len() - FIRST_SCOPE_COLOUR);
+            parts.push((x, width, colour));
+            slots += part_slots;
+            x += width;
+        }
+
+        let background = if report.frame_time > FRAME_BUDGET {
+            OVER_BUDGET_COLOUR
+        } else {
+            BACKGROUND_COLOUR
+        };
+
+        let mut used = 0;
+        for &(x, width, colour) in parts.iter().rev() {
+            used += self.draw_part(x, width, colour, oam);
+        }
+
+        used + self.draw_part(0, WIDTH, background, oam)
+    }
+
+    // Returns the number of slots used, which is fewer than needed if oam runs out
+    fn draw_part(&self, x: i32, width: i32, colour: usize, oam: &mut OamIterator) -> usize {
+        let mut used = 0;
+
+        for tile_x in (x..x + width).step_by(8) {
+            let Some(slot) = oam.next() else {
+                break;
+            };
+
+            let mut object = ObjectUnmanaged::new(self.colours[colour].clone());
+            object.set_position((tile_x, self.y).into()).show();
+            slot.set(&object);
+            used += 1;
+        }
+
+        used
+    }
+}
+
+impl Default for Overlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn scopes_are_added_up_each_frame(gba: &mut crate::Gba) {
+        let timers = gba.timers.timers();
+        let clock = Clock::new(timers.timer2);
+        let mut profiler = Profiler::new(&clock);
+
+        for _ in 0..3 {
+            crate::profile!("outer", {
+                crate::profile!("inner", {
+                    for i in 0..100 {
+                        core::hint::black_box(i);
+                    }
+                });
+            });
+        }
+
+        let report = profiler.end_frame();
+        let outer = report.scope("outer").unwrap();
+        let inner = report.scope("inner").unwrap();
+
+        assert_eq!(outer.calls, 3);
+        assert_eq!(inner.calls, 3);
+        assert!(outer.time > inner.time);
+        assert!(report.frame_time() >= outer.time);
+
+        assert!(profiler.end_frame().scopes().is_empty());
+    }
+
+    #[test_case]
+    fn profile_does_nothing_without_a_profiler(_gba: &mut crate::Gba) {
+        assert_eq!(crate::profile!("nothing", 5), 5);
+    }
+
+    #[test_case]
+    fn overlay_fits_in_oam(gba: &mut crate::Gba) {
+        let (mut oam, _) = gba.display.object.get_unmanaged();
+        let overlay = Overlay::new();
+
+        let report = FrameReport {
+            frame_time: FRAME_BUDGET * 2,
+            scopes: alloc::vec![
+                ScopeReport {
+                    name: "a",
+                    time: FRAME_BUDGET,
+                    calls: 1
+                },
+                ScopeReport {
+                    name: "b",
+                    time: FRAME_BUDGET,
+                    calls: 1
+                },
+            ],
+        };
+
+        // the first scope fills the whole bar, leaving no room for the second
+        assert_eq!(overlay.show(&report, &mut oam.iter()), 2 * BACKGROUND_SLOTS);
+
+        let many_scopes = FrameReport {
+            frame_time: FRAME_BUDGET,
+            scopes: (0..100)
+                .map(|_| ScopeReport {
+                    name: "tiny",
+                    time: Duration::from_cycles(FRAME_BUDGET.as_cycles() / 200),
+                    calls: 1,
+                })
+                .collect(),
+        };
+
+        assert_eq!(overlay.show(&many_scopes, &mut oam.iter()), MAX_SLOTS);
+    }
+}
@@ -0,0 +1,185 @@
+#![deny(missing_docs)]
+//! Low power sleep mode.
+//!
+//! Many commercial games offer a _sleep_ option which turns off the screen and sound and puts the
+//! Game Boy Advance into its lowest power state until the player presses a certain button
+//! combination. This lets a player put down a long game without losing their progress or draining
+//! the batteries.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::input::Button;
+//!
+//! # fn foo() {
+//! // called when the player selects "sleep" from the menu
+//! agb::sleep::sleep_until_pressed(Button::L | Button::R | Button::SELECT);
+//! # }
+//! ```
+
+use crate::{
+    display,
+    input::{Button, ButtonController},
+    interrupt::Interrupt,
+    memory_mapped::MemoryMapped,
+    syscall,
+};
+
+const DISPLAY_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0000) };
+const FORCED_BLANK: u16 = 1 << 7;
+
+// The DMG sound registers get cleared when the master sound enable is turned off, so we need
+// to remember all of them.
+const DMG_SOUND_REGISTERS: usize = 0x0400_0060;
+const DMG_SOUND_REGISTER_COUNT: usize = 16;
+
+const SOUND_CONTROL_L: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0080) };
+const SOUND_CONTROL_H: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0082) };
+const SOUND_CONTROL_X: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0084) };
+const SOUND_ENABLE: u16 = 1 << 7;
+const FIFO_RESET: u16 = 1 << 11 | 1 << 15;
+
+const fn dma_control_hi(dma: usize) -> MemoryMapped<u16> {
+    unsafe { MemoryMapped::new(0x0400_00ba + 0x0c * dma) }
+}
+
+const fn timer_control(timer: usize) -> MemoryMapped<u16> {
+    unsafe { MemoryMapped::new(0x0400_0102 + 4 * timer) }
+}
+
+const ENABLE: u16 = 1 << 15;
+const TIMER_ENABLE: u16 = 1 << 7;
+
+const KEY_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0132) };
+const KEY_INTERRUPT_ENABLE: u16 = 1 << 14;
+const KEY_INTERRUPT_ALL_PRESSED: u16 = 1 << 15;
+
+const INTERRUPT_ENABLE: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0200) };
+const INTERRUPT_REQUEST: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0202) };
+
+struct SavedState {
+    display_control: u16,
+    dmg_sound: [u16; DMG_SOUND_REGISTER_COUNT],
+    sound_control_l: u16,
+    sound_control_h: u16,
+    sound_control_x: u16,
+    dma_controls: [u16; 4],
+    timer_controls: [u16; 4],
+    key_control: u16,
+    interrupt_enable: u16,
+}
+
+fn dmg_sound_register(index: usize) -> MemoryMapped<u16> {
+    unsafe { MemoryMapped::new(DMG_SOUND_REGISTERS + 2 * index) }
+}
+
+impl SavedState {
+    fn save() -> Self {
+        Self {
+            display_control: DISPLAY_CONTROL.get(),
+            dmg_sound: core::array::from_fn(|i| dmg_sound_register(i).get()),
+            sound_control_l: SOUND_CONTROL_L.get(),
+            sound_control_h: SOUND_CONTROL_H.get(),
+            sound_control_x: SOUND_CONTROL_X.get(),
+            dma_controls: core::array::from_fn(|i| dma_control_hi(i).get()),
+            timer_controls: core::array::from_fn(|i| timer_control(i).get()),
+            key_control: KEY_CONTROL.get(),
+            interrupt_enable: INTERRUPT_ENABLE.get(),
+        }
+    }
+
+    fn restore(&self) {
+        KEY_CONTROL.set(self.key_control);
+        INTERRUPT_ENABLE.set(self.interrupt_enable);
+
+        SOUND_CONTROL_X.set(self.sound_control_x);
+        for (i, &value) in self.dmg_sound.iter().enumerate() {
+            dmg_sound_register(i).set(value);
+        }
+        SOUND_CONTROL_L.set(self.sound_control_l);
+        // the FIFOs may contain stale samples, so always reset them
+        SOUND_CONTROL_H.set(self.sound_control_h | FIFO_RESET);
+
+        for (i, &value) in self.dma_controls.iter().enumerate() {
+            dma_control_hi(i).set(value);
+        }
+
+        for (i, &value) in self.timer_controls.iter().enumerate() {
+            timer_control(i).set(value);
+        }
+
+        // avoid tearing by only turning the display back on during vblank
+        display::busy_wait_for_vblank();
+        DISPLAY_CONTROL.set(self.display_control);
+    }
+}
+
+fn wait_for_release(buttons: Button) {
+    let mut input = ButtonController::new();
+    while input.pressed_buttons().contains(buttons) {
+        display::busy_wait_for_vblank();
+        input.update();
+    }
+}
+
+/// Puts the Game Boy Advance into its low power stop mode until every button in `wake_buttons`
+/// is held down at the same time.
+///
+/// Before stopping, the display is blanked, sound output is disabled and all DMA transfers and
+/// timers are paused. Once woken, these are all restored to the state they were in before the
+/// call, so the [`Display`](crate::display::Display), [`Mixer`](crate::sound::mixer::Mixer) and any
+/// running DMA transfers carry on as before. The DMG sound channels will need to be restarted.
+///
+/// If the wake combination is already being held when this is called, this will first wait for it
+/// to be released. Similarly, this function only returns once the wake combination has been
+/// released again, so that the buttons used to wake don't then get acted on by your game.
+///
+/// # Panics
+///
+/// Panics if `wake_buttons` is empty, since it would then be impossible to wake back up.
+pub fn sleep_until_pressed(wake_buttons: Button) {
+    assert!(
+        !wake_buttons.is_empty(),
+        "must provide at least one button to wake up with"
+    );
+
+    wait_for_release(wake_buttons);
+
+    // avoid tearing by only turning off the display during vblank
+    display::busy_wait_for_vblank();
+
+    critical_section::with(|_| {
+        let saved = SavedState::save();
+
+        for (i, &value) in saved.dma_controls.iter().enumerate() {
+            dma_control_hi(i).set(value & !ENABLE);
+        }
+
+        for (i, &value) in saved.timer_controls.iter().enumerate() {
+            timer_control(i).set(value & !TIMER_ENABLE);
+        }
+
+        SOUND_CONTROL_X.set(saved.sound_control_x & !SOUND_ENABLE);
+        DISPLAY_CONTROL.set(saved.display_control | FORCED_BLANK);
+
+        let keypad_interrupt = 1 << Interrupt::Keypad as u16;
+
+        KEY_CONTROL
+            .set(wake_buttons.bits() as u16 | KEY_INTERRUPT_ENABLE | KEY_INTERRUPT_ALL_PRESSED);
+        // Stop only needs the interrupt to be enabled in IE, not in IME. So we can do this inside
+        // the critical section and no interrupt handler will run when we wake up.
+        INTERRUPT_REQUEST.set(keypad_interrupt);
+        INTERRUPT_ENABLE.set(keypad_interrupt);
+
+        syscall::stop();
+
+        // acknowledge the keypad interrupt which woke us up
+        INTERRUPT_REQUEST.set(keypad_interrupt);
+
+        saved.restore();
+    });
+
+    wait_for_release(wake_buttons);
+}
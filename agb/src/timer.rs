@@ -2,6 +2,13 @@ use core::marker::PhantomData;
 
 use crate::memory_mapped::MemoryMapped;
 
+mod clock;
+mod scheduler;
+
+pub(crate) use clock::now_in;
+pub use clock::{Clock, Duration, Instant, CYCLES_PER_SECOND};
+pub use scheduler::{CallbackId, Scheduler};
+
 const fn timer_data(timer: usize) -> MemoryMapped<u16> {
     unsafe { MemoryMapped::new(0x0400_0100 + 4 * timer) }
 }
@@ -0,0 +1,305 @@
+use core::{
+    cell::RefCell,
+    ops::{Add, AddAssign, Mul, Sub, SubAssign},
+};
+
+use critical_section::{CriticalSection, Mutex};
+
+use crate::{
+    interrupt::{add_interrupt_handler, Interrupt, InterruptHandler},
+    memory_mapped::MemoryMapped,
+};
+
+use super::{timer_data, Divider, Timer};
+
+/// The number of CPU cycles in a second, which is how fast the [`Clock`] ticks.
+pub const CYCLES_PER_SECOND: u64 = 1 << 24;
+
+const INTERRUPT_REQUEST: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0202) };
+
+// The number of times each timer has overflowed while being used as a clock
+static OVERFLOWS: Mutex<RefCell<[u64; 4]>> = Mutex::new(RefCell::new([0; 4]));
+
+/// A length of time, stored as a number of CPU cycles.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Duration {
+    cycles: u64,
+}
+
+impl Duration {
+    /// A duration of no time at all.
+    pub const ZERO: Self = Self::from_cycles(0);
+
+    /// Creates a duration of `cycles` CPU cycles.
+    #[must_use]
+    pub const fn from_cycles(cycles: u64) -> Self {
+        Self { cycles }
+    }
+
+    /// Creates a duration of `micros` microseconds, rounded down to a whole number of cycles.
+    #[must_use]
+    pub const fn from_micros(micros: u64) -> Self {
+        Self::from_cycles(micros * CYCLES_PER_SECOND / 1_000_000)
+    }
+
+    /// Creates a duration of `millis` milliseconds, rounded down to a whole number of cycles.
+    #[must_use]
+    pub const fn from_millis(millis: u64) -> Self {
+        Self::from_cycles(millis * CYCLES_PER_SECOND / 1_000)
+    }
+
+    /// Creates a duration of `secs` seconds.
+    #[must_use]
+    pub const fn from_secs(secs: u64) -> Self {
+        Self::from_cycles(secs * CYCLES_PER_SECOND)
+    }
+
+    /// The number of CPU cycles in this duration.
+    #[must_use]
+    pub const fn as_cycles(self) -> u64 {
+        self.cycles
+    }
+
+    /// The number of whole microseconds in this duration.
+    #[must_use]
+    pub const fn as_micros(self) -> u64 {
+        self.cycles * 1_000_000 / CYCLES_PER_SECOND
+    }
+
+    /// The number of whole milliseconds in this duration.
+    #[must_use]
+    pub const fn as_millis(self) -> u64 {
+        self.cycles * 1_000 / CYCLES_PER_SECOND
+    }
+
+    /// Subtracts `other` from this duration, giving zero rather than overflowing.
+    #[must_use]
+    pub const fn saturating_sub(self, other: Duration) -> Duration {
+        Self::from_cycles(self.cycles.saturating_sub(other.cycles))
+    }
+}
+
+impl Add for Duration {
+    type Output = Duration;
+
+    fn add(self, rhs: Duration) -> Duration {
+        Duration::from_cycles(self.cycles + rhs.cycles)
+    }
+}
+
+impl AddAssign for Duration {
+    fn add_assign(&mut self, rhs: Duration) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Duration {
+    type Output = Duration;
+
+    fn sub(self, rhs: Duration) -> Duration {
+        Duration::from_cycles(self.cycles - rhs.cycles)
+    }
+}
+
+impl SubAssign for Duration {
+    fn sub_assign(&mut self, rhs: Duration) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<u32> for Duration {
+    type Output = Duration;
+
+    fn mul(self, rhs: u32) -> Duration {
+        Duration::from_cycles(self.cycles * u64::from(rhs))
+    }
+}
+
+impl From<Duration> for core::time::Duration {
+    fn from(duration: Duration) -> Self {
+        core::time::Duration::from_nanos(
+            (u128::from(duration.cycles) * 1_000_000_000 / u128::from(CYCLES_PER_SECOND)) as u64,
+        )
+    }
+}
+
+/// A point in time measured by a [`Clock`], as the number of cycles since the clock started.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant {
+    cycles: u64,
+}
+
+impl Instant {
+    /// The number of CPU cycles between the clock starting and this instant.
+    #[must_use]
+    pub const fn cycles(self) -> u64 {
+        self.cycles
+    }
+
+    /// The time between `earlier` and this instant, or zero if `earlier` is later.
+    #[must_use]
+    pub const fn duration_since(self, earlier: Instant) -> Duration {
+        Duration::from_cycles(self.cycles.saturating_sub(earlier.cycles))
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, rhs: Duration) -> Instant {
+        Instant {
+            cycles: self.cycles + rhs.cycles,
+        }
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, rhs: Duration) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    fn sub(self, rhs: Duration) -> Instant {
+        Instant {
+            cycles: self.cycles - rhs.cycles,
+        }
+    }
+}
+
+impl Sub for Instant {
+    type Output = Duration;
+
+    fn sub(self, rhs: Instant) -> Duration {
+        self.duration_since(rhs)
+    }
+}
+
+/// A free running clock which counts every CPU cycle, for timing gameplay or profiling.
+///
+/// The clock uses a hardware timer which overflows every 65536 cycles, and counts the
+/// overflows in its interrupt so that it can keep going for far longer than any game
+/// will run.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # fn foo(gba: &mut agb::Gba) {
+/// use agb::timer::Clock;
+///
+/// let timers = gba.timers.timers();
+/// let clock = Clock::new(timers.timer2);
+///
+/// let start = clock.now();
+/// // do something which takes a while
+/// agb::println!("That took {}us", clock.elapsed(start).as_micros());
+/// # }
+/// ```
+pub struct Clock {
+    timer: Timer,
+    _handler: InterruptHandler,
+}
+
+impl Clock {
+    /// Starts a clock using `timer`. The timer is stopped when the clock is dropped.
+    #[must_use]
+    pub fn new(mut timer: Timer) -> Self {
+        let timer_number = timer.timer_number();
+        critical_section::with(|cs| OVERFLOWS.borrow_ref_mut(cs)[timer_number] = 0);
+
+        // safety: doesn't allocate
+        let handler = unsafe {
+            add_interrupt_handler(timer.interrupt(), move |cs| {
+                OVERFLOWS.borrow_ref_mut(cs)[timer_number] += 1;
+            })
+        };
+
+        timer
+            .set_enabled(false)
+            .set_cascade(false)
+            .set_divider(Divider::Divider1)
+            .set_overflow_amount(0)
+            .set_interrupt(true)
+            .set_enabled(true);
+
+        Self {
+            timer,
+            _handler: handler,
+        }
+    }
+
+    /// The current time.
+    #[must_use]
+    pub fn now(&self) -> Instant {
+        critical_section::with(|cs| now_in(cs, self.timer.timer_number()))
+    }
+
+    /// The time since `earlier`.
+    #[must_use]
+    pub fn elapsed(&self, earlier: Instant) -> Duration {
+        self.now().duration_since(earlier)
+    }
+
+    pub(crate) fn timer_number(&self) -> usize {
+        self.timer.timer_number()
+    }
+}
+
+impl Drop for Clock {
+    fn drop(&mut self) {
+        self.timer.set_interrupt(false).set_enabled(false);
+    }
+}
+
+/// The current time of the clock running on timer `timer_number`.
+pub(crate) fn now_in(cs: CriticalSection<'_>, timer_number: usize) -> Instant {
+    let mut overflows = OVERFLOWS.borrow_ref(cs)[timer_number];
+    let mut counter = timer_data(timer_number).get();
+
+    // The timer might have overflowed since interrupts were disabled, in which case the
+    // interrupt hasn't counted it yet.
+    let overflow_pending =
+        INTERRUPT_REQUEST.get() & (1 << (Interrupt::Timer0 as usize + timer_number)) != 0;
+    if overflow_pending {
+        overflows += 1;
+        counter = timer_data(timer_number).get();
+    }
+
+    Instant {
+        cycles: (overflows << 16) | u64::from(counter),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn clock_keeps_counting_after_overflows(gba: &mut crate::Gba) {
+        let timers = gba.timers.timers();
+        let clock = Clock::new(timers.timer2);
+
+        let start = clock.now();
+        let mut previous = start;
+        for _ in 0..200_000 {
+            let now = clock.now();
+            assert!(now >= previous, "clock went backwards");
+            previous = now;
+        }
+
+        assert!(clock.elapsed(start) > Duration::from_cycles(1 << 16));
+    }
+
+    #[test_case]
+    fn durations_convert_between_units(_gba: &mut crate::Gba) {
+        assert_eq!(Duration::from_secs(1).as_cycles(), CYCLES_PER_SECOND);
+        assert_eq!(Duration::from_millis(1500).as_micros(), 1_500_000);
+        assert_eq!(Duration::from_micros(1).as_cycles(), 16);
+        assert_eq!(
+            core::time::Duration::from(Duration::from_secs(2)),
+            core::time::Duration::from_secs(2)
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use core::cell::RefCell;
+
+use alloc::{boxed::Box, vec::Vec};
+use critical_section::{CriticalSection, Mutex};
+
+use crate::interrupt::{add_interrupt_handler, InterruptHandler};
+
+use super::{
+    clock::{now_in, Clock, Duration, Instant},
+    timer_control, timer_data, Timer,
+};
+
+// The clock's timer overflows every this many cycles
+const CLOCK_PERIOD: u64 = 1 << 16;
+
+const CASCADE: u16 = 1 << 2;
+const INTERRUPT: u16 = 1 << 6;
+const ENABLE: u16 = 1 << 7;
+
+type Callback = Box<dyn FnMut(CriticalSection<'_>) + Send>;
+
+struct Scheduled {
+    id: u32,
+    // None once the callback has been cancelled, or it has run and doesn't repeat
+    deadline: Option<Instant>,
+    period: Option<Duration>,
+    callback: Callback,
+}
+
+struct SchedulerState {
+    clock_timer: usize,
+    alarm_timer: usize,
+    next_id: u32,
+    scheduled: Vec<Scheduled>,
+}
+
+impl SchedulerState {
+    fn run_due(&mut self, cs: CriticalSection<'_>) {
+        let now = now_in(cs, self.clock_timer);
+
+        for scheduled in &mut self.scheduled {
+            let Some(deadline) = scheduled.deadline else {
+                continue;
+            };
+
+            if deadline > now {
+                continue;
+            }
+
+            (scheduled.callback)(cs);
+
+            scheduled.deadline = scheduled.period.map(|period| {
+                // if we've fallen behind, don't try to catch up by calling it lots of times at once
+                let next = deadline + period;
+                if next <= now {
+                    now + period
+                } else {
+                    next
+                }
+            });
+        }
+    }
+
+    // Sets the alarm timer to go off at or before the next deadline. Deadlines more than one clock
+    // overflow away are approached by cascading from the clock's timer and counting its overflows,
+    // and then by counting the remaining cycles exactly.
+    fn arm(&self, cs: CriticalSection<'_>) {
+        let control = timer_control(self.alarm_timer);
+        control.set(0);
+
+        let Some(deadline) = self.scheduled.iter().filter_map(|s| s.deadline).min() else {
+            return;
+        };
+
+        let now = now_in(cs, self.clock_timer);
+        let delay = deadline.duration_since(now).as_cycles().max(1);
+
+        if delay < CLOCK_PERIOD {
+            timer_data(self.alarm_timer).set(0u16.wrapping_sub(delay as u16));
+            control.set(INTERRUPT | ENABLE);
+        } else {
+            // a count of 0 is a full 65536 overflows
+            let overflows = (delay / CLOCK_PERIOD).min(CLOCK_PERIOD);
+            timer_data(self.alarm_timer).set(0u16.wrapping_sub(overflows as u16));
+            control.set(CASCADE | INTERRUPT | ENABLE);
+        }
+    }
+
+    fn find(&mut self, id: CallbackId) -> Option<&mut Scheduled> {
+        self.scheduled
+            .iter_mut()
+            .find(|scheduled| scheduled.id == id.0 && scheduled.deadline.is_some())
+    }
+}
+
+static SCHEDULER: Mutex<RefCell<Option<SchedulerState>>> = Mutex::new(RefCell::new(None));
+
+fn with_state<R>(f: impl FnOnce(CriticalSection<'_>, &mut SchedulerState) -> R) -> R {
+    critical_section::with(|cs| {
+        let mut state = SCHEDULER.borrow_ref_mut(cs);
+        f(cs, state.as_mut().expect("scheduler should exist"))
+    })
+}
+
+/// Identifies a callback added to a [`Scheduler`], so that it can be cancelled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CallbackId(u32);
+
+/// Calls any number of callbacks after a delay or periodically, using a single hardware timer.
+///
+/// The scheduler needs a [`Clock`] for the current time, and its timer must be the one after the
+/// clock's timer so that it can cascade from it to wait for long delays. Callbacks are called in
+/// the timer's interrupt, so they have the same restrictions as
+/// [`add_interrupt_handler`](crate::interrupt::add_interrupt_handler).
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # fn foo(gba: &mut agb::Gba) {
+/// use agb::timer::{Clock, Duration, Scheduler};
+///
+/// let timers = gba.timers.timers();
+/// let clock = Clock::new(timers.timer2);
+/// let mut scheduler = Scheduler::new(&clock, timers.timer3);
+///
+/// // safety: doesn't allocate
+/// let blink = unsafe {
+///     scheduler.call_every(Duration::from_millis(500), |_| {
+///         // toggle a cursor
+///     })
+/// };
+///
+/// // later on
+/// scheduler.cancel(blink);
+/// # }
+/// ```
+pub struct Scheduler<'clock> {
+    _clock: &'clock Clock,
+    timer: Timer,
+    _handler: InterruptHandler,
+}
+
+impl<'clock> Scheduler<'clock> {
+    /// Creates a scheduler which gets the time from `clock` and uses `timer` to wait.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `timer` isn't the timer after the clock's timer, or if there is already a scheduler.
+    #[must_use]
+    pub fn new(clock: &'clock Clock, timer: Timer) -> Self {
+        assert_eq!(
+            timer.timer_number(),
+            clock.timer_number() + 1,
+            "the scheduler must use the timer after the clock's timer"
+        );
+
+        critical_section::with(|cs| {
+            let mut state = SCHEDULER.borrow_ref_mut(cs);
+            assert!(state.is_none(), "there can only be one scheduler");
+
+            *state = Some(SchedulerState {
+                clock_timer: clock.timer_number(),
+                alarm_timer: timer.timer_number(),
+                next_id: 0,
+                scheduled: Vec::new(),
+            });
+        });
+
+        // safety: running callbacks and setting the timer doesn't allocate, and callbacks promise not to
+        let handler = unsafe {
+            add_interrupt_handler(timer.interrupt(), |cs| {
+                if let Some(state) = SCHEDULER.borrow_ref_mut(cs).as_mut() {
+                    state.run_due(cs);
+                    state.arm(cs);
+                }
+            })
+        };
+
+        Self {
+            _clock: clock,
+            timer,
+            _handler: handler,
+        }
+    }
+
+    /// Calls `callback` once, after `delay`.
+    ///
+    /// # Safety
+    /// `callback` is called in an interrupt, so it *must not* allocate.
+    pub unsafe fn call_after(
+        &mut self,
+        delay: Duration,
+        callback: impl FnMut(CriticalSection<'_>) + Send + 'static,
+    ) -> CallbackId {
+        self.schedule(delay, None, Box::new(callback))
+    }
+
+    /// Calls `callback` every `period`, starting one `period` from now.
+    ///
+    /// # Safety
+    /// `callback` is called in an interrupt, so it *must not* allocate.
+    ///
+    /// # Panics
+    /// Panics if `period` is zero.
+    pub unsafe fn call_every(
+        &mut self,
+        period: Duration,
+        callback: impl FnMut(CriticalSection<'_>) + Send + 'static,
+    ) -> CallbackId {
+        assert!(period > Duration::ZERO, "period must be longer than zero");
+
+        self.schedule(period, Some(period), Box::new(callback))
+    }
+
+    fn schedule(
+        &mut self,
+        delay: Duration,
+        period: Option<Duration>,
+        callback: Callback,
+    ) -> CallbackId {
+        with_state(|cs, state| {
+            // callbacks which have finished can only be freed here, since the interrupt mustn't
+            state
+                .scheduled
+                .retain(|scheduled| scheduled.deadline.is_some());
+
+            let id = state.next_id;
+            state.next_id = state.next_id.wrapping_add(1);
+
+            state.scheduled.push(Scheduled {
+                id,
+                deadline: Some(now_in(cs, state.clock_timer) + delay),
+                period,
+                callback,
+            });
+
+            state.arm(cs);
+
+            CallbackId(id)
+        })
+    }
+
+    /// Stops `id` from being called again, returning whether it was still scheduled.
+    pub fn cancel(&mut self, id: CallbackId) -> bool {
+        with_state(|cs, state| {
+            let Some(scheduled) = state.find(id) else {
+                return false;
+            };
+
+            scheduled.deadline = None;
+            state.arm(cs);
+            true
+        })
+    }
+
+    /// Whether `id` is going to be called again.
+    #[must_use]
+    pub fn is_scheduled(&self, id: CallbackId) -> bool {
+        with_state(|_, state| state.find(id).is_some())
+    }
+}
+
+impl Drop for Scheduler<'_> {
+    fn drop(&mut self) {
+        self.timer.set_interrupt(false).set_enabled(false);
+        critical_section::with(|cs| SCHEDULER.borrow_ref_mut(cs).take());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use portable_atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    static CALLS: AtomicU32 = AtomicU32::new(0);
+
+    fn count_call(_cs: CriticalSection<'_>) {
+        CALLS.store(CALLS.load(Ordering::SeqCst) + 1, Ordering::SeqCst);
+    }
+
+    fn wait(clock: &Clock, duration: Duration) {
+        let start = clock.now();
+        while clock.elapsed(start) < duration {}
+    }
+
+    #[test_case]
+    fn callbacks_are_called_after_their_delay(gba: &mut crate::Gba) {
+        let timers = gba.timers.timers();
+        let clock = Clock::new(timers.timer2);
+        let mut scheduler = Scheduler::new(&clock, timers.timer3);
+        CALLS.store(0, Ordering::SeqCst);
+
+        // long enough that the scheduler has to cascade
+        let start = clock.now();
+        let id = unsafe { scheduler.call_after(Duration::from_millis(20), count_call) };
+        assert!(scheduler.is_scheduled(id));
+
+        while CALLS.load(Ordering::SeqCst) == 0 {
+            assert!(clock.elapsed(start) < Duration::from_millis(100));
+        }
+
+        assert!(clock.elapsed(start) >= Duration::from_millis(20));
+        assert!(!scheduler.is_scheduled(id));
+    }
+
+    #[test_case]
+    fn periodic_callbacks_repeat_until_cancelled(gba: &mut crate::Gba) {
+        let timers = gba.timers.timers();
+        let clock = Clock::new(timers.timer2);
+        let mut scheduler = Scheduler::new(&clock, timers.timer3);
+        CALLS.store(0, Ordering::SeqCst);
+
+        let id = unsafe { scheduler.call_every(Duration::from_millis(1), count_call) };
+        wait(&clock, Duration::from_micros(10_500));
+
+        assert!(scheduler.cancel(id));
+        let calls = CALLS.load(Ordering::SeqCst);
+        assert!((9..=11).contains(&calls), "called {calls} times");
+
+        wait(&clock, Duration::from_millis(3));
+        assert_eq!(CALLS.load(Ordering::SeqCst), calls);
+        assert!(!scheduler.cancel(id));
+    }
+}
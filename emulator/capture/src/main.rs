@@ -0,0 +1,111 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{anyhow, bail, Context};
+use clap::Parser;
+use mgba::{LogLevel, Logger, MCore, MemoryBacked};
+use mgba_harness::{input::InputScript, to_gba_file};
+use output::{captured_frame_count, Outputs};
+
+mod output;
+
+static LOGGER: Logger = Logger::new(my_logger);
+
+fn my_logger(category: &str, _level: LogLevel, s: String) {
+    if category == "GBA Debug" {
+        eprintln!("{s}");
+    }
+}
+
+/// Runs a ROM without a window for a fixed number of frames, saving what it draws and plays.
+#[derive(Parser)]
+struct CliArguments {
+    rom: PathBuf,
+
+    /// How many frames to run the ROM for.
+    #[arg(long, default_value_t = 60)]
+    frames: u32,
+
+    /// A TOML file of `[[input]]` button presses, in the same format as `mgba-test-runner` scenarios.
+    #[arg(long)]
+    input: Option<PathBuf>,
+
+    /// Only capture one in every this many frames.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    every: u32,
+
+    /// Write each captured frame to this directory as `frame_00000.png`, `frame_00001.png` and so on.
+    #[arg(long)]
+    frames_dir: Option<PathBuf>,
+
+    /// Write the captured frames to this file as an animated GIF.
+    #[arg(long)]
+    gif: Option<PathBuf>,
+
+    /// Write the captured frames to this file as an animated PNG, which unlike a GIF keeps every colour.
+    #[arg(long)]
+    apng: Option<PathBuf>,
+
+    /// Write the audio to this file as a WAV.
+    #[arg(long)]
+    wav: Option<PathBuf>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = CliArguments::parse();
+
+    let input_file = fs::read(&args.rom)
+        .with_context(|| anyhow!("Could not read ROM {}", args.rom.display()))?;
+    let input = args
+        .input
+        .as_ref()
+        .map(|path| {
+            InputScript::load(path)
+                .with_context(|| anyhow!("Could not load input script {}", path.display()))
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    let mut outputs = Outputs::new(args.every);
+    if let Some(dir) = &args.frames_dir {
+        outputs.dump_frames_to(dir)?;
+    }
+    if let Some(gif) = &args.gif {
+        outputs.write_gif_to(gif)?;
+    }
+    if let Some(apng) = &args.apng {
+        outputs.write_apng_to(apng, captured_frame_count(args.frames, args.every))?;
+    }
+    if let Some(wav) = &args.wav {
+        outputs.write_wav_to(wav);
+    }
+
+    mgba::set_global_default_logger(&LOGGER);
+    let Some(mut mgba) = MCore::new() else {
+        bail!("Could not create mgba core");
+    };
+    mgba.load_rom(MemoryBacked::new(to_gba_file(&input_file)));
+
+    let mut audio = [0; 0x1000];
+    for frame in 0..args.frames {
+        mgba.set_keys(input.keys_at(frame));
+        mgba.frame();
+
+        // mgba only buffers a few frames of audio, so it needs reading every frame
+        if outputs.wants_audio() {
+            loop {
+                let produced = mgba.read_audio(&mut audio);
+                if produced == 0 {
+                    break;
+                }
+
+                outputs.add_audio(&audio[..produced * 2]);
+            }
+        }
+
+        outputs.add_frame(frame, mgba.video_buffer())?;
+    }
+
+    outputs.finish()?;
+
+    Ok(())
+}
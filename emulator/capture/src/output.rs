@@ -0,0 +1,172 @@
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context};
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame,
+};
+use mgba_harness::screen::{video_buffer_to_image, HEIGHT, WIDTH};
+
+// The GBA runs at about 59.73 frames per second
+const FRAMES_PER_SECOND: u32 = 60;
+
+/// How many frames are captured when running for `frames` frames and capturing one in every `every`.
+pub fn captured_frame_count(frames: u32, every: u32) -> u32 {
+    frames.div_ceil(every)
+}
+
+/// Everything being written while the ROM runs. Frames are written as they're captured rather than
+/// kept in memory, since a long capture would use a lot of memory.
+pub struct Outputs {
+    frames_dir: Option<PathBuf>,
+    gif: Option<GifEncoder<BufWriter<File>>>,
+    apng: Option<png::Writer<BufWriter<File>>>,
+    wav: Option<(PathBuf, Vec<i16>)>,
+    every: u32,
+}
+
+impl Outputs {
+    pub fn new(every: u32) -> Self {
+        Self {
+            frames_dir: None,
+            gif: None,
+            apng: None,
+            wav: None,
+            every,
+        }
+    }
+
+    pub fn dump_frames_to(&mut self, dir: &Path) -> anyhow::Result<()> {
+        fs::create_dir_all(dir)
+            .with_context(|| anyhow!("Could not create directory {}", dir.display()))?;
+        self.frames_dir = Some(dir.to_owned());
+        Ok(())
+    }
+
+    pub fn write_gif_to(&mut self, path: &Path) -> anyhow::Result<()> {
+        let file = File::create(path)
+            .with_context(|| anyhow!("Could not create GIF {}", path.display()))?;
+
+        let mut encoder = GifEncoder::new(BufWriter::new(file));
+        encoder.set_repeat(Repeat::Infinite)?;
+        self.gif = Some(encoder);
+        Ok(())
+    }
+
+    pub fn write_apng_to(&mut self, path: &Path, frame_count: u32) -> anyhow::Result<()> {
+        let file = File::create(path)
+            .with_context(|| anyhow!("Could not create APNG {}", path.display()))?;
+
+        let mut encoder = png::Encoder::new(BufWriter::new(file), WIDTH as u32, HEIGHT as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(frame_count, 0)?;
+        encoder.set_frame_delay(self.every as u16, FRAMES_PER_SECOND as u16)?;
+
+        self.apng = Some(encoder.write_header()?);
+        Ok(())
+    }
+
+    pub fn write_wav_to(&mut self, path: &Path) {
+        self.wav = Some((path.to_owned(), Vec::new()));
+    }
+
+    pub fn wants_audio(&self) -> bool {
+        self.wav.is_some()
+    }
+
+    pub fn add_audio(&mut self, samples: &[i16]) {
+        if let Some((_, audio)) = &mut self.wav {
+            audio.extend_from_slice(samples);
+        }
+    }
+
+    /// Adds the screen after `frame` has run, if it's one of the frames being captured.
+    pub fn add_frame(&mut self, frame: u32, video_buffer: &[u32]) -> anyhow::Result<()> {
+        if !frame.is_multiple_of(self.every) {
+            return Ok(());
+        }
+
+        let image = video_buffer_to_image(video_buffer);
+
+        if let Some(dir) = &self.frames_dir {
+            let path = dir.join(format!("frame_{frame:05}.png"));
+            image
+                .save(&path)
+                .with_context(|| anyhow!("Could not write frame {}", path.display()))?;
+        }
+
+        if let Some(apng) = &mut self.apng {
+            apng.write_image_data(&image)?;
+        }
+
+        if let Some(gif) = &mut self.gif {
+            let delay = Delay::from_numer_denom_ms(self.every * 1000, FRAMES_PER_SECOND);
+            gif.encode_frame(Frame::from_parts(image, 0, 0, delay))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(self) -> anyhow::Result<()> {
+        if let Some(apng) = self.apng {
+            apng.finish()?;
+        }
+
+        if let Some((path, audio)) = self.wav {
+            let spec = hound::WavSpec {
+                channels: 2,
+                sample_rate: mgba::SAMPLE_RATE as u32,
+                bits_per_sample: 16,
+                sample_format: hound::SampleFormat::Int,
+            };
+
+            let mut writer = hound::WavWriter::create(&path, spec)
+                .with_context(|| anyhow!("Could not create WAV {}", path.display()))?;
+            for sample in audio {
+                writer.write_sample(sample)?;
+            }
+            writer.finalize()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_the_captured_frames() {
+        assert_eq!(captured_frame_count(60, 1), 60);
+        assert_eq!(captured_frame_count(60, 7), 9);
+        assert_eq!(captured_frame_count(3, 10), 1);
+    }
+
+    #[test]
+    fn writes_every_captured_frame_to_the_apng() {
+        let path = std::env::temp_dir().join("mgba-capture-test.png");
+        let video_buffer = vec![0x00ff_0000; WIDTH * HEIGHT];
+
+        let mut outputs = Outputs::new(2);
+        outputs
+            .write_apng_to(&path, captured_frame_count(5, 2))
+            .unwrap();
+        for frame in 0..5 {
+            outputs.add_frame(frame, &video_buffer).unwrap();
+        }
+        outputs.finish().unwrap();
+
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let reader = decoder.read_info().unwrap();
+        let animation = reader.info().animation_control.unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(animation.num_frames, 3);
+    }
+}
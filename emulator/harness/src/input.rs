@@ -0,0 +1,111 @@
+use std::{fs, path::Path};
+
+use serde::Deserialize;
+
+// Must match the bits of `agb::input::Button`
+#[derive(Clone, Copy, Debug, Deserialize)]
+enum Button {
+    A,
+    B,
+    Select,
+    Start,
+    Right,
+    Left,
+    Up,
+    Down,
+    R,
+    L,
+}
+
+/// Buttons to hold down from a frame, read from an `[[input]]` entry in a TOML file.
+///
+/// ```toml
+/// [[input]]
+/// frame = 60
+/// buttons = ["Start"]
+/// # how many frames to hold the buttons for, which defaults to 1
+/// hold = 2
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Input {
+    frame: u32,
+    buttons: Vec<Button>,
+    #[serde(default = "one_frame")]
+    hold: u32,
+}
+
+fn one_frame() -> u32 {
+    1
+}
+
+impl Input {
+    /// The first frame after the buttons are released.
+    pub fn end(&self) -> u32 {
+        self.frame + self.hold
+    }
+}
+
+/// The buttons from `inputs` which are held down during `frame`, in the format `MCore::set_keys` expects.
+pub fn keys_at(inputs: &[Input], frame: u32) -> u32 {
+    inputs
+        .iter()
+        .filter(|input| (input.frame..input.end()).contains(&frame))
+        .flat_map(|input| &input.buttons)
+        .fold(0, |keys, &button| keys | 1 << button as u32)
+}
+
+/// A TOML file containing nothing but `[[input]]` entries, see [`Input`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InputScript {
+    #[serde(default)]
+    input: Vec<Input>,
+}
+
+impl InputScript {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(script: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(script)?)
+    }
+
+    /// The buttons which are held down during `frame`, in the format `MCore::set_keys` expects.
+    pub fn keys_at(&self, frame: u32) -> u32 {
+        keys_at(&self.input, frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holds_buttons_for_the_given_frames() {
+        let script = InputScript::parse(
+            r#"
+            [[input]]
+            frame = 2
+            buttons = ["Up", "B"]
+            hold = 2
+
+            [[input]]
+            frame = 3
+            buttons = ["L"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(script.keys_at(1), 0);
+        assert_eq!(script.keys_at(2), 0b100_0010);
+        assert_eq!(script.keys_at(3), 0b10_0100_0010);
+        assert_eq!(script.keys_at(4), 0);
+    }
+
+    #[test]
+    fn rejects_unknown_buttons() {
+        assert!(InputScript::parse("[[input]]\nframe = 1\nbuttons = [\"Z\"]\n").is_err());
+    }
+}
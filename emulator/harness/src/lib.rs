@@ -0,0 +1,25 @@
+//! Code shared by the tools which run games in mgba, such as `mgba-test-runner` and `mgba-capture`.
+
+pub mod input;
+pub mod screen;
+
+/// Converts an ELF built by agb into a ROM mgba can run. Anything which isn't an ELF, such as a
+/// ROM which has already been converted, is returned unchanged.
+pub fn to_gba_file(input_file: &[u8]) -> Vec<u8> {
+    let mut gba_file = Vec::new();
+
+    let include_debug_info = false;
+    if agb_gbafix::write_gba_file(
+        input_file,
+        Default::default(),
+        agb_gbafix::PaddingBehaviour::DoNotPad,
+        include_debug_info,
+        &mut gba_file,
+    )
+    .is_ok()
+    {
+        gba_file
+    } else {
+        input_file.to_vec()
+    }
+}
@@ -0,0 +1,11 @@
+use image::{Rgba, RgbaImage};
+
+pub const WIDTH: usize = 240;
+pub const HEIGHT: usize = 160;
+
+pub fn video_buffer_to_image(video_buffer: &[u32]) -> RgbaImage {
+    RgbaImage::from_fn(WIDTH as u32, HEIGHT as u32, |x, y| {
+        let [r, g, b, _] = video_buffer[x as usize + y as usize * WIDTH].to_le_bytes();
+        Rgba([r, g, b, 255])
+    })
+}
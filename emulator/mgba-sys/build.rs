@@ -38,6 +38,12 @@ fn generate_bindings() -> MyError {
         .allowlist_type("VDir")
         .allowlist_type("mLogger")
         .allowlist_type("mLogLevel")
+        .allowlist_type("mDebugger")
+        .allowlist_type("mDebuggerPlatform")
+        .allowlist_type("mDebuggerEntryInfo")
+        .allowlist_type("mDebuggerEntryReason")
+        .allowlist_type("mBreakpoint")
+        .allowlist_type("mWatchpoint")
         .allowlist_var("MAP_WRITE")
         .allowlist_var("BYTES_PER_PIXEL")
         .allowlist_function("GBACoreCreate")
@@ -50,6 +56,8 @@ fn generate_bindings() -> MyError {
         .allowlist_function("mCoreLoadConfig")
         .allowlist_function("mTimingGlobalTime")
         .allowlist_function("mLogCategoryName")
+        .allowlist_function("mDebuggerAttach")
+        .allowlist_function("mDebuggerRun")
         .generate_cstr(true)
         .derive_default(true)
         .clang_arg("-I./mgba/include")
@@ -3,4 +3,5 @@
 #include "mgba/include/mgba/core/core.h"
 #include "mgba/include/mgba/core/log.h"
 #include "mgba/include/mgba/core/timing.h"
+#include "mgba/include/mgba/debugger/debugger.h"
 #include "mgba/include/mgba/gba/core.h"
\ No newline at end of file
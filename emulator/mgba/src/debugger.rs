@@ -0,0 +1,201 @@
+/// Identifies a breakpoint or watchpoint so that it can be removed, and which one was hit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BreakpointId(pub(crate) isize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    ReadWrite,
+    /// Only writes which change the value.
+    Change,
+}
+
+impl WatchKind {
+    pub(crate) fn to_mgba(self) -> mgba_sys::mWatchpointType {
+        match self {
+            WatchKind::Read => mgba_sys::mWatchpointType_WATCHPOINT_READ,
+            WatchKind::Write => mgba_sys::mWatchpointType_WATCHPOINT_WRITE,
+            WatchKind::ReadWrite => mgba_sys::mWatchpointType_WATCHPOINT_RW,
+            WatchKind::Change => mgba_sys::mWatchpointType_WATCHPOINT_WRITE_CHANGE,
+        }
+    }
+
+    fn from_mgba(watch_type: mgba_sys::mWatchpointType) -> Self {
+        match watch_type {
+            mgba_sys::mWatchpointType_WATCHPOINT_READ => WatchKind::Read,
+            mgba_sys::mWatchpointType_WATCHPOINT_WRITE => WatchKind::Write,
+            mgba_sys::mWatchpointType_WATCHPOINT_WRITE_CHANGE => WatchKind::Change,
+            _ => WatchKind::ReadWrite,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugEvent {
+    Breakpoint {
+        id: BreakpointId,
+        address: u32,
+    },
+    Watchpoint {
+        id: BreakpointId,
+        address: u32,
+        /// Whether this access was a read or a write.
+        access: WatchKind,
+        old_value: u32,
+        new_value: u32,
+    },
+    IllegalInstruction {
+        address: u32,
+        opcode: u32,
+    },
+}
+
+impl DebugEvent {
+    fn from_entry(
+        reason: mgba_sys::mDebuggerEntryReason,
+        info: Option<&mgba_sys::mDebuggerEntryInfo>,
+    ) -> Option<Self> {
+        let info = info?;
+
+        Some(match reason {
+            mgba_sys::mDebuggerEntryReason_DEBUGGER_ENTER_BREAKPOINT => DebugEvent::Breakpoint {
+                id: BreakpointId(info.pointId),
+                address: info.address,
+            },
+            mgba_sys::mDebuggerEntryReason_DEBUGGER_ENTER_WATCHPOINT => {
+                let watchpoint = unsafe { info.type_.wp };
+                DebugEvent::Watchpoint {
+                    id: BreakpointId(info.pointId),
+                    address: info.address,
+                    access: WatchKind::from_mgba(watchpoint.accessType),
+                    old_value: watchpoint.oldValue,
+                    new_value: watchpoint.newValue,
+                }
+            }
+            mgba_sys::mDebuggerEntryReason_DEBUGGER_ENTER_ILLEGAL_OP => {
+                DebugEvent::IllegalInstruction {
+                    address: info.address,
+                    opcode: unsafe { info.type_.bp.opcode },
+                }
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// What the emulator should do after a breakpoint or watchpoint is hit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugAction {
+    Continue,
+    /// Return the event from `MCore::run_until_paused`.
+    Pause,
+}
+
+type Callback = Box<dyn FnMut(&DebugEvent) -> DebugAction>;
+
+#[repr(C)]
+pub(crate) struct Debugger {
+    // must be the first field, since `entered` gets the debugger and casts it back to this
+    debugger: mgba_sys::mDebugger,
+    callback: Option<Callback>,
+    paused_on: Option<DebugEvent>,
+}
+
+impl Debugger {
+    /// Attaches a new debugger to `core`. It must be detached before it is dropped.
+    pub(crate) unsafe fn attach(core: *mut mgba_sys::mCore) -> Box<Self> {
+        let mut debugger = Box::new(Debugger {
+            debugger: mgba_sys::mDebugger {
+                state: mgba_sys::mDebuggerState_DEBUGGER_RUNNING,
+                type_: mgba_sys::mDebuggerType_DEBUGGER_CUSTOM,
+                entered: Some(entered),
+                ..Default::default()
+            },
+            callback: None,
+            paused_on: None,
+        });
+
+        mgba_sys::mDebuggerAttach(&mut debugger.debugger, core);
+
+        debugger
+    }
+
+    /// Detaches the debugger from `core`, freeing the platform mgba created for it.
+    pub(crate) unsafe fn detach(&mut self, core: *mut mgba_sys::mCore) {
+        (*core).detachDebugger.unwrap()(core);
+
+        let platform = self.debugger.platform;
+        if !platform.is_null() {
+            if let Some(deinit) = (*platform).deinit {
+                deinit(platform);
+            }
+            libc::free(platform.cast());
+            self.debugger.platform = std::ptr::null_mut();
+        }
+    }
+
+    fn platform(&mut self) -> *mut mgba_sys::mDebuggerPlatform {
+        self.debugger.platform
+    }
+
+    pub(crate) fn set_breakpoint(&mut self, address: u32) -> BreakpointId {
+        let breakpoint = mgba_sys::mBreakpoint {
+            address,
+            segment: -1,
+            type_: mgba_sys::mBreakpointType_BREAKPOINT_HARDWARE,
+            ..Default::default()
+        };
+
+        let platform = self.platform();
+        BreakpointId(unsafe { (*platform).setBreakpoint.unwrap()(platform, &breakpoint) })
+    }
+
+    pub(crate) fn set_watchpoint(&mut self, address: u32, kind: WatchKind) -> BreakpointId {
+        let watchpoint = mgba_sys::mWatchpoint {
+            address,
+            segment: -1,
+            type_: kind.to_mgba(),
+            ..Default::default()
+        };
+
+        let platform = self.platform();
+        BreakpointId(unsafe { (*platform).setWatchpoint.unwrap()(platform, &watchpoint) })
+    }
+
+    pub(crate) fn clear(&mut self, id: BreakpointId) -> bool {
+        let platform = self.platform();
+        unsafe { (*platform).clearBreakpoint.unwrap()(platform, id.0) }
+    }
+
+    pub(crate) fn set_callback(&mut self, callback: Callback) {
+        self.callback = Some(callback);
+    }
+
+    /// Runs the emulator for a little while, returning the event if it paused.
+    pub(crate) fn run(&mut self) -> Option<DebugEvent> {
+        unsafe { mgba_sys::mDebuggerRun(&mut self.debugger) };
+        self.paused_on.take()
+    }
+}
+
+unsafe extern "C" fn entered(
+    debugger: *mut mgba_sys::mDebugger,
+    reason: mgba_sys::mDebuggerEntryReason,
+    info: *mut mgba_sys::mDebuggerEntryInfo,
+) {
+    let debugger = &mut *debugger.cast::<Debugger>();
+
+    let Some(event) = DebugEvent::from_entry(reason, info.as_ref()) else {
+        return;
+    };
+
+    let action = match &mut debugger.callback {
+        Some(callback) => callback(&event),
+        None => DebugAction::Pause,
+    };
+
+    if action == DebugAction::Pause {
+        debugger.paused_on = Some(event);
+    }
+}
@@ -1,29 +1,47 @@
+mod debugger;
 mod log;
 mod vfile;
 
 use std::{
     cell::UnsafeCell,
+    ffi::CString,
     ptr::NonNull,
     sync::atomic::{AtomicBool, Ordering},
 };
 
+pub use debugger::{BreakpointId, DebugAction, DebugEvent, WatchKind};
 pub use log::{LogLevel, Logger};
 pub use vfile::{file::FileBacked, memory::MemoryBacked, shared::Shared, MapFlag, VFile};
 
+use debugger::Debugger;
+use thiserror::Error;
 use vfile::VFileAlloc;
 
 pub struct MCore {
     core: NonNull<mgba_sys::mCore>,
     video_buffer: UnsafeCell<Box<[u32]>>,
+    debugger: Option<Box<Debugger>>,
 }
 
 impl Drop for MCore {
     fn drop(&mut self) {
+        if let Some(debugger) = &mut self.debugger {
+            unsafe { debugger.detach(self.core.as_ptr()) };
+        }
+
         unsafe { self.core.as_ref().deinit.unwrap()(self.core.as_ptr()) }
     }
 }
 
-const SAMPLE_RATE: f64 = 44100.0;
+#[derive(Debug, Error)]
+pub enum LoadStateError {
+    #[error("The save state is {actual} bytes long but should be {expected} bytes")]
+    WrongSize { expected: usize, actual: usize },
+    #[error("mgba could not load the save state")]
+    Rejected,
+}
+
+pub const SAMPLE_RATE: f64 = 44100.0;
 
 macro_rules! call_on_core {
     ($core:expr => $fn_name:ident($($arg:expr),* $(,)?)) => {
@@ -100,7 +118,11 @@ impl MCore {
         unsafe { mgba_sys::mCoreConfigLoadDefaults(&mut (*core.as_ptr()).config, &core_options) };
         unsafe { mgba_sys::mCoreLoadConfig(core.as_ptr()) };
 
-        Some(MCore { core, video_buffer })
+        Some(MCore {
+            core,
+            video_buffer,
+            debugger: None,
+        })
     }
 
     pub fn load_rom<V: VFile>(&mut self, vfile: V) {
@@ -120,6 +142,10 @@ impl MCore {
         unsafe { call_on_core!(self.core=>setKeys(buttons)) };
     }
 
+    pub fn frame_counter(&mut self) -> u32 {
+        unsafe { call_on_core!(self.core=>frameCounter()) }
+    }
+
     pub fn load_save<V: VFile>(&mut self, save_file: V) {
         let save_file = VFileAlloc::new(save_file);
         unsafe {
@@ -133,6 +159,136 @@ impl MCore {
         unsafe { &*self.video_buffer.get() }
     }
 
+    pub fn read_u8(&mut self, address: u32) -> u8 {
+        unsafe { call_on_core!(self.core=>busRead8(address)) as u8 }
+    }
+
+    pub fn read_u16(&mut self, address: u32) -> u16 {
+        unsafe { call_on_core!(self.core=>busRead16(address)) as u16 }
+    }
+
+    pub fn read_u32(&mut self, address: u32) -> u32 {
+        unsafe { call_on_core!(self.core=>busRead32(address)) }
+    }
+
+    pub fn write_u8(&mut self, address: u32, value: u8) {
+        unsafe { call_on_core!(self.core=>busWrite8(address, value)) };
+    }
+
+    pub fn write_u16(&mut self, address: u32, value: u16) {
+        unsafe { call_on_core!(self.core=>busWrite16(address, value)) };
+    }
+
+    pub fn write_u32(&mut self, address: u32, value: u32) {
+        unsafe { call_on_core!(self.core=>busWrite32(address, value)) };
+    }
+
+    pub fn read_memory(&mut self, address: u32, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|offset| self.read_u8(address + offset as u32))
+            .collect()
+    }
+
+    pub fn write_memory(&mut self, address: u32, data: &[u8]) {
+        for (offset, &byte) in data.iter().enumerate() {
+            self.write_u8(address + offset as u32, byte);
+        }
+    }
+
+    /// Reads a CPU register by name, such as `r0`, `sp`, `pc` or `cpsr`.
+    pub fn read_register(&mut self, name: &str) -> Option<u32> {
+        let name = CString::new(name).ok()?;
+        let mut value = 0u32;
+
+        let found = unsafe {
+            call_on_core!(self.core=>readRegister(name.as_ptr(), (&mut value as *mut u32).cast()))
+        };
+
+        found.then_some(value)
+    }
+
+    /// Sets a CPU register by name, returning whether the register exists.
+    pub fn write_register(&mut self, name: &str, value: u32) -> bool {
+        let Ok(name) = CString::new(name) else {
+            return false;
+        };
+
+        unsafe {
+            call_on_core!(self.core=>writeRegister(name.as_ptr(), (&value as *const u32).cast()))
+        }
+    }
+
+    /// Saves the whole state of the emulator, which can be restored with [`MCore::load_state`].
+    pub fn save_state(&mut self) -> Option<Vec<u8>> {
+        let size = unsafe { call_on_core!(self.core=>stateSize()) };
+        let mut state = vec![0u8; size];
+
+        let saved = unsafe { call_on_core!(self.core=>saveState(state.as_mut_ptr().cast())) };
+        saved.then_some(state)
+    }
+
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), LoadStateError> {
+        let expected = unsafe { call_on_core!(self.core=>stateSize()) };
+        if state.len() != expected {
+            return Err(LoadStateError::WrongSize {
+                expected,
+                actual: state.len(),
+            });
+        }
+
+        let loaded = unsafe { call_on_core!(self.core=>loadState(state.as_ptr().cast())) };
+        if loaded {
+            Ok(())
+        } else {
+            Err(LoadStateError::Rejected)
+        }
+    }
+
+    fn debugger(&mut self) -> &mut Debugger {
+        let core = self.core.as_ptr();
+        self.debugger
+            .get_or_insert_with(|| unsafe { Debugger::attach(core) })
+    }
+
+    /// Pauses [`MCore::run_until_paused`] when the instruction at `address` is about to run.
+    pub fn add_breakpoint(&mut self, address: u32) -> BreakpointId {
+        self.debugger().set_breakpoint(address)
+    }
+
+    /// Pauses [`MCore::run_until_paused`] when `address` is accessed.
+    pub fn add_watchpoint(&mut self, address: u32, kind: WatchKind) -> BreakpointId {
+        self.debugger().set_watchpoint(address, kind)
+    }
+
+    /// Removes a breakpoint or watchpoint, returning whether it existed.
+    pub fn remove_breakpoint(&mut self, id: BreakpointId) -> bool {
+        self.debugger().clear(id)
+    }
+
+    /// Calls `callback` whenever a breakpoint or watchpoint is hit while running with
+    /// [`MCore::run_until_paused`], which decides whether to keep going. Without a callback, every
+    /// breakpoint and watchpoint pauses.
+    pub fn set_debug_callback(
+        &mut self,
+        callback: impl FnMut(&DebugEvent) -> DebugAction + 'static,
+    ) {
+        self.debugger().set_callback(Box::new(callback));
+    }
+
+    /// Runs for up to `frames` frames, stopping early if a breakpoint or watchpoint pauses the
+    /// emulator, in which case it returns what was hit.
+    pub fn run_until_paused(&mut self, frames: u32) -> Option<DebugEvent> {
+        let target = self.frame_counter().wrapping_add(frames);
+
+        while self.frame_counter() != target {
+            if let Some(event) = self.debugger().run() {
+                return Some(event);
+            }
+        }
+
+        None
+    }
+
     pub fn current_cycle(&mut self) -> u64 {
         unsafe { mgba_sys::mTimingGlobalTime(self.core.as_ref().timing) }
     }
@@ -177,6 +333,60 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn save_states_restore_memory() {
+        let file = MemoryBacked::new_from_slice(TEST_ROM);
+        let mut core = MCore::new().unwrap();
+        core.load_rom(file);
+        core.frame();
+
+        let address = 0x0203_fff0;
+        core.write_u32(address, 0x1234_5678);
+        let state = core.save_state().unwrap();
+
+        core.write_u32(address, 0);
+        core.frame();
+        core.load_state(&state).unwrap();
+
+        assert_eq!(core.read_u32(address), 0x1234_5678);
+        assert_eq!(core.read_u16(address), 0x5678);
+        assert_eq!(core.read_memory(address, 2), [0x78, 0x56]);
+        assert!(matches!(
+            core.load_state(&state[1..]),
+            Err(LoadStateError::WrongSize { .. })
+        ));
+    }
+
+    #[test]
+    fn registers_can_be_read_and_written() {
+        let file = MemoryBacked::new_from_slice(TEST_ROM);
+        let mut core = MCore::new().unwrap();
+        core.load_rom(file);
+        core.frame();
+
+        assert!(core.write_register("r0", 42));
+        assert_eq!(core.read_register("r0"), Some(42));
+        assert_eq!(core.read_register("not_a_register"), None);
+    }
+
+    #[test]
+    fn watchpoints_pause_when_memory_is_written() {
+        let file = MemoryBacked::new_from_slice(TEST_ROM);
+        let mut core = MCore::new().unwrap();
+        core.load_rom(file);
+
+        // the display control register is written when the game starts
+        let id = core.add_watchpoint(0x0400_0000, WatchKind::Write);
+        let event = core.run_until_paused(60);
+
+        assert!(
+            matches!(event, Some(DebugEvent::Watchpoint { id: hit, .. }) if hit == id),
+            "{event:?}"
+        );
+        assert!(core.remove_breakpoint(id));
+        assert_eq!(core.run_until_paused(5), None);
+    }
+
     #[test]
     fn check_running_game_for_some_frames() {
         let file = MemoryBacked::new_from_slice(TEST_ROM);
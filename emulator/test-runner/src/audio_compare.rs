@@ -0,0 +1,404 @@
+use std::{f64::consts::PI, path::Path};
+
+use anyhow::{anyhow, bail, Context};
+
+pub const SAMPLE_RATE: u32 = mgba::SAMPLE_RATE as u32;
+
+// How many stereo samples the recording can be longer or shorter than the reference by, which is
+// about a frame.
+const LENGTH_TOLERANCE: usize = 800;
+
+const SPECTRUM_WINDOW: usize = 1024;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AudioOptions {
+    /// How far each sample can be from the reference and still match.
+    pub tolerance: u16,
+    /// Compare the frequencies in each window rather than the samples, allowing this fraction of
+    /// difference between the spectra.
+    pub spectral: Option<f64>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AudioComparison {
+    Matches,
+    LengthMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    SamplesDiffer {
+        count: usize,
+        /// The index of the first stereo sample which differs.
+        first: usize,
+        max_difference: u16,
+    },
+    SpectrumDiffers {
+        /// The time the first window which differs starts at, in seconds.
+        at: f64,
+        difference: f64,
+    },
+}
+
+impl AudioComparison {
+    pub fn success(&self) -> bool {
+        matches!(self, AudioComparison::Matches)
+    }
+}
+
+/// The assertion sent by `agb::test_runner::assert_audio_output`, which is the path to the
+/// reference WAV followed by any options, for example `audio:sfx/jump.wav;tolerance=64` or
+/// `audio:music.wav;spectral=5` where the spectral difference is a percentage.
+pub fn parse_audio_assertion(assertion: &str) -> anyhow::Result<(&str, AudioOptions)> {
+    let mut parts = assertion.split(';');
+    let path = parts.next().unwrap_or_default();
+    let mut options = AudioOptions::default();
+
+    for part in parts {
+        let (key, value) = part
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Invalid audio option {part}"))?;
+
+        match key {
+            "tolerance" => {
+                options.tolerance = value
+                    .parse()
+                    .with_context(|| anyhow!("Invalid tolerance {value}"))?;
+            }
+            "spectral" => {
+                let percent: u8 = value
+                    .parse()
+                    .with_context(|| anyhow!("Invalid spectral difference {value}"))?;
+                options.spectral = Some(f64::from(percent) / 100.0);
+            }
+            _ => bail!("Unknown audio option {key}"),
+        }
+    }
+
+    Ok((path, options))
+}
+
+/// Loads a 16 bit stereo WAV at the emulator's sample rate as interleaved samples.
+pub fn load_wav(path: impl AsRef<Path>) -> anyhow::Result<Vec<i16>> {
+    let reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+
+    if spec.channels != 2
+        || spec.bits_per_sample != 16
+        || spec.sample_format != hound::SampleFormat::Int
+        || spec.sample_rate != SAMPLE_RATE
+    {
+        bail!(
+            "Expected 16 bit stereo audio at {SAMPLE_RATE}Hz but got {} bit audio with {} channels at {}Hz",
+            spec.bits_per_sample,
+            spec.channels,
+            spec.sample_rate
+        );
+    }
+
+    Ok(reader.into_samples().collect::<Result<_, _>>()?)
+}
+
+pub fn write_wav(path: impl AsRef<Path>, samples: &[i16]) -> anyhow::Result<()> {
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    for &sample in samples {
+        writer.write_sample(sample)?;
+    }
+    writer.finalize()?;
+
+    Ok(())
+}
+
+/// Compares interleaved stereo samples against the reference, over the length they have in common.
+pub fn compare_audio(expected: &[i16], actual: &[i16], options: &AudioOptions) -> AudioComparison {
+    let (expected_len, actual_len) = (expected.len() / 2, actual.len() / 2);
+    if expected_len.abs_diff(actual_len) > LENGTH_TOLERANCE {
+        return AudioComparison::LengthMismatch {
+            expected: expected_len,
+            actual: actual_len,
+        };
+    }
+
+    let len = expected_len.min(actual_len) * 2;
+    let (expected, actual) = (&expected[..len], &actual[..len]);
+
+    match options.spectral {
+        Some(allowed) => compare_spectra(expected, actual, allowed),
+        None => compare_samples(expected, actual, options.tolerance),
+    }
+}
+
+fn compare_samples(expected: &[i16], actual: &[i16], tolerance: u16) -> AudioComparison {
+    let mut count = 0;
+    let mut first = None;
+    let mut max_difference = 0;
+
+    for (i, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+        let difference = expected.abs_diff(*actual);
+        if difference > tolerance {
+            count += 1;
+            first.get_or_insert(i / 2);
+            max_difference = max_difference.max(difference);
+        }
+    }
+
+    match first {
+        None => AudioComparison::Matches,
+        Some(first) => AudioComparison::SamplesDiffer {
+            count,
+            first,
+            max_difference,
+        },
+    }
+}
+
+fn compare_spectra(expected: &[i16], actual: &[i16], allowed: f64) -> AudioComparison {
+    // the last window is usually shorter, and is padded with silence so short clips are compared too
+    let windows = expected
+        .chunks(SPECTRUM_WINDOW * 2)
+        .zip(actual.chunks(SPECTRUM_WINDOW * 2));
+
+    for (i, (expected, actual)) in windows.enumerate() {
+        let expected = spectrum(expected);
+        let actual = spectrum(actual);
+
+        let total: f64 = expected.iter().chain(&actual).sum();
+        let different: f64 = expected
+            .iter()
+            .zip(&actual)
+            .map(|(expected, actual)| (expected - actual).abs())
+            .sum();
+
+        // windows which are both silent are the same, even though they'd divide by zero
+        let difference = if total < f64::EPSILON {
+            0.0
+        } else {
+            different / total
+        };
+
+        if difference > allowed {
+            return AudioComparison::SpectrumDiffers {
+                at: (i * SPECTRUM_WINDOW) as f64 / f64::from(SAMPLE_RATE),
+                difference,
+            };
+        }
+    }
+
+    AudioComparison::Matches
+}
+
+// The magnitude of each frequency in a window of up to SPECTRUM_WINDOW interleaved stereo samples,
+// mixed down to mono. Shorter windows are padded with silence.
+fn spectrum(samples: &[i16]) -> Vec<f64> {
+    let n = samples.len() / 2;
+
+    let mut re: Vec<f64> = samples
+        .chunks_exact(2)
+        .enumerate()
+        .map(|(i, pair)| {
+            let hann = 0.5 - 0.5 * (2.0 * PI * i as f64 / n as f64).cos();
+            (f64::from(pair[0]) + f64::from(pair[1])) / 2.0 * hann
+        })
+        .collect();
+    re.resize(SPECTRUM_WINDOW, 0.0);
+    let mut im = vec![0.0; SPECTRUM_WINDOW];
+
+    fft(&mut re, &mut im);
+
+    re.iter()
+        .zip(&im)
+        .take(SPECTRUM_WINDOW / 2)
+        .map(|(re, im)| re.hypot(*im))
+        .collect()
+}
+
+// An in place radix 2 fast Fourier transform, so the length must be a power of 2
+fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    assert!(n.is_power_of_two() && im.len() == n);
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f64).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // interleaved stereo samples of a sine wave
+    fn sine(frequency: f64, phase: f64, len: usize) -> Vec<i16> {
+        (0..len)
+            .flat_map(|i| {
+                let t = i as f64 / f64::from(SAMPLE_RATE);
+                let sample = ((2.0 * PI * frequency * t + phase).sin() * 8000.0) as i16;
+                [sample, sample]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fft_finds_the_frequency_of_a_sine_wave() {
+        let mut re: Vec<f64> = (0..64)
+            .map(|i| (2.0 * PI * 8.0 * i as f64 / 64.0).sin())
+            .collect();
+        let mut im = vec![0.0; 64];
+
+        fft(&mut re, &mut im);
+
+        let magnitudes: Vec<f64> = re.iter().zip(&im).map(|(re, im)| re.hypot(*im)).collect();
+        let loudest = (0..32)
+            .max_by(|a, b| magnitudes[*a].total_cmp(&magnitudes[*b]))
+            .unwrap();
+
+        assert_eq!(loudest, 8);
+        assert!((magnitudes[8] - 32.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn samples_must_be_within_the_tolerance() {
+        let expected = sine(440.0, 0.0, 4096);
+        let mut actual = expected.clone();
+        actual[101] += 10;
+        actual[3001] -= 100;
+
+        assert!(compare_audio(
+            &expected,
+            &actual,
+            &AudioOptions {
+                tolerance: 100,
+                spectral: None
+            }
+        )
+        .success());
+
+        assert_eq!(
+            compare_audio(&expected, &actual, &AudioOptions::default()),
+            AudioComparison::SamplesDiffer {
+                count: 2,
+                first: 50,
+                max_difference: 100
+            }
+        );
+    }
+
+    #[test]
+    fn spectral_comparison_ignores_phase_but_not_pitch() {
+        let expected = sine(440.0, 0.0, 8192);
+        let options = AudioOptions {
+            tolerance: 0,
+            spectral: Some(0.05),
+        };
+
+        assert!(compare_audio(&expected, &sine(440.0, 1.0, 8192), &options).success());
+        assert!(
+            !compare_audio(&expected, &sine(440.0, 1.0, 8192), &AudioOptions::default()).success()
+        );
+        assert!(matches!(
+            compare_audio(&expected, &sine(660.0, 0.0, 8192), &options),
+            AudioComparison::SpectrumDiffers { at, .. } if at == 0.0
+        ));
+    }
+
+    #[test]
+    fn spectral_comparison_checks_clips_shorter_than_a_window() {
+        let options = AudioOptions {
+            tolerance: 0,
+            spectral: Some(0.05),
+        };
+
+        assert!(compare_audio(&sine(440.0, 0.0, 600), &sine(440.0, 1.0, 600), &options).success());
+        assert!(!compare_audio(&sine(440.0, 0.0, 600), &sine(660.0, 0.0, 600), &options).success());
+
+        // only the partial window at the end differs
+        let expected = sine(440.0, 0.0, SPECTRUM_WINDOW + 500);
+        let mut actual = expected.clone();
+        actual[SPECTRUM_WINDOW * 2..].copy_from_slice(&sine(660.0, 0.0, 500));
+        assert!(matches!(
+            compare_audio(&expected, &actual, &options),
+            AudioComparison::SpectrumDiffers { at, .. }
+                if at == SPECTRUM_WINDOW as f64 / f64::from(SAMPLE_RATE)
+        ));
+    }
+
+    #[test]
+    fn recordings_of_very_different_lengths_do_not_match() {
+        let expected = sine(440.0, 0.0, 4096);
+
+        assert!(compare_audio(
+            &expected,
+            &expected[..expected.len() - 200],
+            &AudioOptions::default()
+        )
+        .success());
+        assert_eq!(
+            compare_audio(&expected, &expected[..2048], &AudioOptions::default()),
+            AudioComparison::LengthMismatch {
+                expected: 4096,
+                actual: 1024
+            }
+        );
+    }
+
+    #[test]
+    fn parses_audio_assertions() {
+        let (path, options) =
+            parse_audio_assertion("sfx/jump.wav;tolerance=64;spectral=5").unwrap();
+
+        assert_eq!(path, "sfx/jump.wav");
+        assert_eq!(options.tolerance, 64);
+        assert_eq!(options.spectral, Some(0.05));
+
+        assert!(parse_audio_assertion("sfx/jump.wav;spectral=0.5").is_err());
+        assert!(parse_audio_assertion("sfx/jump.wav;volume=1").is_err());
+    }
+
+    #[test]
+    fn wav_files_round_trip() {
+        let path = std::env::temp_dir().join("mgba-test-runner-round-trip.wav");
+        let samples = sine(440.0, 0.0, 1000);
+
+        write_wav(&path, &samples).unwrap();
+        let loaded = load_wav(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(loaded, samples);
+    }
+}
@@ -1,19 +1,130 @@
-use std::path::Path;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
-use image::io::Reader;
+use anyhow::{anyhow, bail, Context};
+use image::{io::Reader, Rgba, RgbaImage};
+use mgba_harness::screen::{video_buffer_to_image, HEIGHT, WIDTH};
 
-pub struct ComparisonResult {
-    matches: bool,
+/// A region of the screen, in pixels, which isn't compared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mask {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Mask {
+    fn contains(&self, x: usize, y: usize) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompareOptions {
+    /// How far each colour channel can be from the reference image and still match.
+    pub tolerance: u8,
+    pub masks: Vec<Mask>,
+}
+
+impl CompareOptions {
+    fn is_masked(&self, x: usize, y: usize) -> bool {
+        self.masks.iter().any(|mask| mask.contains(x, y))
+    }
+}
+
+/// The smallest rectangle containing every pixel which differs, with inclusive corners.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bounds {
+    pub min_x: usize,
+    pub min_y: usize,
+    pub max_x: usize,
+    pub max_y: usize,
+}
+
+impl Bounds {
+    fn extend(bounds: Option<Bounds>, x: usize, y: usize) -> Bounds {
+        match bounds {
+            None => Bounds {
+                min_x: x,
+                min_y: y,
+                max_x: x,
+                max_y: y,
+            },
+            Some(bounds) => Bounds {
+                min_x: bounds.min_x.min(x),
+                min_y: bounds.min_y.min(y),
+                max_x: bounds.max_x.max(x),
+                max_y: bounds.max_y.max(y),
+            },
+        }
+    }
+}
+
+pub enum ComparisonResult {
+    Matches,
+    SizeMismatch {
+        width: u32,
+        height: u32,
+    },
+    PixelsDiffer {
+        count: usize,
+        bounds: Bounds,
+        /// The screen darkened, with the pixels which differ in red and the masked regions in blue.
+        diff: RgbaImage,
+    },
 }
 
 impl ComparisonResult {
     pub fn success(&self) -> bool {
-        self.matches
+        matches!(self, ComparisonResult::Matches)
     }
 }
 
-const WIDTH: usize = 240;
-const HEIGHT: usize = 160;
+/// The assertion sent by `agb::test_runner::assert_image_output`, which is the path to the
+/// reference image followed by any options, for example
+/// `image:gfx/test.png;tolerance=8;mask=0,0,32,16`.
+pub fn parse_image_assertion(assertion: &str) -> anyhow::Result<(&str, CompareOptions)> {
+    let mut parts = assertion.split(';');
+    let path = parts.next().unwrap_or_default();
+    let mut options = CompareOptions::default();
+
+    for part in parts {
+        let (key, value) = part
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Invalid image option {part}"))?;
+
+        match key {
+            "tolerance" => {
+                options.tolerance = value
+                    .parse()
+                    .with_context(|| anyhow!("Invalid tolerance {value}"))?;
+            }
+            "mask" => {
+                let numbers = value
+                    .split(',')
+                    .map(str::parse)
+                    .collect::<Result<Vec<usize>, _>>()
+                    .with_context(|| anyhow!("Invalid mask {value}"))?;
+                let [x, y, width, height] = numbers[..] else {
+                    return Err(anyhow!("Mask {value} should be x,y,width,height"));
+                };
+
+                options.masks.push(Mask {
+                    x,
+                    y,
+                    width,
+                    height,
+                });
+            }
+            _ => return Err(anyhow!("Unknown image option {key}")),
+        }
+    }
+
+    Ok((path, options))
+}
 
 fn convert_rgba_to_nearest_gba_colour(c: [u8; 4]) -> [u8; 4] {
     let mut n = c;
@@ -22,29 +133,247 @@ fn convert_rgba_to_nearest_gba_colour(c: [u8; 4]) -> [u8; 4] {
     n
 }
 
-pub fn compare_image(
-    image: impl AsRef<Path>,
-    video_buffer: &[u32],
-) -> anyhow::Result<ComparisonResult> {
-    let expected = Reader::open(image)?.decode()?;
-    let expected_buffer = expected.to_rgba8();
+pub fn load_image(image: impl AsRef<Path>) -> anyhow::Result<RgbaImage> {
+    Ok(Reader::open(image)?.decode()?.to_rgba8())
+}
 
-    let (exp_dim_x, exp_dim_y) = expected_buffer.dimensions();
+pub fn compare_buffers(
+    expected: &RgbaImage,
+    video_buffer: &[u32],
+    options: &CompareOptions,
+) -> ComparisonResult {
+    let (exp_dim_x, exp_dim_y) = expected.dimensions();
     if exp_dim_x != WIDTH as u32 || exp_dim_y != HEIGHT as u32 {
-        return Ok(ComparisonResult { matches: false });
+        return ComparisonResult::SizeMismatch {
+            width: exp_dim_x,
+            height: exp_dim_y,
+        };
     }
 
+    let mut diff = RgbaImage::new(WIDTH as u32, HEIGHT as u32);
+    let mut count = 0;
+    let mut bounds = None;
+
     for y in 0..HEIGHT {
         for x in 0..WIDTH {
-            let video_pixel = video_buffer[x + y * WIDTH];
-            let image_pixel = expected_buffer.get_pixel(x as u32, y as u32);
+            let video_pixel = video_buffer[x + y * WIDTH].to_le_bytes();
+            let image_pixel = expected.get_pixel(x as u32, y as u32);
             let image_pixel = convert_rgba_to_nearest_gba_colour(image_pixel.0);
 
-            if image_pixel[0..3] != video_pixel.to_le_bytes()[0..3] {
-                return Ok(ComparisonResult { matches: false });
-            }
+            let [r, g, b, _] = video_pixel;
+            let diff_pixel = if options.is_masked(x, y) {
+                Rgba([r / 4, g / 4, 128 + b / 2, 255])
+            } else if image_pixel[0..3]
+                .iter()
+                .zip(&video_pixel[0..3])
+                .any(|(a, b)| a.abs_diff(*b) > options.tolerance)
+            {
+                count += 1;
+                bounds = Some(Bounds::extend(bounds, x, y));
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([r / 3, g / 3, b / 3, 255])
+            };
+
+            diff.put_pixel(x as u32, y as u32, diff_pixel);
         }
     }
 
-    Ok(ComparisonResult { matches: true })
+    match bounds {
+        None => ComparisonResult::Matches,
+        Some(bounds) => ComparisonResult::PixelsDiffer {
+            count,
+            bounds,
+            diff,
+        },
+    }
+}
+
+/// Compares the screen against the reference image, or updates the reference image when blessing.
+/// When they don't match, the error describes the difference and a diff is written next to the
+/// reference image.
+pub fn check_reference_image(
+    image_path: &str,
+    video_buffer: &[u32],
+    options: &CompareOptions,
+    bless: bool,
+) -> anyhow::Result<()> {
+    let diff_path = diff_path(image_path);
+
+    let result = if Path::new(image_path).exists() {
+        let expected = load_image(image_path)
+            .with_context(|| anyhow!("Could not open image {} for comparison", image_path))?;
+        Some(compare_buffers(&expected, video_buffer, options))
+    } else {
+        None
+    };
+
+    if result.as_ref().is_some_and(ComparisonResult::success) {
+        let _ = fs::remove_file(&diff_path);
+        return Ok(());
+    }
+
+    if bless {
+        video_buffer_to_image(video_buffer)
+            .save(image_path)
+            .with_context(|| anyhow!("Could not write image {}", image_path))?;
+        let _ = fs::remove_file(&diff_path);
+        eprintln!("Updated {image_path}");
+        return Ok(());
+    }
+
+    match result {
+        None => bail!(
+            "{image_path} does not exist, run with --bless or UPDATE_SNAPSHOTS=1 to create it"
+        ),
+        Some(ComparisonResult::SizeMismatch { width, height }) => {
+            bail!("{image_path} is {width}x{height} rather than {WIDTH}x{HEIGHT}")
+        }
+        Some(ComparisonResult::PixelsDiffer {
+            count,
+            bounds,
+            diff,
+        }) => {
+            diff.save(&diff_path)
+                .with_context(|| anyhow!("Could not write diff {}", diff_path.display()))?;
+            bail!(
+                "{count} pixels differ from {image_path} between ({}, {}) and ({}, {}), see {}",
+                bounds.min_x,
+                bounds.min_y,
+                bounds.max_x,
+                bounds.max_y,
+                diff_path.display()
+            );
+        }
+        Some(ComparisonResult::Matches) => unreachable!("matching images have already returned"),
+    }
+}
+
+/// Where the diff for a failed comparison against `image` is written, which is next to it.
+pub fn diff_path(image: impl AsRef<Path>) -> PathBuf {
+    let image = image.as_ref();
+    let stem = image.file_stem().unwrap_or_default().to_string_lossy();
+    image.with_file_name(format!("{stem}.diff.png"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen(colour: [u8; 3]) -> Vec<u32> {
+        vec![u32::from_le_bytes([colour[0], colour[1], colour[2], 0]); WIDTH * HEIGHT]
+    }
+
+    fn image(colour: [u8; 3]) -> RgbaImage {
+        RgbaImage::from_pixel(
+            WIDTH as u32,
+            HEIGHT as u32,
+            Rgba([colour[0], colour[1], colour[2], 255]),
+        )
+    }
+
+    #[test]
+    fn reports_the_bounds_of_differing_pixels() {
+        let mut video_buffer = screen([0, 0, 0]);
+        video_buffer[10 + 20 * WIDTH] = 0x00ff_ffff;
+        video_buffer[30 + 5 * WIDTH] = 0x00ff_ffff;
+
+        let ComparisonResult::PixelsDiffer {
+            count,
+            bounds,
+            diff,
+        } = compare_buffers(&image([0, 0, 0]), &video_buffer, &CompareOptions::default())
+        else {
+            panic!("images should differ");
+        };
+
+        assert_eq!(count, 2);
+        assert_eq!(
+            bounds,
+            Bounds {
+                min_x: 10,
+                min_y: 5,
+                max_x: 30,
+                max_y: 20
+            }
+        );
+        assert_eq!(diff.get_pixel(10, 20), &Rgba([255, 0, 0, 255]));
+        assert_eq!(diff.get_pixel(0, 0), &Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn ignores_differences_within_the_tolerance_or_masks() {
+        let mut video_buffer = screen([8, 8, 8]);
+        video_buffer[100 + 100 * WIDTH] = 0x00ff_ffff;
+
+        let options = CompareOptions {
+            tolerance: 8,
+            masks: vec![Mask {
+                x: 96,
+                y: 96,
+                width: 8,
+                height: 8,
+            }],
+        };
+
+        assert!(compare_buffers(&image([0, 0, 0]), &video_buffer, &options).success());
+        assert!(
+            !compare_buffers(&image([0, 0, 0]), &video_buffer, &CompareOptions::default())
+                .success()
+        );
+    }
+
+    #[test]
+    fn images_of_the_wrong_size_do_not_match() {
+        let small = RgbaImage::new(10, 10);
+
+        assert!(matches!(
+            compare_buffers(&small, &screen([0, 0, 0]), &CompareOptions::default()),
+            ComparisonResult::SizeMismatch {
+                width: 10,
+                height: 10
+            }
+        ));
+    }
+
+    #[test]
+    fn parses_image_assertions() {
+        let (path, options) =
+            parse_image_assertion("gfx/test.png;tolerance=4;mask=1,2,3,4;mask=5,6,7,8").unwrap();
+
+        assert_eq!(path, "gfx/test.png");
+        assert_eq!(options.tolerance, 4);
+        assert_eq!(
+            options.masks,
+            [
+                Mask {
+                    x: 1,
+                    y: 2,
+                    width: 3,
+                    height: 4
+                },
+                Mask {
+                    x: 5,
+                    y: 6,
+                    width: 7,
+                    height: 8
+                }
+            ]
+        );
+
+        assert_eq!(
+            parse_image_assertion("gfx/test.png").unwrap().1,
+            CompareOptions::default()
+        );
+        assert!(parse_image_assertion("gfx/test.png;mask=1,2").is_err());
+        assert!(parse_image_assertion("gfx/test.png;size=1").is_err());
+    }
+
+    #[test]
+    fn diffs_are_written_next_to_the_image() {
+        assert_eq!(
+            diff_path("gfx/test_logo.png"),
+            Path::new("gfx/test_logo.diff.png")
+        );
+    }
 }
@@ -1,18 +1,30 @@
 use std::{
     collections::VecDeque,
+    env,
     error::Error,
-    fs::File,
-    io::Read,
+    fs,
     path::{Path, PathBuf},
     sync::Mutex,
 };
 
-use anyhow::{anyhow, Context};
+use agb_replay::InputReplay;
+use anyhow::{anyhow, bail, Context};
+use audio_compare::{compare_audio, load_wav, parse_audio_assertion, write_wav, AudioComparison};
 use clap::Parser;
-use image_compare::compare_image;
+use image_compare::{check_reference_image, parse_image_assertion};
 use mgba::{LogLevel, Logger, MCore, MemoryBacked, VFile};
+use mgba_harness::to_gba_file;
+use report::{Outcome, Report, TestResult};
+use scenario::{Check, Scenario};
+use symbols::Symbols;
+use timeout::Timeout;
 
+mod audio_compare;
 mod image_compare;
+mod report;
+mod scenario;
+mod symbols;
+mod timeout;
 
 static LOGGER: Logger = Logger::new(my_logger);
 
@@ -28,10 +40,60 @@ fn my_logger(category: &str, level: LogLevel, s: String) {
 #[derive(Parser)]
 struct CliArguments {
     rom: PathBuf,
+
+    /// Only run the tests whose names contain this string.
+    filter: Option<String>,
+
+    /// A recording made with `agb::input::InputRecording` which is used to press the buttons, one frame at a time.
+    #[arg(long)]
+    replay: Option<PathBuf>,
+
+    /// Fail any test which takes longer than this, as a number of frames (`600f`), cycles (`5000000c`) or seconds (`10s`).
+    #[arg(long)]
+    test_timeout: Option<Timeout>,
+
+    /// Fail if all the tests together take longer than this, in the same units as `--test-timeout`.
+    #[arg(long)]
+    timeout: Option<Timeout>,
+
+    /// Write the screen or audio to any reference images or WAVs which are missing or don't match, rather than failing.
+    /// Setting the `UPDATE_SNAPSHOTS` environment variable to `1` does the same.
+    #[arg(long)]
+    bless: bool,
+
+    /// Play the whole game following a TOML scenario of button presses, screenshots, memory checks
+    /// and log messages, rather than running tests.
+    #[arg(long, conflicts_with_all = ["filter", "replay"])]
+    scenario: Option<PathBuf>,
+
+    /// Write a JUnit XML report of the results to this file.
+    #[arg(long)]
+    junit: Option<PathBuf>,
+
+    /// Write a JSON report of the results, including the cycles each test took, to this file.
+    #[arg(long)]
+    json: Option<PathBuf>,
+}
+
+struct RunningTest {
+    name: String,
+    start_cycle: u64,
+    soft_failure: Option<String>,
 }
 
 struct TestRunner {
     mgba: MCore,
+    replay: Option<InputReplay>,
+    last_frame: u32,
+    filter: Option<String>,
+    test_timeout: Option<Timeout>,
+    timeout: Option<Timeout>,
+    bless: bool,
+    any_soft_failures: bool,
+    audio: Option<Vec<i16>>,
+    last_audio_frame: u32,
+    current_test: Option<RunningTest>,
+    report: Report,
 }
 
 enum Timer {
@@ -39,28 +101,339 @@ enum Timer {
     Total(u64),
 }
 
+fn format_cycles(cycles: u64) -> String {
+    format!(
+        "{} c ≈ {} s",
+        cycles,
+        ((cycles as f64 / (16.78 * 1_000_000.0)) * 100.0).round() / 100.0
+    )
+}
+
 impl TestRunner {
-    fn new<V: VFile>(rom: V) -> Result<Self, Box<dyn Error>> {
+    fn new<V: VFile>(rom: V, replay: Option<InputReplay>, report: Report) -> anyhow::Result<Self> {
         let mut mgba = MCore::new().ok_or(anyhow!("cannot create core"))?;
 
         mgba::set_global_default_logger(&LOGGER);
 
         mgba.load_rom(rom);
+        let last_frame = mgba.frame_counter();
+
+        Ok(Self {
+            mgba,
+            replay,
+            last_frame,
+            filter: None,
+            test_timeout: None,
+            timeout: None,
+            bless: false,
+            any_soft_failures: false,
+            audio: None,
+            last_audio_frame: 0,
+            current_test: None,
+            report,
+        })
+    }
+
+    fn update_keys_from_replay(&mut self) {
+        let Some(replay) = &mut self.replay else {
+            return;
+        };
+
+        let frame = self.mgba.frame_counter();
+        if frame == self.last_frame {
+            return;
+        }
+
+        self.last_frame = frame;
+        self.mgba.set_keys(replay.next().map_or(0, u32::from));
+    }
+
+    // The test runner in agb prints where it wants the filter to be written, and reads it once the
+    // print has finished.
+    fn write_filter(&mut self, location: &str) -> anyhow::Result<()> {
+        let Some(filter) = &self.filter else {
+            return Ok(());
+        };
+
+        let (address, capacity) = location
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Invalid test filter location {location}"))?;
+        let address = u32::from_str_radix(address.trim_start_matches("0x"), 16)
+            .with_context(|| anyhow!("Invalid test filter address {address}"))?;
+        let capacity: usize = capacity
+            .parse()
+            .with_context(|| anyhow!("Invalid test filter capacity {capacity}"))?;
+
+        if filter.len() > capacity {
+            bail!("Test filter must be at most {capacity} bytes long");
+        }
+
+        self.mgba
+            .write_memory(address, &(filter.len() as u32).to_le_bytes());
+        self.mgba.write_memory(address + 4, filter.as_bytes());
+
+        Ok(())
+    }
 
-        Ok(Self { mgba })
+    fn check_image(&mut self, assertion: &str) -> anyhow::Result<()> {
+        let (image_path, options) = parse_image_assertion(assertion)?;
+        check_reference_image(image_path, self.mgba.video_buffer(), &options, self.bless)
     }
 
-    fn run(mut self) -> Result<(), Box<dyn Error>> {
+    // Any audio produced before the test starts recording isn't part of the recording
+    fn start_audio_capture(&mut self) {
+        let mut scratch = [0; 0x1000];
+        while self.mgba.read_audio(&mut scratch) > 0 {}
+
+        self.audio = Some(Vec::new());
+        self.last_audio_frame = self.mgba.frame_counter();
+    }
+
+    // The emulator only buffers a limited amount of audio, so read it every frame while recording
+    fn capture_audio(&mut self) {
+        if self.audio.is_none() {
+            return;
+        }
+
+        let frame = self.mgba.frame_counter();
+        if frame != self.last_audio_frame {
+            self.last_audio_frame = frame;
+            self.read_captured_audio();
+        }
+    }
+
+    fn read_captured_audio(&mut self) {
+        let Some(audio) = &mut self.audio else {
+            return;
+        };
+
+        let mut buffer = [0; 0x1000];
+        loop {
+            let produced = self.mgba.read_audio(&mut buffer);
+            if produced == 0 {
+                break;
+            }
+
+            audio.extend_from_slice(&buffer[..produced * 2]);
+        }
+    }
+
+    // Compares the recorded audio against the reference WAV, or updates the WAV when blessing
+    fn check_audio(&mut self, assertion: &str) -> anyhow::Result<()> {
+        self.read_captured_audio();
+        let audio = self
+            .audio
+            .take()
+            .ok_or_else(|| anyhow!("Audio wasn't being recorded"))?;
+
+        let (audio_path, options) = parse_audio_assertion(assertion)?;
+
+        let result = if Path::new(audio_path).exists() {
+            let expected = load_wav(audio_path)
+                .with_context(|| anyhow!("Could not open {} for comparison", audio_path))?;
+            Some(compare_audio(&expected, &audio, &options))
+        } else {
+            None
+        };
+
+        if result.as_ref().is_some_and(AudioComparison::success) {
+            return Ok(());
+        }
+
+        if self.bless {
+            write_wav(audio_path, &audio)
+                .with_context(|| anyhow!("Could not write {}", audio_path))?;
+            eprintln!("Updated {audio_path}");
+            return Ok(());
+        }
+
+        match result {
+            None => bail!(
+                "{audio_path} does not exist, run with --bless or UPDATE_SNAPSHOTS=1 to create it"
+            ),
+            Some(AudioComparison::LengthMismatch { expected, actual }) => bail!(
+                "{audio_path} is {expected} samples long but {actual} samples were recorded"
+            ),
+            Some(AudioComparison::SamplesDiffer {
+                count,
+                first,
+                max_difference,
+            }) => bail!(
+                "{count} samples differ from {audio_path} by up to {max_difference}, starting at sample {first}"
+            ),
+            Some(AudioComparison::SpectrumDiffers { at, difference }) => bail!(
+                "the frequencies differ from {audio_path} by {:.1}% at {at:.3}s",
+                difference * 100.0
+            ),
+            Some(AudioComparison::Matches) => unreachable!("matching audio has already returned"),
+        }
+    }
+
+    fn run_scenario(&mut self, scenario: &Scenario, symbols: &Symbols) -> anyhow::Result<()> {
+        let start_cycle = self.mgba.current_cycle();
+        let mut logs = Vec::new();
+        let mut any_failures = false;
+
+        for frame in 0..scenario.length() {
+            self.mgba.set_keys(scenario.keys_at(frame));
+            self.mgba.frame();
+
+            loop {
+                let Some((category, level, message)) = LOGGER_BUFFER.lock().unwrap().pop_front()
+                else {
+                    break;
+                };
+
+                match (category.as_ref(), level) {
+                    (_, LogLevel::Fatal) => {
+                        self.report.tests.push(TestResult {
+                            name: format!("frame {frame}: panic"),
+                            outcome: Outcome::Failed(message.clone()),
+                            cycles: self.mgba.current_cycle() - start_cycle,
+                        });
+                        bail!("Failed with fatal message: {}", message);
+                    }
+                    ("GBA Debug", _) => {
+                        eprintln!("{}", message);
+                        logs.push(message);
+                    }
+                    _ => {}
+                }
+            }
+
+            for check in scenario.checks_at(frame) {
+                let name = check.name(frame);
+                let outcome = match self.run_check(&check, symbols, &logs) {
+                    Ok(()) => {
+                        eprintln!("{name}... [ok]");
+                        Outcome::Passed
+                    }
+                    Err(e) => {
+                        eprintln!("{name}... [fail: {e:#}]");
+                        any_failures = true;
+                        Outcome::Failed(format!("{e:#}"))
+                    }
+                };
+
+                self.report.tests.push(TestResult {
+                    name,
+                    outcome,
+                    cycles: self.mgba.current_cycle() - start_cycle,
+                });
+            }
+        }
+
+        if any_failures {
+            bail!("Scenario failed");
+        }
+
+        eprintln!("Scenario finished successfully");
+        Ok(())
+    }
+
+    fn run_check(
+        &mut self,
+        check: &Check,
+        symbols: &Symbols,
+        logs: &[String],
+    ) -> anyhow::Result<()> {
+        match check {
+            Check::Screenshot(screenshot) => check_reference_image(
+                &screenshot.image,
+                self.mgba.video_buffer(),
+                &screenshot.options(),
+                self.bless,
+            ),
+            Check::Memory(memory) => {
+                let address = match (&memory.symbol, memory.address) {
+                    (Some(symbol), _) => symbols.address(symbol)?,
+                    (None, address) => address.unwrap_or_default(),
+                };
+
+                let bytes = self.mgba.read_memory(address + memory.offset, memory.size);
+                memory.check(&bytes)
+            }
+            Check::Log(log) => {
+                if logs.iter().any(|message| message.contains(&log.contains)) {
+                    Ok(())
+                } else {
+                    bail!("Nothing containing {:?} has been logged", log.contains)
+                }
+            }
+        }
+    }
+
+    fn soft_fail(&mut self, failure: String) {
+        eprintln!("{failure}");
+        self.any_soft_failures = true;
+        if let Some(test) = &mut self.current_test {
+            test.soft_failure = Some(failure);
+        }
+    }
+
+    fn finish_current_test(&mut self, outcome: Outcome, cycles: Option<u64>) {
+        let Some(test) = self.current_test.take() else {
+            return;
+        };
+
+        let cycles = cycles.unwrap_or_else(|| self.mgba.current_cycle() - test.start_cycle);
+        let outcome = match (outcome, test.soft_failure) {
+            (Outcome::Passed, Some(soft_failure)) => Outcome::Failed(soft_failure),
+            (outcome, _) => outcome,
+        };
+
+        self.report.tests.push(TestResult {
+            name: test.name,
+            outcome,
+            cycles,
+        });
+    }
+
+    fn check_timeouts(&mut self, run_start_cycle: u64) -> anyhow::Result<()> {
+        let current_cycle = self.mgba.current_cycle();
+
+        if let (Some(test), Some(timeout)) = (&self.current_test, self.test_timeout) {
+            if timeout.has_expired(test.start_cycle, current_cycle) {
+                eprintln!("[timed out: {}]", format_cycles(timeout.cycles()));
+                let name = test.name.clone();
+                self.finish_current_test(Outcome::TimedOut, None);
+                bail!("Test {name} took longer than {timeout}");
+            }
+        }
+
+        if let Some(timeout) = self.timeout {
+            if timeout.has_expired(run_start_cycle, current_cycle) {
+                if self.current_test.is_some() {
+                    eprintln!("[timed out]");
+                    self.finish_current_test(Outcome::TimedOut, None);
+                }
+                bail!("Tests took longer than {timeout}");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run(&mut self) -> anyhow::Result<()> {
         let mut timer: Timer = Timer::Total(0);
+        let run_start_cycle = self.mgba.current_cycle();
 
-        let mut mark_tests_as_soft_failed = false;
-        let mut mark_this_test_as_soft_failed = false;
         loop {
             self.mgba.step();
-            while let Some((category, level, message)) = LOGGER_BUFFER.lock().unwrap().pop_front() {
+            self.update_keys_from_replay();
+            self.capture_audio();
+            self.check_timeouts(run_start_cycle)?;
+
+            loop {
+                let Some((category, level, message)) = LOGGER_BUFFER.lock().unwrap().pop_front()
+                else {
+                    break;
+                };
+
                 match (category.as_ref(), level, message.as_ref()) {
                     (_, LogLevel::Fatal, fatal_message) => {
-                        return Err(anyhow!("Failed with fatal message: {}", fatal_message).into());
+                        self.finish_current_test(Outcome::Failed(fatal_message.to_string()), None);
+                        bail!("Failed with fatal message: {}", fatal_message);
                     }
                     ("GBA I/O", _, "Stub I/O register write: FFF800") => match timer {
                         Timer::Start(time) => {
@@ -72,42 +445,42 @@ impl TestRunner {
                         }
                     },
                     ("GBA Debug", _, debug_message) => {
-                        if let Some(image_path) = debug_message.strip_prefix("image:") {
-                            match compare_image(image_path, self.mgba.video_buffer()).with_context(
-                                || anyhow!("Could not open image {} for comparison", image_path),
-                            ) {
-                                Ok(compare) => {
-                                    if !compare.success() {
-                                        eprintln!("Image and video buffer do not match");
-                                        mark_tests_as_soft_failed = true;
-                                        mark_this_test_as_soft_failed = true;
-                                    }
-                                }
-                                Err(e) => eprintln!("{}", e),
+                        if let Some(assertion) = debug_message.strip_prefix("image:") {
+                            if let Err(e) = self.check_image(assertion) {
+                                self.soft_fail(format!(
+                                    "Image and video buffer do not match: {e:#}"
+                                ));
                             }
-                        } else if debug_message.ends_with("...") {
+                        } else if debug_message == "audio-start" {
+                            self.start_audio_capture();
+                        } else if let Some(assertion) = debug_message.strip_prefix("audio:") {
+                            if let Err(e) = self.check_audio(assertion) {
+                                self.soft_fail(format!("Audio output does not match: {e:#}"));
+                            }
+                        } else if let Some(location) = debug_message.strip_prefix("test-filter:") {
+                            self.write_filter(location)?;
+                        } else if let Some(name) = debug_message.strip_suffix("...") {
                             eprint!("{}", debug_message);
+                            self.current_test = Some(RunningTest {
+                                name: name.to_string(),
+                                start_cycle: self.mgba.current_cycle(),
+                                soft_failure: None,
+                            });
                         } else if debug_message == "[ok]" {
                             let cycles = match timer {
                                 Timer::Start(_) => panic!("test completed with invalid timing"),
                                 Timer::Total(c) => c,
                             };
-                            if mark_this_test_as_soft_failed {
-                                mark_this_test_as_soft_failed = false;
-                                eprintln!(
-                                    "[fail: {} c ≈ {} s]",
-                                    cycles,
-                                    ((cycles as f64 / (16.78 * 1_000_000.0)) * 100.0).round()
-                                        / 100.0
-                                );
+                            let soft_failed = self
+                                .current_test
+                                .as_ref()
+                                .is_some_and(|test| test.soft_failure.is_some());
+                            if soft_failed {
+                                eprintln!("[fail: {}]", format_cycles(cycles));
                             } else {
-                                eprintln!(
-                                    "[ok: {} c ≈ {} s]",
-                                    cycles,
-                                    ((cycles as f64 / (16.78 * 1_000_000.0)) * 100.0).round()
-                                        / 100.0
-                                );
+                                eprintln!("[ok: {}]", format_cycles(cycles));
                             }
+                            self.finish_current_test(Outcome::Passed, Some(cycles));
                         } else {
                             eprintln!("{}", debug_message);
                         }
@@ -116,9 +489,9 @@ impl TestRunner {
                 }
 
                 if message == "Tests finished successfully" {
-                    if mark_tests_as_soft_failed {
+                    if self.any_soft_failures {
                         eprintln!("Tests failed");
-                        return Err(anyhow!("Tests failed").into());
+                        bail!("Tests failed");
                     } else {
                         eprintln!("{}", message);
                         return Ok(());
@@ -132,34 +505,51 @@ impl TestRunner {
 fn main() -> Result<(), Box<dyn Error>> {
     let args = CliArguments::parse();
 
-    let rom = load_rom(args.rom)?;
-    let rom = MemoryBacked::new(rom);
+    let suite = args
+        .rom
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
 
-    TestRunner::new(rom)?.run()?;
+    let input_file = fs::read(&args.rom)?;
+    let rom = MemoryBacked::new(to_gba_file(&input_file));
 
-    Ok(())
-}
+    let replay = args
+        .replay
+        .map(|path| {
+            fs::read(&path)
+                .map(InputReplay::new)
+                .with_context(|| anyhow!("Could not load replay {}", path.display()))
+        })
+        .transpose()?;
 
-fn load_rom<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<u8>> {
-    let mut input_file = File::open(path)?;
-    let mut input_file_buffer = Vec::new();
+    let mut runner = TestRunner::new(rom, replay, Report::new(suite))?;
+    runner.filter = args.filter;
+    runner.test_timeout = args.test_timeout;
+    runner.timeout = args.timeout;
+    runner.bless = args.bless || env::var("UPDATE_SNAPSHOTS").is_ok_and(|update| update == "1");
 
-    input_file.read_to_end(&mut input_file_buffer)?;
+    let result = match &args.scenario {
+        Some(path) => {
+            let scenario = Scenario::load(path)
+                .with_context(|| anyhow!("Could not load scenario {}", path.display()))?;
+            // symbols are only needed for memory checks, and a .gba file doesn't have any
+            let symbols = Symbols::from_elf(&input_file).unwrap_or_default();
 
-    let mut elf_buffer = Vec::new();
+            runner.run_scenario(&scenario, &symbols)
+        }
+        None => runner.run(),
+    };
 
-    let inculde_debug_info = false;
-    if agb_gbafix::write_gba_file(
-        &input_file_buffer,
-        Default::default(),
-        agb_gbafix::PaddingBehaviour::DoNotPad,
-        inculde_debug_info,
-        &mut elf_buffer,
-    )
-    .is_ok()
-    {
-        Ok(elf_buffer)
-    } else {
-        Ok(input_file_buffer)
+    // write the reports even if the tests failed, since that's when they're most useful
+    if let Some(junit) = &args.junit {
+        runner.report.write_junit(junit)?;
+    }
+    if let Some(json) = &args.json {
+        runner.report.write_json(json)?;
     }
+
+    result?;
+
+    Ok(())
 }
@@ -0,0 +1,252 @@
+use std::{fmt::Write as _, fs, path::Path};
+
+use anyhow::{anyhow, Context};
+
+use crate::timeout::CYCLES_PER_SECOND;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    Failed(String),
+    TimedOut,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestResult {
+    pub name: String,
+    pub outcome: Outcome,
+    pub cycles: u64,
+}
+
+impl TestResult {
+    fn seconds(&self) -> f64 {
+        self.cycles as f64 / CYCLES_PER_SECOND as f64
+    }
+
+    // JUnit splits names into the class and the test, which for us is the module and the function
+    fn class_and_test_name(&self) -> (&str, &str) {
+        self.name.rsplit_once("::").unwrap_or(("", &self.name))
+    }
+}
+
+/// The results of every test which was run, for writing out as JUnit XML or JSON so that CI can
+/// show the failures and track how many cycles each test takes.
+pub struct Report {
+    pub suite: String,
+    pub tests: Vec<TestResult>,
+}
+
+impl Report {
+    pub fn new(suite: impl Into<String>) -> Self {
+        Self {
+            suite: suite.into(),
+            tests: Vec::new(),
+        }
+    }
+
+    fn count(&self, f: impl Fn(&Outcome) -> bool) -> usize {
+        self.tests.iter().filter(|test| f(&test.outcome)).count()
+    }
+
+    fn total_cycles(&self) -> u64 {
+        self.tests.iter().map(|test| test.cycles).sum()
+    }
+
+    pub fn to_junit(&self) -> String {
+        let failures = self.count(|outcome| *outcome != Outcome::Passed);
+        let seconds = self.total_cycles() as f64 / CYCLES_PER_SECOND as f64;
+
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            xml,
+            "<testsuites tests=\"{}\" failures=\"{failures}\" time=\"{seconds:.6}\">",
+            self.tests.len()
+        );
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{failures}\" time=\"{seconds:.6}\">",
+            escape_xml(&self.suite),
+            self.tests.len()
+        );
+
+        for test in &self.tests {
+            let (class_name, test_name) = test.class_and_test_name();
+            let _ = writeln!(
+                xml,
+                "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.6}\">",
+                escape_xml(class_name),
+                escape_xml(test_name),
+                test.seconds()
+            );
+            let _ = writeln!(
+                xml,
+                "      <properties><property name=\"cycles\" value=\"{}\"/></properties>",
+                test.cycles
+            );
+
+            match &test.outcome {
+                Outcome::Passed => {}
+                Outcome::Failed(message) => {
+                    let _ = writeln!(xml, "      <failure message=\"{}\"/>", escape_xml(message));
+                }
+                Outcome::TimedOut => {
+                    let _ = writeln!(
+                        xml,
+                        "      <failure message=\"timed out after {} cycles\"/>",
+                        test.cycles
+                    );
+                }
+            }
+
+            xml.push_str("    </testcase>\n");
+        }
+
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        let _ = write!(
+            json,
+            "{{\"suite\":\"{}\",\"passed\":{},\"failed\":{},\"timed_out\":{},\"cycles\":{},\"tests\":[",
+            escape_json(&self.suite),
+            self.count(|outcome| *outcome == Outcome::Passed),
+            self.count(|outcome| matches!(outcome, Outcome::Failed(_))),
+            self.count(|outcome| *outcome == Outcome::TimedOut),
+            self.total_cycles()
+        );
+
+        for (i, test) in self.tests.iter().enumerate() {
+            if i != 0 {
+                json.push(',');
+            }
+
+            let (status, message) = match &test.outcome {
+                Outcome::Passed => ("passed", None),
+                Outcome::Failed(message) => ("failed", Some(message)),
+                Outcome::TimedOut => ("timed_out", None),
+            };
+
+            let _ = write!(
+                json,
+                "{{\"name\":\"{}\",\"status\":\"{status}\",\"cycles\":{},\"seconds\":{:.6}",
+                escape_json(&test.name),
+                test.cycles,
+                test.seconds()
+            );
+            if let Some(message) = message {
+                let _ = write!(json, ",\"message\":\"{}\"", escape_json(message));
+            }
+            json.push('}');
+        }
+
+        json.push_str("]}\n");
+        json
+    }
+
+    pub fn write_junit(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, self.to_junit())
+            .with_context(|| anyhow!("Could not write JUnit report {}", path.display()))
+    }
+
+    pub fn write_json(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, self.to_json())
+            .with_context(|| anyhow!("Could not write JSON report {}", path.display()))
+    }
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        let mut report = Report::new("agb");
+        report.tests = vec![
+            TestResult {
+                name: "agb::dma::tests::copy".to_string(),
+                outcome: Outcome::Passed,
+                cycles: CYCLES_PER_SECOND / 2,
+            },
+            TestResult {
+                name: "agb::sound::tests::play".to_string(),
+                outcome: Outcome::Failed("assertion `left == right` \"failed\" <here>".to_string()),
+                cycles: 10,
+            },
+            TestResult {
+                name: "agb::executor::tests::spin".to_string(),
+                outcome: Outcome::TimedOut,
+                cycles: 300,
+            },
+        ];
+        report
+    }
+
+    #[test]
+    fn junit_report_includes_failures_and_cycles() {
+        let xml = report().to_junit();
+
+        assert!(xml.contains("<testsuites tests=\"3\" failures=\"2\""));
+        assert!(xml
+            .contains("<testcase classname=\"agb::dma::tests\" name=\"copy\" time=\"0.500000\">"));
+        assert!(xml.contains("<property name=\"cycles\" value=\"8388608\"/>"));
+        assert!(xml.contains(
+            "<failure message=\"assertion `left == right` &quot;failed&quot; &lt;here&gt;\"/>"
+        ));
+        assert!(xml.contains("<failure message=\"timed out after 300 cycles\"/>"));
+    }
+
+    #[test]
+    fn json_report_includes_failures_and_cycles() {
+        let json = report().to_json();
+
+        assert!(json.starts_with(
+            "{\"suite\":\"agb\",\"passed\":1,\"failed\":1,\"timed_out\":1,\"cycles\":8388918,"
+        ));
+        assert!(json.contains(
+            "{\"name\":\"agb::dma::tests::copy\",\"status\":\"passed\",\"cycles\":8388608,\"seconds\":0.500000}"
+        ));
+        assert!(json.contains("\"message\":\"assertion `left == right` \\\"failed\\\" <here>\"}"));
+        assert!(json.contains("\"status\":\"timed_out\",\"cycles\":300"));
+    }
+
+    #[test]
+    fn escapes_control_characters() {
+        assert_eq!(escape_json("a\u{1}b\nc"), "a\\u0001b\\nc");
+        assert_eq!(escape_xml("a\u{1}b&c"), "ab&amp;c");
+    }
+}
@@ -0,0 +1,308 @@
+use std::{fs, path::Path};
+
+use anyhow::bail;
+use mgba_harness::input::{self, Input};
+use serde::Deserialize;
+
+use crate::image_compare::{CompareOptions, Mask};
+
+/// A script for playing a whole game, read from a TOML file. Frames are counted from when the ROM
+/// starts, and each check happens at the end of its frame.
+///
+/// ```toml
+/// # defaults to just after the last frame mentioned
+/// frames = 400
+///
+/// [[input]]
+/// frame = 60
+/// buttons = ["Start"]
+/// # how many frames to hold the buttons for, which defaults to 1
+/// hold = 2
+///
+/// [[screenshot]]
+/// frame = 120
+/// image = "scenarios/title.png"
+/// tolerance = 8
+/// masks = [[0, 0, 32, 8]]
+///
+/// [[memory]]
+/// frame = 300
+/// symbol = "hyperspace_roll::SCORE"
+/// size = 4
+/// equals = 0
+///
+/// [[log]]
+/// frame = 300
+/// contains = "Level 1"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    frames: Option<u32>,
+    #[serde(default)]
+    input: Vec<Input>,
+    #[serde(default)]
+    screenshot: Vec<Screenshot>,
+    #[serde(default)]
+    memory: Vec<MemoryCheck>,
+    #[serde(default)]
+    log: Vec<LogCheck>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Screenshot {
+    frame: u32,
+    pub image: String,
+    #[serde(default)]
+    tolerance: u8,
+    #[serde(default)]
+    masks: Vec<[usize; 4]>,
+}
+
+impl Screenshot {
+    pub fn options(&self) -> CompareOptions {
+        CompareOptions {
+            tolerance: self.tolerance,
+            masks: self
+                .masks
+                .iter()
+                .map(|&[x, y, width, height]| Mask {
+                    x,
+                    y,
+                    width,
+                    height,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MemoryCheck {
+    frame: u32,
+    pub symbol: Option<String>,
+    pub address: Option<u32>,
+    #[serde(default)]
+    pub offset: u32,
+    #[serde(default = "word")]
+    pub size: usize,
+    pub equals: i64,
+}
+
+fn word() -> usize {
+    4
+}
+
+impl MemoryCheck {
+    fn location(&self) -> String {
+        let base = match (&self.symbol, self.address) {
+            (Some(symbol), _) => symbol.clone(),
+            (None, Some(address)) => format!("{address:#010x}"),
+            (None, None) => "nowhere".to_string(),
+        };
+
+        if self.offset == 0 {
+            base
+        } else {
+            format!("{base}+{:#x}", self.offset)
+        }
+    }
+
+    /// Checks the little endian value read from memory, where `equals` can be negative for signed
+    /// values.
+    pub fn check(&self, bytes: &[u8]) -> anyhow::Result<()> {
+        let mut value = [0; 8];
+        value[..bytes.len()].copy_from_slice(bytes);
+        let value = u64::from_le_bytes(value);
+
+        let mask = u64::MAX >> (64 - 8 * bytes.len());
+        let expected = self.equals as u64 & mask;
+
+        if value != expected {
+            bail!(
+                "{} is {value} ({value:#x}) rather than {}",
+                self.location(),
+                self.equals
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LogCheck {
+    frame: u32,
+    pub contains: String,
+}
+
+pub enum Check<'a> {
+    Screenshot(&'a Screenshot),
+    Memory(&'a MemoryCheck),
+    Log(&'a LogCheck),
+}
+
+impl Check<'_> {
+    pub fn name(&self, frame: u32) -> String {
+        match self {
+            Check::Screenshot(screenshot) => {
+                format!("frame {frame}: screenshot {}", screenshot.image)
+            }
+            Check::Memory(memory) => format!("frame {frame}: memory {}", memory.location()),
+            Check::Log(log) => format!("frame {frame}: log {:?}", log.contains),
+        }
+    }
+}
+
+impl Scenario {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(scenario: &str) -> anyhow::Result<Self> {
+        let scenario: Scenario = toml::from_str(scenario)?;
+
+        for memory in &scenario.memory {
+            if memory.symbol.is_some() == memory.address.is_some() {
+                bail!("Memory checks need exactly one of symbol or address");
+            }
+
+            if ![1, 2, 4].contains(&memory.size) {
+                bail!("Memory checks must have a size of 1, 2 or 4 bytes");
+            }
+        }
+
+        Ok(scenario)
+    }
+
+    /// How many frames to run the game for.
+    pub fn length(&self) -> u32 {
+        self.frames.unwrap_or_else(|| {
+            let inputs = self.input.iter().map(Input::end);
+            let checks = self
+                .screenshot
+                .iter()
+                .map(|screenshot| screenshot.frame)
+                .chain(self.memory.iter().map(|memory| memory.frame))
+                .chain(self.log.iter().map(|log| log.frame))
+                .map(|frame| frame + 1);
+
+            inputs.chain(checks).max().unwrap_or(0)
+        })
+    }
+
+    /// The buttons which are held down during `frame`, in the format `MCore::set_keys` expects.
+    pub fn keys_at(&self, frame: u32) -> u32 {
+        input::keys_at(&self.input, frame)
+    }
+
+    /// The checks which happen at the end of `frame`.
+    pub fn checks_at(&self, frame: u32) -> impl Iterator<Item = Check<'_>> {
+        let screenshots = self
+            .screenshot
+            .iter()
+            .filter(move |screenshot| screenshot.frame == frame)
+            .map(Check::Screenshot);
+        let memory = self
+            .memory
+            .iter()
+            .filter(move |memory| memory.frame == frame)
+            .map(Check::Memory);
+        let logs = self
+            .log
+            .iter()
+            .filter(move |log| log.frame == frame)
+            .map(Check::Log);
+
+        screenshots.chain(memory).chain(logs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENARIO: &str = r#"
+        [[input]]
+        frame = 10
+        buttons = ["Start", "A"]
+        hold = 3
+
+        [[input]]
+        frame = 11
+        buttons = ["Left"]
+
+        [[screenshot]]
+        frame = 20
+        image = "title.png"
+        masks = [[0, 0, 8, 8]]
+
+        [[memory]]
+        frame = 20
+        symbol = "game::SCORE"
+        size = 2
+        equals = -1
+
+        [[log]]
+        frame = 30
+        contains = "Level 1"
+    "#;
+
+    #[test]
+    fn holds_buttons_for_the_given_frames() {
+        let scenario = Scenario::parse(SCENARIO).unwrap();
+
+        assert_eq!(scenario.keys_at(9), 0);
+        assert_eq!(scenario.keys_at(10), 0b1001);
+        assert_eq!(scenario.keys_at(11), 0b10_1001);
+        assert_eq!(scenario.keys_at(12), 0b1001);
+        assert_eq!(scenario.keys_at(13), 0);
+    }
+
+    #[test]
+    fn runs_until_after_the_last_check() {
+        let scenario = Scenario::parse(SCENARIO).unwrap();
+        assert_eq!(scenario.length(), 31);
+
+        let scenario = Scenario::parse("frames = 100\n").unwrap();
+        assert_eq!(scenario.length(), 100);
+    }
+
+    #[test]
+    fn finds_the_checks_for_each_frame() {
+        let scenario = Scenario::parse(SCENARIO).unwrap();
+
+        let names: Vec<_> = scenario.checks_at(20).map(|check| check.name(20)).collect();
+        assert_eq!(
+            names,
+            [
+                "frame 20: screenshot title.png",
+                "frame 20: memory game::SCORE"
+            ]
+        );
+        assert_eq!(scenario.checks_at(21).count(), 0);
+    }
+
+    #[test]
+    fn compares_memory_with_signed_values() {
+        let scenario = Scenario::parse(SCENARIO).unwrap();
+        let memory = &scenario.memory[0];
+
+        assert!(memory.check(&[0xff, 0xff]).is_ok());
+        assert!(memory.check(&[0xfe, 0xff]).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_scenarios() {
+        assert!(Scenario::parse("[[input]]\nframe = 1\nbuttons = [\"Z\"]\n").is_err());
+        assert!(Scenario::parse("[[memory]]\nframe = 1\nequals = 1\n").is_err());
+        assert!(Scenario::parse(
+            "[[memory]]\nframe = 1\naddress = 0x3000000\nsize = 3\nequals = 1\n"
+        )
+        .is_err());
+        assert!(Scenario::parse("speed = 2\n").is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+
+/// The addresses of the symbols in the ELF file for the ROM, so that scenarios can check the
+/// values of statics by name.
+#[derive(Default)]
+pub struct Symbols {
+    addresses: HashMap<String, u32>,
+}
+
+impl Symbols {
+    pub fn from_elf(data: &[u8]) -> anyhow::Result<Self> {
+        let elf = elf::ElfBytes::<elf::endian::AnyEndian>::minimal_parse(data)?;
+        let mut symbols = Self::default();
+
+        let Some((symbol_table, string_table)) = elf.symbol_table()? else {
+            return Ok(symbols);
+        };
+
+        for symbol in symbol_table {
+            if symbol.st_name == 0 || symbol.is_undefined() {
+                continue;
+            }
+
+            let name = string_table.get(symbol.st_name as usize)?;
+            symbols.insert(name, symbol.st_value as u32);
+        }
+
+        Ok(symbols)
+    }
+
+    // Rust symbols can be found by their path without the hash, as well as their mangled name
+    fn insert(&mut self, name: &str, address: u32) {
+        let demangled = format!("{:#}", rustc_demangle::demangle(name));
+        if demangled != name {
+            self.addresses.insert(demangled, address);
+        }
+
+        self.addresses.insert(name.to_string(), address);
+    }
+
+    pub fn address(&self, name: &str) -> anyhow::Result<u32> {
+        self.addresses.get(name).copied().ok_or_else(|| {
+            if self.addresses.is_empty() {
+                anyhow!("Cannot find {name} because the ROM has no symbols, run the ELF file rather than the GBA file")
+            } else {
+                anyhow!("Cannot find the symbol {name}")
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_rust_symbols_by_their_path() {
+        let mut symbols = Symbols::default();
+        symbols.insert(
+            "_ZN15hyperspace_roll5SCORE17h0123456789abcdefE",
+            0x0300_0010,
+        );
+        symbols.insert("agb_rs__program_counter", 0x0300_0020);
+
+        assert_eq!(
+            symbols.address("hyperspace_roll::SCORE").unwrap(),
+            0x0300_0010
+        );
+        assert_eq!(
+            symbols
+                .address("_ZN15hyperspace_roll5SCORE17h0123456789abcdefE")
+                .unwrap(),
+            0x0300_0010
+        );
+        assert_eq!(
+            symbols.address("agb_rs__program_counter").unwrap(),
+            0x0300_0020
+        );
+        assert!(symbols.address("hyperspace_roll::LIVES").is_err());
+    }
+}
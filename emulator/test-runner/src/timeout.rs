@@ -0,0 +1,93 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, Context};
+
+pub const CYCLES_PER_SECOND: u64 = 1 << 24;
+pub const CYCLES_PER_FRAME: u64 = 280_896;
+
+/// How long a test, or the whole run, is allowed to take. Written on the command line as a number
+/// followed by `f` for frames, `c` for cycles or `s` for seconds, where a bare number is frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timeout {
+    cycles: u64,
+}
+
+impl Timeout {
+    pub fn cycles(self) -> u64 {
+        self.cycles
+    }
+
+    pub fn has_expired(self, start_cycle: u64, current_cycle: u64) -> bool {
+        current_cycle.saturating_sub(start_cycle) > self.cycles
+    }
+}
+
+impl FromStr for Timeout {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (amount, multiplier) = if let Some(frames) = s.strip_suffix('f') {
+            (frames, CYCLES_PER_FRAME)
+        } else if let Some(cycles) = s.strip_suffix('c') {
+            (cycles, 1)
+        } else if let Some(seconds) = s.strip_suffix('s') {
+            (seconds, CYCLES_PER_SECOND)
+        } else {
+            (s, CYCLES_PER_FRAME)
+        };
+
+        let amount: u64 = amount.parse().with_context(|| {
+            anyhow!("Invalid timeout {s}, expected something like 600f, 5000000c or 10s")
+        })?;
+
+        Ok(Self {
+            cycles: amount
+                .checked_mul(multiplier)
+                .ok_or_else(|| anyhow!("Timeout {s} is too long"))?,
+        })
+    }
+}
+
+impl fmt::Display for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} c", self.cycles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_unit() {
+        assert_eq!(
+            "600f".parse::<Timeout>().unwrap().cycles(),
+            600 * CYCLES_PER_FRAME
+        );
+        assert_eq!(
+            "600".parse::<Timeout>().unwrap().cycles(),
+            600 * CYCLES_PER_FRAME
+        );
+        assert_eq!("1234c".parse::<Timeout>().unwrap().cycles(), 1234);
+        assert_eq!(
+            "10s".parse::<Timeout>().unwrap().cycles(),
+            10 * CYCLES_PER_SECOND
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_timeouts() {
+        assert!("".parse::<Timeout>().is_err());
+        assert!("10m".parse::<Timeout>().is_err());
+        assert!("-5s".parse::<Timeout>().is_err());
+    }
+
+    #[test]
+    fn expires_once_the_timeout_has_passed() {
+        let timeout: Timeout = "100c".parse().unwrap();
+
+        assert!(!timeout.has_expired(50, 150));
+        assert!(timeout.has_expired(50, 151));
+    }
+}
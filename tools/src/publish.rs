@@ -178,6 +178,7 @@ mod test {
                 "agb-macros",
                 "agb-fixnum",
                 "agb-hashmap",
+                "agb-replay",
             ]
         );
         Ok(())
@@ -1,5 +1,10 @@
 import { styled } from "styled-components";
-import { AddressInfo, AgbDebug, useAgbDebug } from "../useAgbDebug.hook";
+import {
+  AddressInfo,
+  AgbDebug,
+  CrashReport,
+  useAgbDebug,
+} from "../useAgbDebug.hook";
 import { ReactNode, useMemo, useState } from "react";
 
 const BacktraceListWrapper = styled.div`
@@ -49,7 +54,7 @@ function DebugBacktraceDecode({
   encodedBacktrace,
   debug,
 }: DebugBacktraceDecodeProps) {
-  const backtraceAddresses = useBacktraceData(debug, encodedBacktrace);
+  const crashReport = useBacktraceData(debug, encodedBacktrace);
   const [backtraceLocations, setBacktraceLocations] = useState<AddressInfo[][]>(
     []
   );
@@ -57,14 +62,16 @@ function DebugBacktraceDecode({
   const [backtraceLocationsError, setBacktraceLocationsError] =
     useState<string>("");
 
-  if (typeof backtraceAddresses === "string") {
+  if (typeof crashReport === "string") {
     return (
       <DebugError>
-        Something went wrong decoding the backtrace: {backtraceAddresses}
+        Something went wrong decoding the backtrace: {crashReport}
       </DebugError>
     );
   }
 
+  const backtraceAddresses = Array.from(crashReport.frames);
+
   return (
     <>
       <BacktraceListWrapper>
@@ -79,6 +86,7 @@ function DebugBacktraceDecode({
           ))}
         </BacktraceList>
       </BacktraceListWrapper>
+      <CrashDetails debug={debug} report={crashReport} />
       <p>
         If you add the elf file used to make the GBA file, or the GBA file
         itself if it was made with <NonWrapCode>agb-gbafix --debug</NonWrapCode>
@@ -211,11 +219,85 @@ async function loadLocations(debug: AgbDebug, addresses: number[], file: File) {
   return debugInfo;
 }
 
+function formatWord(value: number) {
+  return `0x${value.toString(16).padStart(8, "0")}`;
+}
+
+const RegisterTable = styled.table`
+  font-family: monospace;
+  border-spacing: 20px 0;
+`;
+
+function CrashDetails({
+  debug,
+  report,
+}: {
+  debug: AgbDebug;
+  report: CrashReport;
+}) {
+  const registers = Array.from(report.registers);
+  const stack = Array.from(report.stack);
+  const panicLocation = report.panic_location;
+
+  if (registers.length === 0) {
+    return null;
+  }
+
+  const registerNames = debug.register_names();
+
+  return (
+    <>
+      {panicLocation && (
+        <p>
+          Panicked at line {panicLocation.line} column {panicLocation.column}{" "}
+          (panic hash <code>{formatWord(panicLocation.hash)}</code>)
+        </p>
+      )}
+      <p>
+        The registers and stack are captured in agb&apos;s panic handler rather
+        than where the panic happened, with the frames of the code which
+        panicked above the stack shown here.
+      </p>
+      <RegisterTable>
+        <thead>
+          <tr>
+            <th>Register</th>
+            <th>Value</th>
+          </tr>
+        </thead>
+        <tbody>
+          {registers.map((value, idx) => (
+            <tr key={idx}>
+              <td>{registerNames[idx]}</td>
+              <td>{formatWord(value)}</td>
+            </tr>
+          ))}
+        </tbody>
+      </RegisterTable>
+      <RegisterTable>
+        <thead>
+          <tr>
+            <th>Stack</th>
+            <th>Value</th>
+          </tr>
+        </thead>
+        <tbody>
+          {stack.map((value, idx) => (
+            <tr key={idx}>
+              <td>sp+{idx * 4}</td>
+              <td>{formatWord(value)}</td>
+            </tr>
+          ))}
+        </tbody>
+      </RegisterTable>
+    </>
+  );
+}
+
 function useBacktraceData(debug: AgbDebug, trace: string) {
   return useMemo(() => {
     try {
-      const addresses = debug?.decode_backtrace(trace);
-      return addresses && Array.from(addresses);
+      return debug.decode_crash_report(trace);
     } catch (e: unknown) {
       return `${e}`;
     }
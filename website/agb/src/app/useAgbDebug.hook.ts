@@ -1,17 +1,20 @@
 import { useEffect, useState } from "react";
 import debugInit, {
-  decode_backtrace,
+  decode_crash_report,
+  register_names,
   DebugFile,
   InitOutput,
   AddressInfo,
+  CrashReport,
 } from "./vendor/backtrace/backtrace";
 
 let agbDebug: Promise<InitOutput> | undefined;
 
-export { AddressInfo };
+export { AddressInfo, CrashReport };
 
 export interface AgbDebug {
-  decode_backtrace: (backtrace: string) => Uint32Array;
+  decode_crash_report: (backtrace: string) => CrashReport;
+  register_names: () => string[];
   debug_file: (file: Uint8Array) => DebugFile;
 }
 
@@ -27,7 +30,8 @@ export function useAgbDebug(): AgbDebug | undefined {
       await agbDebug;
 
       setDebug({
-        decode_backtrace,
+        decode_crash_report,
+        register_names,
         debug_file: (file: Uint8Array) => new DebugFile(file),
       });
     })();
@@ -6,6 +6,47 @@ pub fn decode_backtrace(backtrace: &str) -> Result<Vec<u32>, JsError> {
     Ok(agb_debug::gwilym_decode(backtrace)?.collect())
 }
 
+#[wasm_bindgen(getter_with_clone)]
+pub struct CrashReport {
+    pub frames: Vec<u32>,
+    /// r0-r15 and then the CPSR, or empty for backtraces from before these were included
+    pub registers: Vec<u32>,
+    pub stack: Vec<u32>,
+    pub panic_location: Option<PanicLocation>,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct PanicLocation {
+    pub line: u32,
+    pub column: u32,
+    pub hash: u32,
+}
+
+#[wasm_bindgen]
+pub fn decode_crash_report(backtrace: &str) -> Result<CrashReport, JsError> {
+    let report = agb_debug::decode_crash_report(backtrace)?;
+
+    Ok(CrashReport {
+        frames: report.frames,
+        registers: report.registers,
+        stack: report.stack,
+        panic_location: report.panic.map(|panic| PanicLocation {
+            line: panic.line,
+            column: panic.column,
+            hash: panic.hash,
+        }),
+    })
+}
+
+#[wasm_bindgen]
+pub fn register_names() -> Vec<String> {
+    agb_debug::REGISTER_NAMES
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
 #[wasm_bindgen]
 pub struct DebugFile {
     dwarf: Addr2LineContext,